@@ -14,4 +14,12 @@ pub struct Args {
     /// Change Database path
     #[arg(short = 'D', long, default_value = "/tmp/rustysearch.db")]
     pub database_path: String,
+
+    /// Synonym file in the Solr/WordNet format
+    #[arg(short = 's', long, default_value = "")]
+    pub synonyms_path: String,
+
+    /// File the terms of successful queries are logged to, for spelling correction
+    #[arg(short = 'q', long, default_value = "/tmp/search.querylog")]
+    pub query_log_path: String,
 }
@@ -1,43 +1,1376 @@
-use actix_web::{web, HttpResponse, Responder};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use actix_web::{error, web, HttpResponse, Responder, ResponseError};
 use serde::{Deserialize, Serialize};
 
+use crate::search::cursor::Cursor;
+use crate::search::engine::{SearchEngine, DEFAULT_MAX_QUERY_TERMS};
+use crate::search::facets::FacetRequest;
+use crate::search::filter::Filter;
+use crate::search::function_score::FunctionScore;
+use crate::search::fusion::{fuse, Fusion};
+use crate::search::group::{group, GroupBy, DEFAULT_GROUP_SIZE};
+use crate::search::highlight::{Encoder, HighlightOptions};
+use crate::search::hnsw::{Metric, DEFAULT_NUM_CANDIDATES, MAX_NUM_CANDIDATES};
+use crate::search::ltr::{Feature, FeatureExtractor, RankingModel};
+use crate::search::percolator::PercolatorQuery;
+use crate::search::pit::{parse_keep_alive, MAX_OPEN_POINTS_IN_TIME};
+use crate::search::query::{Fuzziness, Query, QueryError};
+use crate::search::rescore::Rescore;
+use crate::search::rules::QueryRule;
+use crate::search::script::ScriptScore;
+use crate::search::sort::SortKey;
+use crate::search::synonyms::SynonymSet;
+use crate::search::template::SearchTemplate;
 use crate::types::app_state::AppStateWithSearchEngine;
+use crate::types::document::Document;
+use crate::types::field::{FieldInput, FieldValue};
+use crate::types::response::{
+    ClosePointInTimeResponse, ErrorResponse, ExplainResponse, HitGroup, IndexDocumentResponse,
+    NumberOfDocumentsResponse, PercolatorQueriesResponse, PercolatorQueryResponse,
+    PercolatorQueryResultResponse, PointInTimeResponse, QueryRuleResponse, QueryRuleResultResponse,
+    QueryRulesResponse, RankingModelResponse, RankingModelResultResponse, RankingModelsResponse,
+    SearchHit, SearchResponse, SearchTemplateResponse, SearchTemplateResultResponse,
+    SearchTemplatesResponse, SpellResponse, SuggestResponse, SynonymsResponse, VectorFieldResponse,
+};
 
-#[derive(Deserialize, Serialize)]
+#[derive(Default, Deserialize, Serialize)]
 pub struct AddDocumentRequest {
     pub url: String,
     pub content: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub fields: BTreeMap<String, FieldInput>,
+    #[serde(default)]
+    pub vectors: BTreeMap<String, Vec<f32>>,
+    #[serde(default)]
+    pub boost: Option<f64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct SpellRequest {
+    query: String,
+    #[serde(default)]
+    use_query_log: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct SynonymsRequest {
+    pub rules: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct SuggestRequest {
+    prefix: String,
+    #[serde(default = "default_size")]
+    size: usize,
+    #[serde(default)]
+    titles: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct OpenPointInTimeRequest {
+    #[serde(default = "default_keep_alive")]
+    keep_alive: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ClosePointInTimeRequest {
+    pub id: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct VectorFieldRequest {
+    dimension: usize,
+    #[serde(default)]
+    metric: Metric,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct KnnRequest {
+    field: String,
+    vector: Vec<f32>,
+    #[serde(default = "default_size")]
+    k: usize,
+    num_candidates: Option<usize>,
+    filter: Option<String>,
+    pit: Option<String>,
+    keep_alive: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct SearchRequest {
+    #[serde(flatten)]
+    params: QueryRequest,
+    function_score: Option<FunctionScore>,
+    script_score: Option<ScriptScore>,
+    rescore: Option<Rescore>,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+pub struct TemplateSearchRequest {
+    #[serde(default)]
+    pub params: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct SimilarRequest {
+    url: String,
+    #[serde(default)]
+    from: usize,
+    #[serde(default = "default_size")]
+    size: usize,
+    #[serde(default = "default_max_query_terms")]
+    max_query_terms: usize,
+    filter: Option<String>,
+    pit: Option<String>,
+    keep_alive: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ExplainRequest {
+    query: String,
+    url: String,
+    fuzziness: Option<String>,
+    max_expansions: Option<usize>,
+    pit: Option<String>,
+    keep_alive: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct HybridKnnRequest {
+    field: String,
+    vector: Vec<f32>,
+    num_candidates: Option<usize>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct HybridRequest {
+    query: String,
+    knn: HybridKnnRequest,
+    #[serde(default)]
+    fusion: Fusion,
+    #[serde(default = "default_window_size")]
+    window_size: usize,
+    #[serde(default)]
+    from: usize,
+    #[serde(default = "default_size")]
+    size: usize,
+    filter: Option<String>,
+    #[serde(default)]
+    explain: bool,
+    pit: Option<String>,
+    keep_alive: Option<String>,
 }
 
 #[derive(Deserialize, Serialize)]
 pub struct QueryRequest {
     query: String,
+    #[serde(default)]
+    from: usize,
+    #[serde(default = "default_size")]
+    size: usize,
+    fuzziness: Option<String>,
+    filter: Option<String>,
+    facets: Option<String>,
+    sort: Option<String>,
+    search_after: Option<String>,
+    pit: Option<String>,
+    keep_alive: Option<String>,
+    max_expansions: Option<usize>,
+    #[serde(default)]
+    use_query_log: bool,
+    #[serde(default)]
+    matched_terms: bool,
+    #[serde(default)]
+    highlight: bool,
+    #[serde(default)]
+    explain: bool,
+    #[serde(default)]
+    collapse_duplicates: bool,
+    group_by: Option<String>,
+    #[serde(default = "default_group_size")]
+    group_size: usize,
+    log_features: Option<String>,
+    pre_tag: Option<String>,
+    post_tag: Option<String>,
+    fragment_size: Option<usize>,
+    number_of_fragments: Option<usize>,
+    encoder: Option<Encoder>,
+}
+
+// Parses a query with the options shared by searching and explaining, so both score the same query
+fn parse_query(
+    query: &str,
+    fuzziness: Option<&str>,
+    max_expansions: Option<usize>,
+) -> Result<Query, QueryError> {
+    let mut query = Query::parse(query)?;
+    if let Some(max_expansions) = max_expansions {
+        query = query.with_max_expansions(max_expansions);
+    }
+    match fuzziness {
+        Some(fuzziness) => Ok(query.with_fuzziness(fuzziness.parse::<Fuzziness>()?)),
+        None => Ok(query),
+    }
+}
+
+impl QueryRequest {
+    fn parse_query(&self) -> Result<Query, QueryError> {
+        parse_query(&self.query, self.fuzziness.as_deref(), self.max_expansions)
+    }
+
+    fn highlight_options(&self) -> HighlightOptions {
+        let defaults = HighlightOptions::default();
+        HighlightOptions {
+            pre_tag: self.pre_tag.clone().unwrap_or(defaults.pre_tag),
+            post_tag: self.post_tag.clone().unwrap_or(defaults.post_tag),
+            fragment_size: self.fragment_size.unwrap_or(defaults.fragment_size),
+            number_of_fragments: self
+                .number_of_fragments
+                .unwrap_or(defaults.number_of_fragments),
+            encoder: self.encoder.unwrap_or(defaults.encoder),
+        }
+    }
+}
+
+fn default_size() -> usize {
+    10
 }
 
+fn default_group_size() -> usize {
+    DEFAULT_GROUP_SIZE
+}
+
+fn default_max_query_terms() -> usize {
+    DEFAULT_MAX_QUERY_TERMS
+}
+
+fn default_window_size() -> usize {
+    100
+}
+
+fn default_keep_alive() -> String {
+    String::from("1m")
+}
 
-pub async fn add_document_to_index(data: web::Data<AppStateWithSearchEngine>, req: web::Json<AddDocumentRequest>) -> impl Responder {
-    data.search_engine.lock().unwrap().index(&req.url, &req.content);
-    HttpResponse::Created().body("Document added to index!")
+/// Returns the configuration of query string extractors, answering malformed parameters with
+/// an `ErrorResponse` like all other errors.
+pub fn query_config() -> web::QueryConfig {
+    web::QueryConfig::default().error_handler(|err, _| {
+        let response = HttpResponse::build(err.status_code()).json(ErrorResponse::new(&format!(
+            "Invalid query parameters: {}",
+            err
+        )));
+        error::InternalError::from_response(err, response).into()
+    })
+}
+
+/// Returns the configuration of JSON body extractors, answering malformed bodies with an
+/// `ErrorResponse` like all other errors.
+pub fn json_config() -> web::JsonConfig {
+    web::JsonConfig::default().error_handler(|err, _| {
+        let response = HttpResponse::build(err.status_code()).json(ErrorResponse::new(&format!(
+            "Invalid request body: {}",
+            err
+        )));
+        error::InternalError::from_response(err, response).into()
+    })
+}
+
+// Returns the snapshot to search, so writers are not blocked while the results are built
+fn reader(
+    data: &AppStateWithSearchEngine,
+    pit: Option<&str>,
+    keep_alive: Option<Duration>,
+) -> Result<Arc<SearchEngine>, HttpResponse> {
+    match pit {
+        Some(id) => data.points_in_time.get(id, keep_alive).ok_or_else(|| {
+            let error = format!("Point in time '{}' does not exist or has expired", id);
+            HttpResponse::NotFound().json(ErrorResponse::new(&error))
+        }),
+        None => Ok(data.search_engine.lock().unwrap().snapshot()),
+    }
+}
+
+// Builds a hit with the fields, title and content of a document
+fn search_hit(search_engine: &SearchEngine, url: String, score: f64) -> SearchHit {
+    let mut fields: BTreeMap<String, serde_json::Value> = search_engine
+        .fields(&url)
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_json()))
+        .collect();
+    if let Some(title) = search_engine.title(&url) {
+        fields.insert(String::from("title"), title.into());
+    }
+    if let Some(content) = search_engine.document(&url) {
+        fields.insert(String::from("content"), content.into());
+    }
+    SearchHit {
+        url,
+        score,
+        fields,
+        matched_terms: None,
+        highlight: None,
+        sort: None,
+        explanation: None,
+        features: None,
+    }
+}
+
+pub async fn add_document_to_index(
+    data: web::Data<AppStateWithSearchEngine>,
+    req: web::Json<AddDocumentRequest>,
+) -> impl Responder {
+    let document = Document {
+        url: req.url.clone(),
+        content: req.content.clone(),
+        title: req.title.clone(),
+        fields: req
+            .fields
+            .iter()
+            .map(|(name, value)| (name.clone(), FieldValue::from(value.clone())))
+            .collect(),
+        vectors: req.vectors.clone(),
+        boost: req.boost,
+    };
+    if req
+        .boost
+        .is_some_and(|boost| !boost.is_finite() || boost < 0.0)
+    {
+        return HttpResponse::BadRequest()
+            .json(ErrorResponse::new("boost must be a non-negative number"));
+    }
+    let mut search_engine = data.search_engine.lock().unwrap();
+    if let Err(e) = search_engine.validate_vectors(&document) {
+        return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string()));
+    }
+    let created = search_engine.index_document(&document);
+    let duplicates = search_engine.near_duplicates(&req.url);
+    let percolator_matches = search_engine.percolate(&req.url);
+    drop(search_engine);
+
+    // Hooks run on the blocking thread pool, so slow ones delay neither the response nor other requests
+    if data.percolator_hook.is_some() && !percolator_matches.is_empty() {
+        let data = data.clone();
+        let url = req.url.clone();
+        let matches = percolator_matches.clone();
+        actix_web::rt::spawn(web::block(move || {
+            if let Some(hook) = &data.percolator_hook {
+                hook(&url, &matches);
+            }
+        }));
+    }
+    let response = IndexDocumentResponse {
+        url: req.url.clone(),
+        result: String::from(if created { "created" } else { "updated" }),
+        duplicates,
+        percolator_matches,
+    };
+    if created {
+        HttpResponse::Created().json(response)
+    } else {
+        HttpResponse::Ok().json(response)
+    }
 }
 
 pub async fn get_number_of_documents(data: web::Data<AppStateWithSearchEngine>) -> impl Responder {
     let number_of_documents = data.search_engine.lock().unwrap().number_of_documents();
-    HttpResponse::Ok().body(format!("Number of documents: {}", number_of_documents))
+    HttpResponse::Ok().json(NumberOfDocumentsResponse {
+        number_of_documents,
+    })
+}
+
+pub async fn search(
+    data: web::Data<AppStateWithSearchEngine>,
+    req: web::Query<QueryRequest>,
+) -> impl Responder {
+    run_search(&data, &req, None, None, None)
+}
+
+pub async fn search_with_body(
+    data: web::Data<AppStateWithSearchEngine>,
+    req: web::Json<SearchRequest>,
+) -> impl Responder {
+    run_search(
+        &data,
+        &req.params,
+        req.function_score.as_ref(),
+        req.script_score.as_ref(),
+        req.rescore.as_ref(),
+    )
 }
 
-pub async fn search(data: web::Data<AppStateWithSearchEngine>, req: web::Query<QueryRequest>) -> impl Responder {
+pub async fn search_with_template(
+    data: web::Data<AppStateWithSearchEngine>,
+    path: web::Path<String>,
+    req: web::Json<TemplateSearchRequest>,
+) -> impl Responder {
+    let template = data
+        .search_engine
+        .lock()
+        .unwrap()
+        .search_templates()
+        .get(path.as_str())
+        .cloned();
+    let Some(template) = template else {
+        return HttpResponse::NotFound().json(ErrorResponse::new("Search template not found"));
+    };
+    let body = match template.render(&req.params) {
+        Ok(body) => body,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    };
+    log::debug!("Rendered search template {}: {}", path.as_str(), body);
+
+    // The rendered body is checked like the body of `POST /search`
+    match serde_json::from_value::<SearchRequest>(body) {
+        Ok(req) => run_search(
+            &data,
+            &req.params,
+            req.function_score.as_ref(),
+            req.script_score.as_ref(),
+            req.rescore.as_ref(),
+        ),
+        Err(e) => HttpResponse::BadRequest().json(ErrorResponse::new(&format!(
+            "Invalid rendered template: {}",
+            e
+        ))),
+    }
+}
+
+fn run_search(
+    data: &AppStateWithSearchEngine,
+    req: &QueryRequest,
+    function_score: Option<&FunctionScore>,
+    script_score: Option<&ScriptScore>,
+    rescore: Option<&Rescore>,
+) -> HttpResponse {
     if req.query.is_empty() {
-        return HttpResponse::BadRequest().body("Query is empty");
+        return HttpResponse::BadRequest().json(ErrorResponse::new("Query is empty"));
     }
 
     // Get the query string from query parameters
     log::debug!("Searching for: {}", &req.query);
 
-    let results = data.search_engine.lock().unwrap().search(&req.query);
-    HttpResponse::Ok().json(results)
+    let query = match req.parse_query() {
+        Ok(query) => query,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    };
+    let filters = match Filter::parse_all(req.filter.as_deref().unwrap_or_default()) {
+        Ok(filters) => filters,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    };
+    let facet_requests = match FacetRequest::parse_all(req.facets.as_deref().unwrap_or_default()) {
+        Ok(facet_requests) => facet_requests,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    };
+    let sort_keys = match SortKey::parse_all(req.sort.as_deref().unwrap_or_default()) {
+        Ok(sort_keys) if sort_keys.is_empty() => vec![SortKey::score()],
+        Ok(sort_keys) => sort_keys,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    };
+    let logged_features = match Feature::parse_all(req.log_features.as_deref().unwrap_or_default())
+    {
+        Ok(logged_features) => logged_features,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    };
+    let group_by = GroupBy::parse(req.group_by.as_deref().unwrap_or_default());
+    let search_after = match &req.search_after {
+        Some(_) if group_by.is_some() => {
+            return HttpResponse::BadRequest().json(ErrorResponse::new(
+                "search_after cannot be combined with group_by",
+            ));
+        }
+        Some(_) if req.from > 0 => {
+            return HttpResponse::BadRequest().json(ErrorResponse::new(
+                "search_after cannot be combined with from",
+            ));
+        }
+        Some(token) => match Cursor::decode(token, sort_keys.len()) {
+            Ok(cursor) => Some(cursor),
+            Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+        },
+        None => None,
+    };
+    let keep_alive = match req.keep_alive.as_deref().map(parse_keep_alive).transpose() {
+        Ok(keep_alive) => keep_alive,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    };
+    if let Some(Err(e)) = function_score.map(FunctionScore::validate) {
+        return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string()));
+    }
+    // Scripts are compiled once and then run for every hit
+    let script = match script_score.map(ScriptScore::compile).transpose() {
+        Ok(script) => script,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    };
+    if let Some(rescore) = rescore {
+        if req.sort.is_some() || search_after.is_some() {
+            return HttpResponse::BadRequest().json(ErrorResponse::new(
+                "rescore cannot be combined with sort or search_after",
+            ));
+        }
+        if let Err(e) = rescore.validate() {
+            return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string()));
+        }
+    }
+
+    let start = Instant::now();
+    let search_engine = match reader(data, req.pit.as_deref(), keep_alive) {
+        Ok(search_engine) => search_engine,
+        Err(response) => return response,
+    };
+    if let Err(e) = search_engine.validate_filters(&filters) {
+        return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string()));
+    }
+    if let Some(Err(e)) = rescore.map(|rescore| rescore.check_models(&search_engine)) {
+        return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string()));
+    }
+    let mut results = match search_engine.search_query(&query) {
+        Ok(results) => results,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    };
+
+    // Offer a corrected query when nothing matched, otherwise learn from the query
+    let suggestion = if results.is_empty() {
+        let query_log = req.use_query_log.then(|| data.query_log.counts());
+        search_engine.suggest_correction(&req.query, query_log.as_deref())
+    } else {
+        data.query_log.record(&query);
+        None
+    };
+
+    // Filters narrow down the matches without changing their scores
+    if !filters.is_empty() {
+        let candidates = search_engine.filter(&filters);
+        results.retain(|url, _| candidates.contains(url));
+    }
+
+    // Function scores replace the text scores before anything is ranked or counted
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as i64);
+    if let Some(function_score) = function_score {
+        for (url, score) in results.iter_mut() {
+            *score = function_score.score(&search_engine, url, *score, now);
+        }
+    }
+    if let Some(script) = &script {
+        if let Err(e) = script.score_all(&search_engine, &mut results, now) {
+            return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string()));
+        }
+    }
+
+    // Query rules curate the final scores, so pinned hits pass the filters and keep their place
+    let outcome = search_engine.evaluate_query_rules(&req.query);
+    outcome.apply(&mut results, |url| search_engine.document(url).is_some());
+
+    // Only the best hit of every cluster of near-duplicates is kept
+    if req.collapse_duplicates {
+        search_engine.collapse_duplicates(&mut results);
+    }
+
+    // Facets count the full match set, not only the returned page
+    let facets = match search_engine.facets(results.keys(), &facet_requests) {
+        Ok(facets) => facets
+            .into_iter()
+            .map(|(field, facet)| (field, facet.into()))
+            .collect(),
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    };
+
+    // Rank by score unless a sort is given, ties are broken by URL to keep the order stable
+    let total = results.len();
+    let mut max_score = results.values().copied().reduce(f64::max);
+    let mut ranked = search_engine.sort_results(results, &sort_keys, search_after.as_ref());
+
+    // Features are logged with the scores of the first pass, the models are trained on those
+    let extractor =
+        (!logged_features.is_empty()).then(|| FeatureExtractor::new(&search_engine, &query, now));
+    let first_pass: HashMap<String, f64> = match extractor {
+        Some(_) => ranked.iter().cloned().collect(),
+        None => HashMap::new(),
+    };
+
+    // The second pass only rescores the top window of the first one
+    let mut rescored = HashSet::new();
+    if let Some(rescore) = rescore {
+        ranked = rescore.rescore(&search_engine, &query, ranked, now);
+        rescored = ranked
+            .iter()
+            .take(rescore.window_size)
+            .map(|(url, _)| url.clone())
+            .collect();
+        max_score = ranked.iter().map(|(_, score)| *score).reduce(f64::max);
+    }
+    // Pinned hits lead the first page and are left out of the pages after a cursor
+    let ranked = outcome.pin(ranked, search_after.is_none());
+    let remaining = ranked.len();
+
+    let highlight_options = req.highlight_options();
+    let build_hit = |(url, score): (String, f64)| {
+        let matched_terms = req
+            .matched_terms
+            .then(|| search_engine.matched_terms(&query, &url));
+        let highlight = req
+            .highlight
+            .then(|| search_engine.highlight(&query, &url, &highlight_options));
+        let sort = req.sort.is_some().then(|| {
+            search_engine
+                .sort_values(&url, score, &sort_keys)
+                .iter()
+                .map(|value| {
+                    value
+                        .as_ref()
+                        .map_or(serde_json::Value::Null, |v| v.to_json())
+                })
+                .collect()
+        });
+        // The query already matched, so its expansion cannot fail here
+        let explanation = req
+            .explain
+            .then(|| {
+                let score = search_engine
+                    .explain(&query, &url)
+                    .ok()
+                    .flatten()
+                    .map(|text| match function_score {
+                        Some(function_score) => {
+                            function_score.explain(&search_engine, &url, text, now)
+                        }
+                        None => text,
+                    })
+                    .map(|score| match &script {
+                        Some(script) => script.explain(&search_engine, &url, score, now),
+                        None => score,
+                    });
+                outcome.explain(&url, score)
+            })
+            .flatten()
+            .map(|original| match rescore {
+                Some(rescore) if rescored.contains(&url) => {
+                    rescore.explain(&search_engine, &query, &url, original, now)
+                }
+                _ => original,
+            });
+        let features = extractor.as_ref().map(|extractor| {
+            let score = first_pass.get(&url).copied().unwrap_or(score);
+            extractor.extract(&logged_features, &url, score)
+        });
+        SearchHit {
+            matched_terms,
+            highlight,
+            sort,
+            explanation,
+            features,
+            ..search_hit(&search_engine, url, score)
+        }
+    };
+
+    // Grouped results page through the groups, each showing its best hits
+    if let Some(group_by) = group_by {
+        let groups = group(
+            ranked,
+            |url| search_engine.group_key(url, &group_by),
+            req.group_size,
+        );
+        let total_groups = groups.len();
+        let groups = groups
+            .into_iter()
+            .skip(req.from)
+            .take(req.size)
+            .map(|group| HitGroup {
+                key: group.key,
+                count: group.count,
+                hits: group.hits.into_iter().map(build_hit).collect(),
+            })
+            .collect();
+        return HttpResponse::Ok().json(SearchResponse {
+            took_ms: start.elapsed().as_millis() as u64,
+            total,
+            max_score,
+            hits: Vec::new(),
+            suggestion,
+            facets,
+            next_cursor: None,
+            pit_id: req.pit.clone(),
+            groups: Some(groups),
+            total_groups: Some(total_groups),
+            redirect: outcome.redirect,
+        });
+    }
+
+    let page: Vec<(String, f64)> = ranked.into_iter().skip(req.from).take(req.size).collect();
+
+    // Hand out a cursor to the next page as long as more hits follow, pinned hits have no place in the sort order
+    let next_cursor = page
+        .iter()
+        .rfind(|(url, _)| !outcome.pinned.contains(url))
+        .filter(|_| req.from + page.len() < remaining)
+        .map(|(url, score)| {
+            Cursor {
+                values: search_engine.sort_values(url, *score, &sort_keys),
+                url: url.clone(),
+            }
+            .encode()
+        });
+
+    let hits = page.into_iter().map(build_hit).collect();
+
+    HttpResponse::Ok().json(SearchResponse {
+        took_ms: start.elapsed().as_millis() as u64,
+        total,
+        max_score,
+        hits,
+        suggestion,
+        facets,
+        next_cursor,
+        pit_id: req.pit.clone(),
+        groups: None,
+        total_groups: None,
+        redirect: outcome.redirect,
+    })
+}
+
+pub async fn similar(
+    data: web::Data<AppStateWithSearchEngine>,
+    req: web::Query<SimilarRequest>,
+) -> impl Responder {
+    let filters = match Filter::parse_all(req.filter.as_deref().unwrap_or_default()) {
+        Ok(filters) => filters,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    };
+    let keep_alive = match req.keep_alive.as_deref().map(parse_keep_alive).transpose() {
+        Ok(keep_alive) => keep_alive,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    };
+
+    let start = Instant::now();
+    let search_engine = match reader(&data, req.pit.as_deref(), keep_alive) {
+        Ok(search_engine) => search_engine,
+        Err(response) => return response,
+    };
+    if let Err(e) = search_engine.validate_filters(&filters) {
+        return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string()));
+    }
+    let mut results = match search_engine.similar(&req.url, req.max_query_terms) {
+        Some(results) => results,
+        None => return HttpResponse::NotFound().json(ErrorResponse::new("Document not found")),
+    };
+    if !filters.is_empty() {
+        let candidates = search_engine.filter(&filters);
+        results.retain(|url, _| candidates.contains(url));
+    }
+
+    let total = results.len();
+    let max_score = results.values().copied().reduce(f64::max);
+    let hits = search_engine
+        .sort_results(results, &[SortKey::score()], None)
+        .into_iter()
+        .skip(req.from)
+        .take(req.size)
+        .map(|(url, score)| search_hit(&search_engine, url, score))
+        .collect();
+
+    HttpResponse::Ok().json(SearchResponse {
+        took_ms: start.elapsed().as_millis() as u64,
+        total,
+        max_score,
+        hits,
+        suggestion: None,
+        facets: BTreeMap::new(),
+        next_cursor: None,
+        pit_id: req.pit.clone(),
+        groups: None,
+        total_groups: None,
+        redirect: None,
+    })
+}
+
+pub async fn explain(
+    data: web::Data<AppStateWithSearchEngine>,
+    req: web::Query<ExplainRequest>,
+) -> impl Responder {
+    if req.query.is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse::new("Query is empty"));
+    }
+    let query = match parse_query(&req.query, req.fuzziness.as_deref(), req.max_expansions) {
+        Ok(query) => query,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    };
+    let keep_alive = match req.keep_alive.as_deref().map(parse_keep_alive).transpose() {
+        Ok(keep_alive) => keep_alive,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    };
+
+    let search_engine = match reader(&data, req.pit.as_deref(), keep_alive) {
+        Ok(search_engine) => search_engine,
+        Err(response) => return response,
+    };
+    if search_engine.document(&req.url).is_none() {
+        return HttpResponse::NotFound().json(ErrorResponse::new("Document not found"));
+    }
+    match search_engine.explain(&query, &req.url) {
+        Ok(explanation) => HttpResponse::Ok().json(ExplainResponse {
+            url: req.url.clone(),
+            matched: explanation.is_some(),
+            explanation,
+        }),
+        Err(e) => HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    }
+}
+
+pub async fn create_vector_field(
+    data: web::Data<AppStateWithSearchEngine>,
+    path: web::Path<String>,
+    req: web::Json<VectorFieldRequest>,
+) -> impl Responder {
+    let field = path.into_inner();
+    let result =
+        data.search_engine
+            .lock()
+            .unwrap()
+            .create_vector_field(&field, req.dimension, req.metric);
+    match result {
+        Ok(()) => HttpResponse::Ok().json(VectorFieldResponse {
+            field,
+            dimension: req.dimension,
+            metric: req.metric,
+        }),
+        Err(e) => HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    }
+}
+
+pub async fn knn(
+    data: web::Data<AppStateWithSearchEngine>,
+    req: web::Json<KnnRequest>,
+) -> impl Responder {
+    let num_candidates = req
+        .num_candidates
+        .unwrap_or(DEFAULT_NUM_CANDIDATES)
+        .max(req.k);
+    if num_candidates > MAX_NUM_CANDIDATES {
+        let error = format!(
+            "k and num_candidates must not exceed {}",
+            MAX_NUM_CANDIDATES
+        );
+        return HttpResponse::BadRequest().json(ErrorResponse::new(&error));
+    }
+    let filters = match Filter::parse_all(req.filter.as_deref().unwrap_or_default()) {
+        Ok(filters) => filters,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    };
+    let keep_alive = match req.keep_alive.as_deref().map(parse_keep_alive).transpose() {
+        Ok(keep_alive) => keep_alive,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    };
+
+    let start = Instant::now();
+    let search_engine = match reader(&data, req.pit.as_deref(), keep_alive) {
+        Ok(search_engine) => search_engine,
+        Err(response) => return response,
+    };
+    if let Err(e) = search_engine.validate_filters(&filters) {
+        return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string()));
+    }
+    let neighbors =
+        match search_engine.knn(&req.field, &req.vector, req.k, num_candidates, &filters) {
+            Ok(neighbors) => neighbors,
+            Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+        };
+
+    let max_score = neighbors.first().map(|(_, score)| *score);
+    let hits: Vec<SearchHit> = neighbors
+        .into_iter()
+        .map(|(url, score)| search_hit(&search_engine, url, score))
+        .collect();
+    HttpResponse::Ok().json(SearchResponse {
+        took_ms: start.elapsed().as_millis() as u64,
+        total: hits.len(),
+        max_score,
+        hits,
+        suggestion: None,
+        facets: BTreeMap::new(),
+        next_cursor: None,
+        pit_id: req.pit.clone(),
+        groups: None,
+        total_groups: None,
+        redirect: None,
+    })
+}
+
+pub async fn hybrid_search(
+    data: web::Data<AppStateWithSearchEngine>,
+    req: web::Json<HybridRequest>,
+) -> impl Responder {
+    if req.query.is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse::new("Query is empty"));
+    }
+    let num_candidates = req
+        .knn
+        .num_candidates
+        .unwrap_or(DEFAULT_NUM_CANDIDATES)
+        .max(req.window_size);
+    if num_candidates > MAX_NUM_CANDIDATES {
+        let error = format!(
+            "window_size and num_candidates must not exceed {}",
+            MAX_NUM_CANDIDATES
+        );
+        return HttpResponse::BadRequest().json(ErrorResponse::new(&error));
+    }
+    let query = match Query::parse(&req.query) {
+        Ok(query) => query,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    };
+    let filters = match Filter::parse_all(req.filter.as_deref().unwrap_or_default()) {
+        Ok(filters) => filters,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    };
+    let keep_alive = match req.keep_alive.as_deref().map(parse_keep_alive).transpose() {
+        Ok(keep_alive) => keep_alive,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    };
+    if let Err(e) = req.fusion.validate() {
+        return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string()));
+    }
+
+    let start = Instant::now();
+    let search_engine = match reader(&data, req.pit.as_deref(), keep_alive) {
+        Ok(search_engine) => search_engine,
+        Err(response) => return response,
+    };
+    if let Err(e) = search_engine.validate_filters(&filters) {
+        return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string()));
+    }
+
+    // Both retrievers contribute their best `window_size` hits passing the filters
+    let mut results = match search_engine.search_query(&query) {
+        Ok(results) => results,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    };
+    if !filters.is_empty() {
+        let candidates = search_engine.filter(&filters);
+        results.retain(|url, _| candidates.contains(url));
+    }
+    let mut lexical = search_engine.sort_results(results, &[SortKey::score()], None);
+    lexical.truncate(req.window_size);
+    let vector = match search_engine.knn(
+        &req.knn.field,
+        &req.knn.vector,
+        req.window_size,
+        num_candidates,
+        &filters,
+    ) {
+        Ok(vector) => vector,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    };
+
+    let fused = fuse(&lexical, &vector, &req.fusion);
+    let total = fused.len();
+    let max_score = fused.first().map(|(_, score, _)| *score);
+    let hits = fused
+        .into_iter()
+        .skip(req.from)
+        .take(req.size)
+        .map(|(url, score, explanation)| SearchHit {
+            explanation: req.explain.then_some(explanation),
+            ..search_hit(&search_engine, url, score)
+        })
+        .collect();
+
+    HttpResponse::Ok().json(SearchResponse {
+        took_ms: start.elapsed().as_millis() as u64,
+        total,
+        max_score,
+        hits,
+        suggestion: None,
+        facets: BTreeMap::new(),
+        next_cursor: None,
+        pit_id: req.pit.clone(),
+        groups: None,
+        total_groups: None,
+        redirect: None,
+    })
+}
+
+pub async fn open_point_in_time(
+    data: web::Data<AppStateWithSearchEngine>,
+    req: web::Query<OpenPointInTimeRequest>,
+) -> impl Responder {
+    let keep_alive = match parse_keep_alive(&req.keep_alive) {
+        Ok(keep_alive) => keep_alive,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    };
+
+    let snapshot = data.search_engine.lock().unwrap().snapshot();
+    match data.points_in_time.open(snapshot, keep_alive) {
+        Some(id) => HttpResponse::Ok().json(PointInTimeResponse {
+            id,
+            keep_alive: req.keep_alive.clone(),
+        }),
+        None => {
+            let error = format!(
+                "Too many open points in time, the maximum is {}",
+                MAX_OPEN_POINTS_IN_TIME
+            );
+            HttpResponse::TooManyRequests().json(ErrorResponse::new(&error))
+        }
+    }
+}
+
+pub async fn close_point_in_time(
+    data: web::Data<AppStateWithSearchEngine>,
+    req: web::Json<ClosePointInTimeRequest>,
+) -> impl Responder {
+    if !data.points_in_time.close(&req.id) {
+        let error = format!("Point in time '{}' does not exist or has expired", req.id);
+        return HttpResponse::NotFound().json(ErrorResponse::new(&error));
+    }
+    HttpResponse::Ok().json(ClosePointInTimeResponse { succeeded: true })
+}
+
+pub async fn suggest(
+    data: web::Data<AppStateWithSearchEngine>,
+    req: web::Query<SuggestRequest>,
+) -> impl Responder {
+    if req.prefix.is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse::new("Prefix is empty"));
+    }
+
+    let start = Instant::now();
+    let suggestions = data
+        .search_engine
+        .lock()
+        .unwrap()
+        .suggest(&req.prefix, req.size, req.titles);
+
+    HttpResponse::Ok().json(SuggestResponse {
+        took_ms: start.elapsed().as_millis() as u64,
+        suggestions,
+    })
+}
+
+pub async fn spell(
+    data: web::Data<AppStateWithSearchEngine>,
+    req: web::Query<SpellRequest>,
+) -> impl Responder {
+    if req.query.is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse::new("Query is empty"));
+    }
+
+    let search_engine = data.search_engine.lock().unwrap().snapshot();
+    let query_log = req.use_query_log.then(|| data.query_log.counts());
+    let suggestion = search_engine.suggest_correction(&req.query, query_log.as_deref());
+
+    HttpResponse::Ok().json(SpellResponse {
+        query: req.query.clone(),
+        suggestion,
+    })
+}
+
+pub async fn get_synonyms(data: web::Data<AppStateWithSearchEngine>) -> impl Responder {
+    let rules = data
+        .search_engine
+        .lock()
+        .unwrap()
+        .synonyms()
+        .rules()
+        .to_vec();
+    HttpResponse::Ok().json(SynonymsResponse { rules })
+}
+
+pub async fn update_synonyms(
+    data: web::Data<AppStateWithSearchEngine>,
+    req: web::Json<SynonymsRequest>,
+) -> impl Responder {
+    let synonyms = match SynonymSet::from_rules(&req.rules) {
+        Ok(synonyms) => synonyms,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    };
+    let rules = synonyms.rules().to_vec();
+    data.search_engine.lock().unwrap().set_synonyms(synonyms);
+    HttpResponse::Ok().json(SynonymsResponse { rules })
+}
+
+pub async fn get_query_rules(data: web::Data<AppStateWithSearchEngine>) -> impl Responder {
+    let rules = data.search_engine.lock().unwrap().query_rules().clone();
+    HttpResponse::Ok().json(QueryRulesResponse { rules })
+}
+
+pub async fn get_query_rule(
+    data: web::Data<AppStateWithSearchEngine>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let rule = data
+        .search_engine
+        .lock()
+        .unwrap()
+        .query_rules()
+        .get(&id)
+        .cloned();
+    match rule {
+        Some(rule) => HttpResponse::Ok().json(QueryRuleResponse { id, rule }),
+        None => HttpResponse::NotFound().json(ErrorResponse::new("Query rule not found")),
+    }
+}
+
+pub async fn put_query_rule(
+    data: web::Data<AppStateWithSearchEngine>,
+    path: web::Path<String>,
+    req: web::Json<QueryRule>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let result = data
+        .search_engine
+        .lock()
+        .unwrap()
+        .put_query_rule(&id, req.into_inner());
+    match result {
+        Ok(true) => HttpResponse::Created().json(QueryRuleResultResponse {
+            id,
+            result: String::from("created"),
+        }),
+        Ok(false) => HttpResponse::Ok().json(QueryRuleResultResponse {
+            id,
+            result: String::from("updated"),
+        }),
+        Err(e) => HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    }
+}
+
+pub async fn delete_query_rule(
+    data: web::Data<AppStateWithSearchEngine>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let id = path.into_inner();
+    if data.search_engine.lock().unwrap().delete_query_rule(&id) {
+        HttpResponse::Ok().json(QueryRuleResultResponse {
+            id,
+            result: String::from("deleted"),
+        })
+    } else {
+        HttpResponse::NotFound().json(ErrorResponse::new("Query rule not found"))
+    }
+}
+
+pub async fn get_ranking_models(data: web::Data<AppStateWithSearchEngine>) -> impl Responder {
+    let models = data.search_engine.lock().unwrap().ranking_models().clone();
+    HttpResponse::Ok().json(RankingModelsResponse { models })
+}
+
+pub async fn get_ranking_model(
+    data: web::Data<AppStateWithSearchEngine>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let name = path.into_inner();
+    let model = data
+        .search_engine
+        .lock()
+        .unwrap()
+        .ranking_model(&name)
+        .cloned();
+    match model {
+        Some(model) => HttpResponse::Ok().json(RankingModelResponse { name, model }),
+        None => HttpResponse::NotFound().json(ErrorResponse::new("Ranking model not found")),
+    }
+}
+
+pub async fn put_ranking_model(
+    data: web::Data<AppStateWithSearchEngine>,
+    path: web::Path<String>,
+    req: web::Json<RankingModel>,
+) -> impl Responder {
+    let name = path.into_inner();
+    let result = data
+        .search_engine
+        .lock()
+        .unwrap()
+        .put_ranking_model(&name, req.into_inner());
+    match result {
+        Ok(true) => HttpResponse::Created().json(RankingModelResultResponse {
+            name,
+            result: String::from("created"),
+        }),
+        Ok(false) => HttpResponse::Ok().json(RankingModelResultResponse {
+            name,
+            result: String::from("updated"),
+        }),
+        Err(e) => HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    }
+}
+
+pub async fn delete_ranking_model(
+    data: web::Data<AppStateWithSearchEngine>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let name = path.into_inner();
+    if data
+        .search_engine
+        .lock()
+        .unwrap()
+        .delete_ranking_model(&name)
+    {
+        HttpResponse::Ok().json(RankingModelResultResponse {
+            name,
+            result: String::from("deleted"),
+        })
+    } else {
+        HttpResponse::NotFound().json(ErrorResponse::new("Ranking model not found"))
+    }
+}
+
+pub async fn get_search_templates(data: web::Data<AppStateWithSearchEngine>) -> impl Responder {
+    let templates = data
+        .search_engine
+        .lock()
+        .unwrap()
+        .search_templates()
+        .clone();
+    HttpResponse::Ok().json(SearchTemplatesResponse { templates })
+}
+
+pub async fn get_search_template(
+    data: web::Data<AppStateWithSearchEngine>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let template = data
+        .search_engine
+        .lock()
+        .unwrap()
+        .search_templates()
+        .get(&id)
+        .cloned();
+    match template {
+        Some(template) => HttpResponse::Ok().json(SearchTemplateResponse { id, template }),
+        None => HttpResponse::NotFound().json(ErrorResponse::new("Search template not found")),
+    }
+}
+
+pub async fn put_search_template(
+    data: web::Data<AppStateWithSearchEngine>,
+    path: web::Path<String>,
+    req: web::Json<SearchTemplate>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let result = data
+        .search_engine
+        .lock()
+        .unwrap()
+        .put_search_template(&id, req.into_inner());
+    match result {
+        Ok(true) => HttpResponse::Created().json(SearchTemplateResultResponse {
+            id,
+            result: String::from("created"),
+        }),
+        Ok(false) => HttpResponse::Ok().json(SearchTemplateResultResponse {
+            id,
+            result: String::from("updated"),
+        }),
+        Err(e) => HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    }
+}
+
+pub async fn delete_search_template(
+    data: web::Data<AppStateWithSearchEngine>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let id = path.into_inner();
+    if data
+        .search_engine
+        .lock()
+        .unwrap()
+        .delete_search_template(&id)
+    {
+        HttpResponse::Ok().json(SearchTemplateResultResponse {
+            id,
+            result: String::from("deleted"),
+        })
+    } else {
+        HttpResponse::NotFound().json(ErrorResponse::new("Search template not found"))
+    }
+}
+
+pub async fn get_percolator_queries(data: web::Data<AppStateWithSearchEngine>) -> impl Responder {
+    let queries = data
+        .search_engine
+        .lock()
+        .unwrap()
+        .percolator_queries()
+        .clone();
+    HttpResponse::Ok().json(PercolatorQueriesResponse { queries })
+}
+
+pub async fn get_percolator_query(
+    data: web::Data<AppStateWithSearchEngine>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let query = data
+        .search_engine
+        .lock()
+        .unwrap()
+        .percolator_queries()
+        .get(&id)
+        .cloned();
+    match query {
+        Some(query) => HttpResponse::Ok().json(PercolatorQueryResponse { id, query }),
+        None => HttpResponse::NotFound().json(ErrorResponse::new("Percolator query not found")),
+    }
+}
+
+pub async fn put_percolator_query(
+    data: web::Data<AppStateWithSearchEngine>,
+    path: web::Path<String>,
+    req: web::Json<PercolatorQuery>,
+) -> impl Responder {
+    let id = path.into_inner();
+    if req.query.trim().is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse::new("Query is empty"));
+    }
+    let result = data
+        .search_engine
+        .lock()
+        .unwrap()
+        .put_percolator_query(&id, req.into_inner());
+    match result {
+        Ok(true) => HttpResponse::Created().json(PercolatorQueryResultResponse {
+            id,
+            result: String::from("created"),
+        }),
+        Ok(false) => HttpResponse::Ok().json(PercolatorQueryResultResponse {
+            id,
+            result: String::from("updated"),
+        }),
+        Err(e) => HttpResponse::BadRequest().json(ErrorResponse::new(&e.to_string())),
+    }
+}
+
+pub async fn delete_percolator_query(
+    data: web::Data<AppStateWithSearchEngine>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let id = path.into_inner();
+    if data
+        .search_engine
+        .lock()
+        .unwrap()
+        .delete_percolator_query(&id)
+    {
+        HttpResponse::Ok().json(PercolatorQueryResultResponse {
+            id,
+            result: String::from("deleted"),
+        })
+    } else {
+        HttpResponse::NotFound().json(ErrorResponse::new("Percolator query not found"))
+    }
 }
 
 pub async fn debug_index(data: web::Data<AppStateWithSearchEngine>) -> impl Responder {
     data.search_engine.lock().unwrap().debug_index();
     HttpResponse::Ok().json("Index debugged!")
-}
\ No newline at end of file
+}
@@ -1,4 +1,4 @@
-use std::sync::Mutex;
+use std::{fs, io};
 
 use actix_web::{middleware::Logger, web, App, HttpServer};
 use clap::Parser;
@@ -7,7 +7,7 @@ use env_logger::Env;
 use rustysearch::{
     cmd::arguments::Args,
     handlers::{hello, search},
-    search::engine::SearchEngine,
+    search::{engine::SearchEngine, query_log::QueryLog, synonyms::SynonymSet},
     types::app_state::AppStateWithSearchEngine,
 };
 
@@ -24,17 +24,35 @@ async fn main() -> std::io::Result<()> {
     }
 
     // Initialize the search engine
-    let search_engine = SearchEngine::new(1.5, 0.75);
+    // Refuse to start on an unreadable index, the first write would replace it
+    let mut search_engine =
+        SearchEngine::open(1.5, 0.75).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
 
-    // Wrap the search engine in a Mutex and then in an AppState
-    let app_state = web::Data::new(AppStateWithSearchEngine {
-        search_engine: Mutex::new(search_engine.clone()),
-    });
+    // Load synonyms from file, they replace the synonyms stored with the index
+    if !args.synonyms_path.is_empty() {
+        let synonyms = SynonymSet::parse(&fs::read_to_string(&args.synonyms_path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        search_engine.set_synonyms(synonyms);
+    }
+
+    // Wrap the search engine in a Mutex and then in an AppState with the persisted query log,
+    // reporting percolator matches in the log
+    let app_state = web::Data::new(
+        AppStateWithSearchEngine::new(search_engine.clone())
+            .with_query_log(QueryLog::open(&args.query_log_path)?)
+            .with_percolator_hook(|url, ids| {
+                log::info!("Document {} matches percolator queries {:?}", url, ids)
+            }),
+    );
+    let shutdown_state = app_state.clone();
 
     HttpServer::new(move || {
         App::new()
             // Inject the search engine into the application state
             .app_data(app_state.clone())
+            // Answer malformed requests with the same JSON errors as the handlers
+            .app_data(search::query_config())
+            .app_data(search::json_config())
             // enable logger
             .wrap(Logger::default())
             // Hello Routes
@@ -48,10 +66,88 @@ async fn main() -> std::io::Result<()> {
                 "/search/index/number_of_documents",
                 web::get().to(search::get_number_of_documents),
             )
+            .route("/search/suggest", web::get().to(search::suggest))
+            .route("/search/spell", web::get().to(search::spell))
+            .route("/search/synonyms", web::get().to(search::get_synonyms))
+            .route("/search/synonyms", web::put().to(search::update_synonyms))
+            .route(
+                "/search/vectors/{field}",
+                web::put().to(search::create_vector_field),
+            )
+            .route("/search/similar", web::get().to(search::similar))
+            .route("/search/rules", web::get().to(search::get_query_rules))
+            .route("/search/rules/{id}", web::get().to(search::get_query_rule))
+            .route("/search/rules/{id}", web::put().to(search::put_query_rule))
+            .route(
+                "/search/rules/{id}",
+                web::delete().to(search::delete_query_rule),
+            )
+            .route(
+                "/search/template/{id}",
+                web::post().to(search::search_with_template),
+            )
+            .route(
+                "/search/templates",
+                web::get().to(search::get_search_templates),
+            )
+            .route(
+                "/search/templates/{id}",
+                web::get().to(search::get_search_template),
+            )
+            .route(
+                "/search/templates/{id}",
+                web::put().to(search::put_search_template),
+            )
+            .route(
+                "/search/templates/{id}",
+                web::delete().to(search::delete_search_template),
+            )
+            .route(
+                "/search/percolator",
+                web::get().to(search::get_percolator_queries),
+            )
+            .route(
+                "/search/percolator/{id}",
+                web::get().to(search::get_percolator_query),
+            )
+            .route(
+                "/search/percolator/{id}",
+                web::put().to(search::put_percolator_query),
+            )
+            .route(
+                "/search/percolator/{id}",
+                web::delete().to(search::delete_percolator_query),
+            )
+            .route("/search/models", web::get().to(search::get_ranking_models))
+            .route(
+                "/search/models/{name}",
+                web::get().to(search::get_ranking_model),
+            )
+            .route(
+                "/search/models/{name}",
+                web::put().to(search::put_ranking_model),
+            )
+            .route(
+                "/search/models/{name}",
+                web::delete().to(search::delete_ranking_model),
+            )
+            .route("/search/explain", web::get().to(search::explain))
+            .route("/search/knn", web::post().to(search::knn))
+            .route("/search/hybrid", web::post().to(search::hybrid_search))
+            .route("/search/_pit", web::post().to(search::open_point_in_time))
+            .route(
+                "/search/_pit",
+                web::delete().to(search::close_point_in_time),
+            )
             .route("/search", web::get().to(search::search))
+            .route("/search", web::post().to(search::search_with_body))
             .route("/search/debug", web::get().to(search::debug_index))
     })
     .bind(("0.0.0.0", 4000))?
     .run()
-    .await
+    .await?;
+
+    // Keep the queries recorded since the last periodic write
+    shutdown_state.query_log.flush();
+    Ok(())
 }
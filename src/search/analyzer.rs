@@ -0,0 +1,92 @@
+/// A single token produced by the analyzer.
+///
+/// `start` and `end` are byte offsets into the original text, trimmed of
+/// surrounding punctuation so they can be used to highlight the token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub term: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Removes punctuation, collapses whitespace and lowercases the given string.
+///
+/// **Arguments**
+///
+/// * `input_string` - The string to normalize.
+///
+/// **Returns**
+///
+/// The normalized string.
+pub fn normalize_string(input_string: &str) -> String {
+    let string_without_punc: String = input_string
+        .chars()
+        .filter(|&c| !c.is_ascii_punctuation())
+        .collect();
+    let string_without_double_spaces: String = string_without_punc
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ");
+    string_without_double_spaces.to_lowercase()
+}
+
+/// Splits the given text into normalized terms.
+///
+/// **Arguments**
+///
+/// * `text` - The text to analyze.
+///
+/// **Returns**
+///
+/// A vector of the normalized terms in the order they appear in the text.
+pub fn analyze(text: &str) -> Vec<String> {
+    normalize_string(text)
+        .split_whitespace()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// Splits the given text into tokens and keeps track of their position in the text.
+///
+/// Produces the same terms as `analyze`.
+///
+/// **Arguments**
+///
+/// * `text` - The text to tokenize.
+///
+/// **Returns**
+///
+/// A vector of tokens in the order they appear in the text.
+pub fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chunk_start = None;
+    for (i, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        match (c.is_whitespace(), chunk_start) {
+            (false, None) => chunk_start = Some(i),
+            (true, Some(start)) => {
+                if let Some(token) = make_token(text, start, i) {
+                    tokens.push(token);
+                }
+                chunk_start = None;
+            }
+            _ => {}
+        }
+    }
+    tokens
+}
+
+fn make_token(text: &str, start: usize, end: usize) -> Option<Token> {
+    let chunk = &text[start..end];
+    let term = normalize_string(chunk);
+    if term.is_empty() {
+        return None;
+    }
+    let trimmed_start = chunk.trim_start_matches(|c: char| c.is_ascii_punctuation());
+    let trimmed = trimmed_start.trim_end_matches(|c: char| c.is_ascii_punctuation());
+    let start = start + (chunk.len() - trimmed_start.len());
+    Some(Token {
+        term,
+        start,
+        end: start + trimmed.len(),
+    })
+}
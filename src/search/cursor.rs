@@ -0,0 +1,63 @@
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+
+use crate::search::query::QueryError;
+use crate::types::field::FieldValue;
+
+/// The maximum size of a decoded cursor, tokens come from clients and are not trusted.
+const MAX_CURSOR_BYTES: u64 = 64 * 1024;
+
+fn options() -> impl Options {
+    bincode::DefaultOptions::new().with_limit(MAX_CURSOR_BYTES)
+}
+
+/// The position of a hit in a sorted result set, used to continue after it with `search_after`.
+///
+/// Cursors are handed out as opaque tokens. Scores are stored as numbers and
+/// missing field values as `None`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cursor {
+    /// The sort values of the hit, one per sort key.
+    pub values: Vec<Option<FieldValue>>,
+    /// The URL of the hit, the final tiebreak of every sort.
+    pub url: String,
+}
+
+impl Cursor {
+    /// Encodes the cursor as an opaque, URL-safe token.
+    pub fn encode(&self) -> String {
+        // Only fails for cursors above the size limit, which then fail to decode
+        let bytes = options().serialize(self).unwrap_or_default();
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// Decodes a token created by `encode`.
+    ///
+    /// **Arguments**
+    ///
+    /// * `token` - The token.
+    /// * `number_of_keys` - The number of sort keys of the request the token is used with.
+    ///
+    /// **Returns**
+    ///
+    /// The cursor, or an error if the token is malformed or was created for a different sort.
+    pub fn decode(token: &str, number_of_keys: usize) -> Result<Cursor, QueryError> {
+        let invalid = || QueryError::InvalidCursor(token.to_string());
+        if !token.len().is_multiple_of(2)
+            || token.len() as u64 > 2 * MAX_CURSOR_BYTES
+            || !token.is_ascii()
+        {
+            return Err(invalid());
+        }
+        let bytes = (0..token.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&token[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| invalid())?;
+        let cursor: Cursor = options().deserialize(&bytes).map_err(|_| invalid())?;
+        if cursor.values.len() != number_of_keys {
+            return Err(invalid());
+        }
+        Ok(cursor)
+    }
+}
@@ -1,10 +1,62 @@
-use bincode::{deserialize_from, serialize_into};
-use std::collections::{BTreeMap, HashMap};
+use bincode::{serialize_into, Options};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs::File;
-use std::io::{BufReader, BufWriter};
-use std::{f64, fs};
+use std::io::{BufWriter, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::{f64, fs, io};
 
-use crate::types::index;
+use crate::search::analyzer::{analyze, normalize_string};
+use crate::search::cursor::Cursor;
+use crate::search::explain::Explanation;
+use crate::search::facets::{Facet, FacetRequest};
+use crate::search::filter::{Filter, FilterCache, FilterOp};
+use crate::search::fuzzy::fuzzy_terms;
+use crate::search::group::{domain, GroupBy};
+use crate::search::highlight::{highlight, HighlightOptions};
+use crate::search::hnsw::{Hnsw, Metric, VectorError};
+use crate::search::ltr::{ModelError, RankingModel};
+use crate::search::percolator::{Percolator, PercolatorQuery};
+use crate::search::query::{Clause, Query, QueryError};
+use crate::search::rules::{QueryRule, RuleError, RuleOutcome};
+use crate::search::simhash::{bands, is_near_duplicate, simhash};
+use crate::search::sort::{SortKey, SortTarget};
+use crate::search::spelling::correct_term;
+use crate::search::suggest::{Completions, Suggestion, SuggestionSource};
+use crate::search::synonyms::SynonymSet;
+use crate::search::template::{SearchTemplate, TemplateError};
+use crate::search::terms::{prefix_terms, regex_terms, wildcard_terms};
+use crate::types::document::Document;
+use crate::types::field::FieldValue;
+use crate::types::index::{self, IndexError, SavedIndex, SavedIndexV0};
+
+/// Where the index is stored.
+const INDEX_PATH: &str = "/tmp/search.db";
+
+/// The maximum number of dictionary terms a fuzzy clause expands to.
+const MAX_FUZZY_EXPANSIONS: usize = 50;
+
+/// Score penalty per edit applied to terms found by fuzzy expansion.
+const FUZZY_PENALTY_PER_EDIT: f64 = 0.1;
+
+/// The number of terms a more-like-this query picks from the source document by default.
+pub const DEFAULT_MAX_QUERY_TERMS: usize = 25;
+
+/// The maximum number of phrases a phrase with synonyms expands to.
+const MAX_SYNONYM_PATHS: usize = 64;
+
+// The values a document is sorted by, borrowed from the doc values where possible
+type SortValues<'a> = Vec<Option<Cow<'a, FieldValue>>>;
+
+// Sums the details in the same order the scores are accumulated, or `None` if there are none
+fn sum_of(details: Vec<Explanation>, description: &str) -> Option<Explanation> {
+    if details.is_empty() {
+        return None;
+    }
+    let value = details.iter().map(|d| d.value).sum();
+    Some(Explanation::new(value, description).with_details(details))
+}
 
 fn update_url_scores(old: &mut HashMap<String, f64>, new: &HashMap<String, f64>) {
     for (url, score) in new {
@@ -14,18 +66,6 @@ fn update_url_scores(old: &mut HashMap<String, f64>, new: &HashMap<String, f64>)
     }
 }
 
-fn normalize_string(input_string: &str) -> String {
-    let string_without_punc: String = input_string
-        .chars()
-        .filter(|&c| !c.is_ascii_punctuation())
-        .collect();
-    let string_without_double_spaces: String = string_without_punc
-        .split_whitespace()
-        .collect::<Vec<&str>>()
-        .join(" ");
-    string_without_double_spaces.to_lowercase()
-}
-
 /// SearchEngine represents a search engine that indexes and searches documents based on the BM25 ranking algorithm.
 ///
 /// The search engine maintains an index of words and their frequencies in each document, as well as the actual document content.
@@ -44,17 +84,45 @@ fn normalize_string(input_string: &str) -> String {
 /// engine.index("https://example.com/doc1", "This is the content of document 1");
 ///
 /// // Perform a search
-/// let results = engine.search("content");
+/// let results = engine.search("content").unwrap();
 ///
 /// // Print the search results
 /// for (url, score) in results {
 ///     println!("{} - Relevance Score: {}", url, score);
 /// }
+/// # rustysearch::search::engine::remove_index_from_disk();
 /// ```
 #[derive(Default, Debug, Clone)]
 pub struct SearchEngine {
-    index: BTreeMap<String, HashMap<String, i32>>,
-    documents: BTreeMap<String, String>,
+    // The data is shared copy-on-write, so cloning the engine takes a cheap snapshot
+    index: Arc<BTreeMap<String, HashMap<String, i32>>>,
+    documents: Arc<BTreeMap<String, String>>,
+    titles: Arc<BTreeMap<String, String>>,
+    // Normalized titles to the URLs carrying them, used for completions
+    title_index: Arc<BTreeMap<String, BTreeSet<String>>>,
+    // Terms weighted by document frequency and normalized titles by the number of documents
+    // carrying them, for completions as the user types
+    term_completions: Arc<Completions>,
+    title_completions: Arc<Completions>,
+    synonyms: Arc<SynonymSet>,
+    // Columnar field storage: field name to the value of every document that has it
+    doc_values: Arc<BTreeMap<String, BTreeMap<String, FieldValue>>>,
+    // Vector field name to the nearest-neighbor graph of its vectors
+    vector_fields: Arc<BTreeMap<String, Hnsw>>,
+    // SimHash signature of every document with content, used to find near-duplicates
+    signatures: Arc<BTreeMap<String, u64>>,
+    // Static boosts set at index time, documents without one have a boost of 1.0
+    boosts: Arc<BTreeMap<String, f64>>,
+    // Curation rules by ID, applied to the results of matching queries
+    query_rules: Arc<BTreeMap<String, QueryRule>>,
+    // Trained models by name, used to rerank the top hits
+    ranking_models: Arc<BTreeMap<String, RankingModel>>,
+    // Stored queries that new documents are matched against
+    percolator: Arc<Percolator>,
+    // Parameterized search bodies by ID
+    search_templates: Arc<BTreeMap<String, SearchTemplate>>,
+    // Documents passing recently used filters, reset whenever the index changes
+    filter_cache: Arc<Mutex<FilterCache>>,
     k1: f64,
     b: f64,
 }
@@ -69,24 +137,101 @@ impl SearchEngine {
     ///
     /// **Returns**
     ///
-    /// A new instance of SearchEngine.
+    /// A new instance of SearchEngine. Panics if the index on disk cannot be read, because
+    /// starting empty would overwrite it on the next write; use `open` to handle the error.
     pub fn new(k1: f64, b: f64) -> SearchEngine {
+        SearchEngine::open(k1, b).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Opens the index stored on disk, migrating files written by older versions.
+    ///
+    /// **Arguments**
+    ///
+    /// * `k1` - The k1 parameter of the BM25 algorithm.
+    /// * `b` - The b parameter of the BM25 algorithm.
+    ///
+    /// **Returns**
+    ///
+    /// The search engine, empty if there is no index on disk, or an error if the index
+    /// cannot be read.
+    pub fn open(k1: f64, b: f64) -> Result<SearchEngine, IndexError> {
         // try to get Index from disk
         let mut index_btreemap = BTreeMap::new();
         let mut documents_btreemap = BTreeMap::new();
-        let saved_index = get_index_from_disk();
+        let mut titles_btreemap = BTreeMap::new();
+        let mut synonym_rules = Vec::new();
+        let mut doc_values = BTreeMap::new();
+        let mut signatures = BTreeMap::new();
+        let mut boosts = BTreeMap::new();
+        let saved_index = get_index_from_disk()?;
 
         if !saved_index.index_btree_map.is_empty() && !saved_index.documents_btree_map.is_empty() {
             index_btreemap = saved_index.index_btree_map;
             documents_btreemap = saved_index.documents_btree_map;
+            titles_btreemap = saved_index.titles_btree_map;
+            doc_values = saved_index.doc_values;
+            signatures = saved_index.signatures;
+            boosts = saved_index.boosts;
+        }
+        if !saved_index.synonym_rules.is_empty() {
+            synonym_rules = saved_index.synonym_rules;
         }
 
-        SearchEngine {
-            index: index_btreemap,
-            documents: documents_btreemap,
+        let mut title_index: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for (url, title) in &titles_btreemap {
+            title_index
+                .entry(normalize_string(title))
+                .or_default()
+                .insert(url.clone());
+        }
+
+        let term_completions =
+            Completions::build(index_btreemap.iter().map(|(term, urls)| (term, urls.len())));
+        let title_completions =
+            Completions::build(title_index.iter().map(|(title, urls)| (title, urls.len())));
+
+        let mut engine = SearchEngine {
+            index: Arc::new(index_btreemap),
+            documents: Arc::new(documents_btreemap),
+            titles: Arc::new(titles_btreemap),
+            term_completions: Arc::new(term_completions),
+            title_completions: Arc::new(title_completions),
+            title_index: Arc::new(title_index),
+            // The rules were validated before they were written
+            synonyms: Arc::new(SynonymSet::from_rules(&synonym_rules).unwrap_or_default()),
+            doc_values: Arc::new(doc_values),
+            vector_fields: Arc::new(saved_index.vector_fields),
+            signatures: Arc::new(signatures),
+            boosts: Arc::new(boosts),
+            query_rules: Arc::new(saved_index.query_rules),
+            ranking_models: Arc::new(saved_index.ranking_models),
+            percolator: Arc::default(),
+            // The templates were validated before they were written
+            search_templates: Arc::new(
+                saved_index
+                    .search_templates
+                    .iter()
+                    .filter_map(|(id, json)| Some((id.clone(), serde_json::from_str(json).ok()?)))
+                    .collect(),
+            ),
+            filter_cache: Arc::default(),
             k1,
             b,
-        }
+        };
+        engine.percolator = Arc::new(engine.compile_percolator(saved_index.percolator_queries));
+        Ok(engine)
+    }
+
+    /// Takes an immutable snapshot of the current state of the index.
+    ///
+    /// The snapshot shares the data with the engine and only the parts changed by later
+    /// writes are copied, so taking it is cheap and it never sees those writes.
+    ///
+    /// **Returns**
+    ///
+    /// A read-only view of the index as of now.
+    pub fn snapshot(&self) -> Arc<SearchEngine> {
+        Arc::new(self.clone())
     }
 
     /// Returns a vector of all the document URLs in the search engine's index.
@@ -117,6 +262,19 @@ impl SearchEngine {
         total_length as f64 / self.documents.len() as f64
     }
 
+    /// Returns the average title length of the documents with a title.
+    ///
+    /// **Returns**
+    ///
+    /// The average title length, 0.0 if no document has a title.
+    pub fn average_title_length(&self) -> f64 {
+        if self.titles.is_empty() {
+            return 0.0;
+        }
+        let total_length: usize = self.titles.values().map(|t| t.len()).sum();
+        total_length as f64 / self.titles.len() as f64
+    }
+
     /// Calculates the inverse document frequency (IDF) score for a given keyword.
     ///
     /// **Arguments**
@@ -146,16 +304,153 @@ impl SearchEngine {
         let idf_score = self.idf(kw);
         let avdl = self.avdl();
         for (url, freq) in self.get_urls(kw) {
-            let numerator = freq as f64 * (self.k1 + 1.0);
-            let denominator = freq as f64
-                + self.k1
-                    * (1.0 - self.b
-                        + self.b * self.documents.get(&url).unwrap().len() as f64 / avdl);
-            result.insert(url.to_string(), idf_score * numerator / denominator);
+            let score = self.term_score(idf_score, freq, self.documents[&url].len(), avdl);
+            result.insert(url, score);
         }
         result
     }
 
+    /// Computes the BM25 weight of a term in a text, shared by the ranking, its explanation
+    /// and the ranking features so all of them agree.
+    ///
+    /// **Arguments**
+    ///
+    /// * `idf` - The IDF of the term.
+    /// * `freq` - The number of occurrences of the term in the text.
+    /// * `document_length` - The length of the text.
+    /// * `avdl` - The average length of the texts.
+    ///
+    /// **Returns**
+    ///
+    /// The weight of the term.
+    pub fn term_score(&self, idf: f64, freq: i32, document_length: usize, avdl: f64) -> f64 {
+        let numerator = freq as f64 * (self.k1 + 1.0);
+        let denominator =
+            freq as f64 + self.k1 * (1.0 - self.b + self.b * document_length as f64 / avdl);
+        idf * numerator / denominator
+    }
+
+    /// Explains how the score of a document for a query was computed.
+    ///
+    /// The explanation follows the ranking step by step: the clauses are summed, expanded terms
+    /// are summed, phrases sum their terms, synonyms take their best alternative and every term
+    /// is scored with BM25 from its IDF, frequency, document length, avdl, k1, b and boost.
+    ///
+    /// **Arguments**
+    ///
+    /// * `query` - The parsed query.
+    /// * `url` - The URL of the document.
+    ///
+    /// **Returns**
+    ///
+    /// The explanation, `None` if the document does not match the query,
+    /// or an error if a clause expands to too many terms.
+    pub fn explain(&self, query: &Query, url: &str) -> Result<Option<Explanation>, QueryError> {
+        if !self.documents.contains_key(url) {
+            return Ok(None);
+        }
+        let query = self.apply_synonyms(query);
+        let mut details = Vec::new();
+        for clause in &query.clauses {
+            details.extend(self.explain_clause(clause, url, query.max_expansions)?);
+        }
+        Ok(sum_of(details, "sum of:"))
+    }
+
+    fn explain_clause(
+        &self,
+        clause: &Clause,
+        url: &str,
+        max_expansions: usize,
+    ) -> Result<Option<Explanation>, QueryError> {
+        let explanation = match clause {
+            Clause::Phrase(phrase) => {
+                if !self.phrase_scores(phrase).contains_key(url) {
+                    return Ok(None);
+                }
+                let details = phrase
+                    .iter()
+                    .filter_map(|term| self.explain_term(term, 1.0, url))
+                    .collect();
+                sum_of(
+                    details,
+                    &format!("phrase \"{}\", sum of:", phrase.join(" ")),
+                )
+            }
+            Clause::Synonyms(alternatives) => {
+                let mut details = Vec::new();
+                for alternative in alternatives {
+                    let mut alternative_details = Vec::new();
+                    for clause in alternative {
+                        alternative_details.extend(self.explain_clause(
+                            clause,
+                            url,
+                            max_expansions,
+                        )?);
+                    }
+                    details.extend(sum_of(alternative_details, "synonym alternative, sum of:"));
+                }
+                let best = details.iter().map(|d| d.value).reduce(f64::max);
+                best.map(|value| {
+                    Explanation::new(value, "best matching synonym alternative, max of:")
+                        .with_details(details)
+                })
+            }
+            Clause::Term(term) => self.explain_term(term, 1.0, url),
+            _ => {
+                let details = self
+                    .expand_clause(clause, max_expansions)?
+                    .into_iter()
+                    .filter_map(|(kw, boost)| self.explain_term(&kw, boost, url))
+                    .collect();
+                sum_of(details, "expanded terms, sum of:")
+            }
+        };
+        Ok(explanation)
+    }
+
+    fn explain_term(&self, kw: &str, boost: f64, url: &str) -> Option<Explanation> {
+        let freq = *self.index.get(kw)?.get(url)?;
+        let n = self.number_of_documents();
+        let n_kw = self.get_urls(kw).len();
+        let idf = self.idf(kw);
+        let document_length = self.documents[url].len();
+        let avdl = self.avdl();
+        let score = self.term_score(idf, freq, document_length, avdl);
+
+        let numerator = freq as f64 * (self.k1 + 1.0);
+        let denominator =
+            freq as f64 + self.k1 * (1.0 - self.b + self.b * document_length as f64 / avdl);
+        let mut details = vec![
+            Explanation::new(
+                idf,
+                "idf, computed as ln(1 + (N - n + 0.5) / (n + 0.5)) from:",
+            )
+            .with_details(vec![
+                Explanation::new(n_kw as f64, "n, number of documents containing the term"),
+                Explanation::new(n as f64, "N, total number of documents"),
+            ]),
+            Explanation::new(
+                numerator / denominator,
+                "tf, computed as freq * (k1 + 1) / (freq + k1 * (1 - b + b * dl / avdl)) from:",
+            )
+            .with_details(vec![
+                Explanation::new(freq as f64, "freq, occurrences of the term in the document"),
+                Explanation::new(self.k1, "k1, term saturation parameter"),
+                Explanation::new(self.b, "b, length normalization parameter"),
+                Explanation::new(document_length as f64, "dl, length of the document"),
+                Explanation::new(avdl, "avdl, average length of the documents"),
+            ]),
+        ];
+        let description = if boost == 1.0 {
+            format!("weight of '{}', idf * tf:", kw)
+        } else {
+            details.insert(0, Explanation::new(boost, "boost"));
+            format!("weight of '{}', boost * idf * tf:", kw)
+        };
+        Some(Explanation::new(score * boost, &description).with_details(details))
+    }
+
     /// Performs a search for the given query and returns the relevance scores for the matching documents.
     ///
     /// **Arguments**
@@ -164,20 +459,574 @@ impl SearchEngine {
     ///
     /// **Returns**
     ///
-    /// A HashMap containing the document URLs as keys and their relevance scores as values.
-    pub fn search(&mut self, query: &str) -> HashMap<String, f64> {
-        let keywords = normalize_string(query)
-            .split_whitespace()
-            .map(|s| s.to_string())
-            .collect::<Vec<String>>();
+    /// A HashMap containing the document URLs as keys and their relevance scores as values,
+    /// or an error if the query cannot be parsed.
+    pub fn search(&self, query: &str) -> Result<HashMap<String, f64>, QueryError> {
+        let query = Query::parse(query)?;
+        self.search_query(&query)
+    }
+
+    /// Performs a search for an already parsed query.
+    ///
+    /// **Arguments**
+    ///
+    /// * `query` - The parsed query.
+    ///
+    /// **Returns**
+    ///
+    /// A HashMap containing the document URLs as keys and their relevance scores as values,
+    /// or an error if a clause expands to too many terms.
+    pub fn search_query(&self, query: &Query) -> Result<HashMap<String, f64>, QueryError> {
+        let query = self.apply_synonyms(query);
         let mut url_scores: HashMap<String, f64> = HashMap::new();
-        for kw in keywords {
-            let kw_urls_score = self.bm25(&kw);
-            update_url_scores(&mut url_scores, &kw_urls_score);
+        for clause in &query.clauses {
+            let clause_scores = self.score_clause(clause, query.max_expansions)?;
+            update_url_scores(&mut url_scores, &clause_scores);
+        }
+        Ok(url_scores)
+    }
+
+    fn score_clause(
+        &self,
+        clause: &Clause,
+        max_expansions: usize,
+    ) -> Result<HashMap<String, f64>, QueryError> {
+        let mut url_scores: HashMap<String, f64> = HashMap::new();
+        match clause {
+            Clause::Phrase(phrase) => url_scores = self.phrase_scores(phrase),
+            Clause::Synonyms(alternatives) => {
+                // Each document is scored by its best matching alternative
+                for alternative in alternatives {
+                    let mut alternative_scores = HashMap::new();
+                    for clause in alternative {
+                        let clause_scores = self.score_clause(clause, max_expansions)?;
+                        update_url_scores(&mut alternative_scores, &clause_scores);
+                    }
+                    for (url, score) in alternative_scores {
+                        url_scores
+                            .entry(url)
+                            .and_modify(|best| *best = score.max(*best))
+                            .or_insert(score);
+                    }
+                }
+            }
+            _ => {
+                for (kw, boost) in self.expand_clause(clause, max_expansions)? {
+                    let mut kw_urls_score = self.bm25(&kw);
+                    kw_urls_score.values_mut().for_each(|score| *score *= boost);
+                    update_url_scores(&mut url_scores, &kw_urls_score);
+                }
+            }
+        }
+        Ok(url_scores)
+    }
+
+    // Scores the documents containing the terms in the given order by the sum of their term scores
+    fn phrase_scores(&self, phrase: &[String]) -> HashMap<String, f64> {
+        let term_scores: Vec<HashMap<String, f64>> = phrase.iter().map(|t| self.bm25(t)).collect();
+        let mut url_scores = HashMap::new();
+        for url in term_scores[0].keys() {
+            if !term_scores.iter().all(|scores| scores.contains_key(url)) {
+                continue;
+            }
+            // The index has no positions, so candidates are verified against the stored content
+            let tokens = analyze(&self.documents[url]);
+            if tokens.windows(phrase.len()).any(|window| window == phrase) {
+                let score = term_scores.iter().map(|scores| scores[url]).sum();
+                url_scores.insert(url.clone(), score);
+            }
         }
         url_scores
     }
 
+    /// Expands the clauses of a query into the dictionary terms they match.
+    ///
+    /// Exact terms keep a boost of 1.0. Fuzzy clauses expand to the nearest dictionary terms,
+    /// which are scored slightly below exact matches for every edit. Prefix, wildcard and
+    /// regex clauses expand to every matching dictionary term with a boost of 1.0.
+    /// Phrases and synonyms expand to all of their terms.
+    ///
+    /// **Arguments**
+    ///
+    /// * `query` - The parsed query.
+    ///
+    /// **Returns**
+    ///
+    /// A vector of terms with the boost to apply to their scores,
+    /// or an error if a clause expands to more than `query.max_expansions` terms.
+    pub fn expand_query(&self, query: &Query) -> Result<Vec<(String, f64)>, QueryError> {
+        let query = self.apply_synonyms(query);
+        let mut terms = Vec::new();
+        for clause in &query.clauses {
+            terms.extend(self.expand_clause(clause, query.max_expansions)?);
+        }
+        Ok(terms)
+    }
+
+    fn expand_clause(
+        &self,
+        clause: &Clause,
+        max_expansions: usize,
+    ) -> Result<Vec<(String, f64)>, QueryError> {
+        let terms = match clause {
+            Clause::Term(term) => vec![(term.clone(), 1.0)],
+            Clause::Fuzzy { term, fuzziness } => {
+                let mut expansions = fuzzy_terms(&self.index, term, fuzziness.max_distance(term));
+                // Prefer the closest and most common terms when there are too many
+                expansions.sort_by_key(|(t, distance)| {
+                    (*distance, std::cmp::Reverse(self.index[t].len()))
+                });
+                expansions.truncate(MAX_FUZZY_EXPANSIONS);
+                expansions
+                    .into_iter()
+                    .map(|(t, distance)| (t, 1.0 - FUZZY_PENALTY_PER_EDIT * distance as f64))
+                    .collect()
+            }
+            Clause::Prefix(prefix) => prefix_terms(&self.index, prefix, max_expansions)?
+                .into_iter()
+                .map(|t| (t, 1.0))
+                .collect(),
+            Clause::Wildcard(pattern) => wildcard_terms(&self.index, pattern, max_expansions)?
+                .into_iter()
+                .map(|t| (t, 1.0))
+                .collect(),
+            Clause::Regex(pattern) => regex_terms(&self.index, pattern, max_expansions)?
+                .into_iter()
+                .map(|t| (t, 1.0))
+                .collect(),
+            Clause::Phrase(phrase) => phrase.iter().map(|t| (t.clone(), 1.0)).collect(),
+            Clause::Synonyms(alternatives) => {
+                let mut terms = Vec::new();
+                for clause in alternatives.iter().flatten() {
+                    terms.extend(self.expand_clause(clause, max_expansions)?);
+                }
+                terms
+            }
+        };
+        Ok(terms)
+    }
+
+    // Rewrites runs of exact terms and phrases that have synonyms into synonym clauses
+    fn apply_synonyms(&self, query: &Query) -> Query {
+        if self.synonyms.is_empty() {
+            return query.clone();
+        }
+        let mut clauses = Vec::new();
+        let mut terms = Vec::new();
+        for clause in &query.clauses {
+            match clause {
+                Clause::Term(term) => terms.push(term.clone()),
+                Clause::Phrase(phrase) => {
+                    self.push_term_synonyms(&mut clauses, std::mem::take(&mut terms));
+                    clauses.push(self.phrase_synonyms(phrase));
+                }
+                clause => {
+                    self.push_term_synonyms(&mut clauses, std::mem::take(&mut terms));
+                    clauses.push(clause.clone());
+                }
+            }
+        }
+        self.push_term_synonyms(&mut clauses, terms);
+        Query {
+            clauses,
+            ..query.clone()
+        }
+    }
+
+    // The original words of a span keep matching individually, multi-word synonyms must match as phrases
+    fn push_term_synonyms(&self, clauses: &mut Vec<Clause>, terms: Vec<String>) {
+        let as_terms = |tokens: &[String]| tokens.iter().cloned().map(Clause::Term).collect();
+        for span in self.synonyms.graph(&terms) {
+            if span.alternatives == [span.original.clone()] {
+                clauses.extend(as_terms(&span.original));
+                continue;
+            }
+            let alternatives = span
+                .alternatives
+                .iter()
+                .map(|alternative| {
+                    if *alternative == span.original || alternative.len() == 1 {
+                        as_terms(alternative)
+                    } else {
+                        vec![Clause::Phrase(alternative.clone())]
+                    }
+                })
+                .collect();
+            clauses.push(Clause::Synonyms(alternatives));
+        }
+    }
+
+    // Builds one phrase for every path through the synonym graph of the phrase
+    fn phrase_synonyms(&self, phrase: &[String]) -> Clause {
+        let mut paths: Vec<Vec<String>> = vec![Vec::new()];
+        for span in self.synonyms.graph(phrase) {
+            paths = paths
+                .iter()
+                .flat_map(|path| {
+                    span.alternatives
+                        .iter()
+                        .map(move |alternative| [path.as_slice(), alternative].concat())
+                })
+                .take(MAX_SYNONYM_PATHS)
+                .collect();
+        }
+        if paths.len() == 1 {
+            return Clause::Phrase(paths.remove(0));
+        }
+        Clause::Synonyms(paths.into_iter().map(|p| vec![Clause::Phrase(p)]).collect())
+    }
+
+    /// Replaces the synonyms used at query time and writes them to disk.
+    ///
+    /// No reindexing is needed because synonyms are only applied to queries.
+    ///
+    /// **Arguments**
+    ///
+    /// * `synonyms` - The new synonym set.
+    pub fn set_synonyms(&mut self, synonyms: SynonymSet) {
+        self.synonyms = Arc::new(synonyms);
+        // The stored queries are indexed with the synonyms applied
+        let queries = self.percolator.queries().clone();
+        self.percolator = Arc::new(self.compile_percolator(queries));
+        self.write_index_to_disk();
+    }
+
+    /// Defines a vector field that documents can store vectors in.
+    ///
+    /// **Arguments**
+    ///
+    /// * `name` - The name of the field.
+    /// * `dimension` - The number of dimensions of every vector.
+    /// * `metric` - The similarity metric used for nearest-neighbor search.
+    ///
+    /// **Returns**
+    ///
+    /// An error if the dimension is out of range or the field exists with another definition.
+    pub fn create_vector_field(
+        &mut self,
+        name: &str,
+        dimension: usize,
+        metric: Metric,
+    ) -> Result<(), VectorError> {
+        if let Some(graph) = self.vector_fields.get(name) {
+            if graph.dimension() == dimension && graph.metric() == metric {
+                return Ok(());
+            }
+            return Err(VectorError::FieldExists(name.to_string()));
+        }
+        let graph = Hnsw::new(dimension, metric)?;
+        Arc::make_mut(&mut self.vector_fields).insert(name.to_string(), graph);
+        self.write_index_to_disk();
+        Ok(())
+    }
+
+    /// Returns the vector fields by name.
+    pub fn vector_fields(&self) -> &BTreeMap<String, Hnsw> {
+        &self.vector_fields
+    }
+
+    /// Checks that the vectors of a document fit the defined vector fields.
+    ///
+    /// **Arguments**
+    ///
+    /// * `document` - The document to check.
+    ///
+    /// **Returns**
+    ///
+    /// An error for the first vector of an unknown field or with the wrong dimension.
+    pub fn validate_vectors(&self, document: &Document) -> Result<(), VectorError> {
+        for (name, vector) in &document.vectors {
+            self.vector_field(name, vector)?;
+        }
+        Ok(())
+    }
+
+    fn vector_field(&self, name: &str, vector: &[f32]) -> Result<&Hnsw, VectorError> {
+        let graph = self
+            .vector_fields
+            .get(name)
+            .ok_or_else(|| VectorError::UnknownField(name.to_string()))?;
+        if vector.len() != graph.dimension() {
+            return Err(VectorError::DimensionMismatch {
+                field: name.to_string(),
+                expected: graph.dimension(),
+                actual: vector.len(),
+            });
+        }
+        Ok(graph)
+    }
+
+    /// Finds the documents whose vectors are nearest to the given vector.
+    ///
+    /// Filters are applied during the graph search, so up to `k` passing documents
+    /// are returned even if most neighbors are filtered out.
+    ///
+    /// **Arguments**
+    ///
+    /// * `field` - The vector field to search.
+    /// * `vector` - The query vector.
+    /// * `k` - The number of documents to return.
+    /// * `num_candidates` - The number of candidates to consider, larger is more accurate.
+    /// * `filters` - Filters the documents must pass.
+    ///
+    /// **Returns**
+    ///
+    /// The URLs and similarity scores of the nearest documents, most similar first.
+    pub fn knn(
+        &self,
+        field: &str,
+        vector: &[f32],
+        k: usize,
+        num_candidates: usize,
+        filters: &[Filter],
+    ) -> Result<Vec<(String, f64)>, VectorError> {
+        let graph = self.vector_field(field, vector)?;
+        if filters.is_empty() {
+            return Ok(graph.search(vector, k, num_candidates, |_| true));
+        }
+        let candidates = self.filter(filters);
+        Ok(graph.search(vector, k, num_candidates, |url| candidates.contains(url)))
+    }
+
+    /// Returns the query rules by ID.
+    pub fn query_rules(&self) -> &BTreeMap<String, QueryRule> {
+        &self.query_rules
+    }
+
+    /// Creates or replaces a query rule and writes it to disk.
+    ///
+    /// **Arguments**
+    ///
+    /// * `id` - The ID of the rule.
+    /// * `rule` - The rule.
+    ///
+    /// **Returns**
+    ///
+    /// True if the rule was created, false if it replaced one, or an error if it is invalid.
+    pub fn put_query_rule(&mut self, id: &str, rule: QueryRule) -> Result<bool, RuleError> {
+        rule.validate()?;
+        let created = Arc::make_mut(&mut self.query_rules)
+            .insert(id.to_string(), rule)
+            .is_none();
+        self.write_index_to_disk();
+        Ok(created)
+    }
+
+    /// Deletes a query rule and writes the change to disk.
+    ///
+    /// **Arguments**
+    ///
+    /// * `id` - The ID of the rule.
+    ///
+    /// **Returns**
+    ///
+    /// True if the rule existed.
+    pub fn delete_query_rule(&mut self, id: &str) -> bool {
+        let deleted = Arc::make_mut(&mut self.query_rules).remove(id).is_some();
+        if deleted {
+            self.write_index_to_disk();
+        }
+        deleted
+    }
+
+    /// Combines the actions of the query rules matching a query, in the order of their IDs.
+    ///
+    /// **Arguments**
+    ///
+    /// * `query` - The query text.
+    ///
+    /// **Returns**
+    ///
+    /// The pinned, hidden and boosted documents and the redirect of the matching rules.
+    pub fn evaluate_query_rules(&self, query: &str) -> RuleOutcome {
+        RuleOutcome::evaluate(self.query_rules.values(), query)
+    }
+
+    /// Returns the ranking models by name.
+    pub fn ranking_models(&self) -> &BTreeMap<String, RankingModel> {
+        &self.ranking_models
+    }
+
+    /// Returns the ranking model with the given name.
+    ///
+    /// **Arguments**
+    ///
+    /// * `name` - The name of the model.
+    ///
+    /// **Returns**
+    ///
+    /// The model, or `None` if there is none by that name.
+    pub fn ranking_model(&self, name: &str) -> Option<&RankingModel> {
+        self.ranking_models.get(name)
+    }
+
+    /// Creates or replaces a ranking model and writes it to disk.
+    ///
+    /// **Arguments**
+    ///
+    /// * `name` - The name of the model.
+    /// * `model` - The model.
+    ///
+    /// **Returns**
+    ///
+    /// True if the model was created, false if it replaced one, or an error if it is invalid.
+    pub fn put_ranking_model(
+        &mut self,
+        name: &str,
+        model: RankingModel,
+    ) -> Result<bool, ModelError> {
+        model.validate()?;
+        let created = Arc::make_mut(&mut self.ranking_models)
+            .insert(name.to_string(), model)
+            .is_none();
+        self.write_index_to_disk();
+        Ok(created)
+    }
+
+    /// Deletes a ranking model and writes the change to disk.
+    ///
+    /// **Arguments**
+    ///
+    /// * `name` - The name of the model.
+    ///
+    /// **Returns**
+    ///
+    /// True if the model existed.
+    pub fn delete_ranking_model(&mut self, name: &str) -> bool {
+        let deleted = Arc::make_mut(&mut self.ranking_models)
+            .remove(name)
+            .is_some();
+        if deleted {
+            self.write_index_to_disk();
+        }
+        deleted
+    }
+
+    /// Returns the search templates by ID.
+    pub fn search_templates(&self) -> &BTreeMap<String, SearchTemplate> {
+        &self.search_templates
+    }
+
+    /// Creates or replaces a search template and writes it to disk.
+    ///
+    /// **Arguments**
+    ///
+    /// * `id` - The ID of the template.
+    /// * `template` - The template.
+    ///
+    /// **Returns**
+    ///
+    /// True if the template was created, false if it replaced one, or an error if it is malformed.
+    pub fn put_search_template(
+        &mut self,
+        id: &str,
+        template: SearchTemplate,
+    ) -> Result<bool, TemplateError> {
+        template.validate()?;
+        let created = Arc::make_mut(&mut self.search_templates)
+            .insert(id.to_string(), template)
+            .is_none();
+        self.write_index_to_disk();
+        Ok(created)
+    }
+
+    /// Deletes a search template and writes the change to disk.
+    ///
+    /// **Arguments**
+    ///
+    /// * `id` - The ID of the template.
+    ///
+    /// **Returns**
+    ///
+    /// True if the template existed.
+    pub fn delete_search_template(&mut self, id: &str) -> bool {
+        let deleted = Arc::make_mut(&mut self.search_templates)
+            .remove(id)
+            .is_some();
+        if deleted {
+            self.write_index_to_disk();
+        }
+        deleted
+    }
+
+    /// Returns the stored percolator queries by ID.
+    pub fn percolator_queries(&self) -> &BTreeMap<String, PercolatorQuery> {
+        self.percolator.queries()
+    }
+
+    /// Creates or replaces a percolator query and writes it to disk.
+    ///
+    /// **Arguments**
+    ///
+    /// * `id` - The ID of the query.
+    /// * `query` - The query.
+    ///
+    /// **Returns**
+    ///
+    /// True if the query was created, false if it replaced one, or an error if it is malformed.
+    pub fn put_percolator_query(
+        &mut self,
+        id: &str,
+        query: PercolatorQuery,
+    ) -> Result<bool, QueryError> {
+        let (parsed, filters) = query.parse()?;
+        let parsed = self.apply_synonyms(&parsed);
+        let created = Arc::make_mut(&mut self.percolator).insert(id, query, parsed, filters);
+        self.write_index_to_disk();
+        Ok(created)
+    }
+
+    /// Deletes a percolator query and writes the change to disk.
+    ///
+    /// **Arguments**
+    ///
+    /// * `id` - The ID of the query.
+    ///
+    /// **Returns**
+    ///
+    /// True if the query existed.
+    pub fn delete_percolator_query(&mut self, id: &str) -> bool {
+        let deleted = Arc::make_mut(&mut self.percolator).remove(id);
+        if deleted {
+            self.write_index_to_disk();
+        }
+        deleted
+    }
+
+    /// Finds the percolator queries matching an indexed document.
+    ///
+    /// **Arguments**
+    ///
+    /// * `url` - The URL of the document.
+    ///
+    /// **Returns**
+    ///
+    /// The IDs of the matching queries in order, empty if the document does not exist.
+    pub fn percolate(&self, url: &str) -> Vec<String> {
+        let Some(content) = self.documents.get(url) else {
+            return Vec::new();
+        };
+        self.percolator
+            .percolate(&analyze(content), |field| self.field(url, field))
+    }
+
+    // Indexes stored queries, which were validated before they were written
+    fn compile_percolator(&self, queries: BTreeMap<String, PercolatorQuery>) -> Percolator {
+        let mut percolator = Percolator::default();
+        for (id, query) in queries {
+            if let Ok((parsed, filters)) = query.parse() {
+                let parsed = self.apply_synonyms(&parsed);
+                percolator.insert(&id, query, parsed, filters);
+            }
+        }
+        percolator
+    }
+
+    /// Returns the synonyms used at query time.
+    pub fn synonyms(&self) -> &SynonymSet {
+        &self.synonyms
+    }
+
     /// Indexes a document with the given URL and content.
     ///
     /// **Arguments**
@@ -185,20 +1034,429 @@ impl SearchEngine {
     /// * `url` - The URL of the document.
     /// * `content` - The content of the document.
     pub fn index(&mut self, url: &str, content: &str) {
-        self.documents.insert(url.to_string(), content.to_string());
-        let words = normalize_string(content)
-            .split_whitespace()
-            .map(|s| s.to_string())
-            .collect::<Vec<String>>();
-        for word in words {
-            *self
-                .index
-                .entry(word)
+        self.index_document(&Document::new(url, content));
+    }
+
+    /// Indexes a document together with its optional title.
+    ///
+    /// A document with the same URL is replaced entirely, including its postings and title.
+    ///
+    /// **Arguments**
+    ///
+    /// * `document` - The document to index.
+    ///
+    /// **Returns**
+    ///
+    /// True if the document was created, false if it replaced one.
+    pub fn index_document(&mut self, document: &Document) -> bool {
+        let url = document.url.as_str();
+        let old_content = Arc::make_mut(&mut self.documents)
+            .insert(url.to_string(), document.content.to_string());
+        let words = analyze(&document.content);
+        let index = Arc::make_mut(&mut self.index);
+        let term_completions = Arc::make_mut(&mut self.term_completions);
+        // The old postings go first, so replaced words stop matching and counts do not add up
+        if let Some(old_content) = &old_content {
+            for word in analyze(old_content) {
+                let Some(urls) = index.get_mut(&word) else {
+                    continue;
+                };
+                if urls.remove(url).is_some() {
+                    term_completions.set(&word, urls.len());
+                    if urls.is_empty() {
+                        index.remove(&word);
+                    }
+                }
+            }
+        }
+        for word in &words {
+            let urls = index.entry(word.clone()).or_default();
+            *urls.entry(url.to_string()).or_insert(0) += 1;
+        }
+        for word in &words {
+            term_completions.set(word, index[word].len());
+        }
+        match &document.title {
+            Some(title) => self.set_title(url, title),
+            None => self.remove_title(url),
+        }
+        let signatures = Arc::make_mut(&mut self.signatures);
+        match simhash(&document.content) {
+            Some(signature) => signatures.insert(url.to_string(), signature),
+            None => signatures.remove(url),
+        };
+        let boosts = Arc::make_mut(&mut self.boosts);
+        match document.boost {
+            Some(boost) => boosts.insert(url.to_string(), boost),
+            None => boosts.remove(url),
+        };
+        let doc_values = Arc::make_mut(&mut self.doc_values);
+        for column in doc_values.values_mut() {
+            column.remove(url);
+        }
+        for (name, value) in &document.fields {
+            doc_values
+                .entry(name.clone())
                 .or_default()
-                .entry(url.to_string())
-                .or_insert(0) += 1;
+                .insert(url.to_string(), value.clone());
         }
+        for (name, graph) in Arc::make_mut(&mut self.vector_fields).iter_mut() {
+            match document.vectors.get(name) {
+                Some(vector) if vector.len() == graph.dimension() => graph.insert(url, vector),
+                _ => graph.remove(url),
+            }
+        }
+        // Snapshots taken before this change keep their own cache
+        self.filter_cache = Arc::default();
         self.write_index_to_disk();
+        old_content.is_none()
+    }
+
+    fn remove_title(&mut self, url: &str) {
+        let Some(old_title) = Arc::make_mut(&mut self.titles).remove(url) else {
+            return;
+        };
+        let title_index = Arc::make_mut(&mut self.title_index);
+        let old_key = normalize_string(&old_title);
+        if let Some(urls) = title_index.get_mut(&old_key) {
+            urls.remove(url);
+            Arc::make_mut(&mut self.title_completions).set(&old_key, urls.len());
+            if urls.is_empty() {
+                title_index.remove(&old_key);
+            }
+        }
+    }
+
+    fn set_title(&mut self, url: &str, title: &str) {
+        self.remove_title(url);
+        Arc::make_mut(&mut self.titles).insert(url.to_string(), title.to_string());
+        let title_index = Arc::make_mut(&mut self.title_index);
+        let title_completions = Arc::make_mut(&mut self.title_completions);
+        let key = normalize_string(title);
+        let urls = title_index.entry(key.clone()).or_default();
+        urls.insert(url.to_string());
+        title_completions.set(&key, urls.len());
+    }
+
+    /// Returns the value of a field of the document with the given URL.
+    ///
+    /// **Arguments**
+    ///
+    /// * `url` - The URL of the document.
+    /// * `field` - The name of the field.
+    ///
+    /// **Returns**
+    ///
+    /// The value, or `None` if the document does not have the field.
+    pub fn field(&self, url: &str, field: &str) -> Option<&FieldValue> {
+        self.doc_values
+            .get(field)
+            .and_then(|column| column.get(url))
+    }
+
+    /// Returns all fields of the document with the given URL.
+    ///
+    /// **Arguments**
+    ///
+    /// * `url` - The URL of the document.
+    ///
+    /// **Returns**
+    ///
+    /// The field names and values of the document.
+    pub fn fields(&self, url: &str) -> BTreeMap<&str, &FieldValue> {
+        self.doc_values
+            .iter()
+            .filter_map(|(name, column)| column.get(url).map(|value| (name.as_str(), value)))
+            .collect()
+    }
+
+    /// Returns the static boost of a document set at index time.
+    ///
+    /// **Arguments**
+    ///
+    /// * `url` - The URL of the document.
+    ///
+    /// **Returns**
+    ///
+    /// The boost, 1.0 if the document has none.
+    pub fn boost(&self, url: &str) -> f64 {
+        self.boosts.get(url).copied().unwrap_or(1.0)
+    }
+
+    /// Returns the key a document is grouped by.
+    ///
+    /// **Arguments**
+    ///
+    /// * `url` - The URL of the document.
+    /// * `group_by` - The domain of the URL or a keyword field.
+    ///
+    /// **Returns**
+    ///
+    /// The key, or `None` if the document has no domain or no keyword value for the field.
+    pub fn group_key(&self, url: &str, group_by: &GroupBy) -> Option<String> {
+        match group_by {
+            GroupBy::Domain => domain(url),
+            GroupBy::Field(field) => match self.field(url, field) {
+                Some(FieldValue::Keyword(keyword)) => Some(keyword.clone()),
+                _ => None,
+            },
+        }
+    }
+
+    /// Orders search results by the given sort keys.
+    ///
+    /// Field values are read from the doc-value columns, so stored documents are never
+    /// touched. Documents that tie on every key are ordered by URL to keep the order stable.
+    ///
+    /// **Arguments**
+    ///
+    /// * `results` - The scores of the matching documents.
+    /// * `keys` - The sort keys, the first one has the highest priority.
+    /// * `after` - Only return the documents sorted after this cursor, for `search_after`.
+    ///
+    /// **Returns**
+    ///
+    /// The URLs and scores in sort order.
+    pub fn sort_results(
+        &self,
+        results: HashMap<String, f64>,
+        keys: &[SortKey],
+        after: Option<&Cursor>,
+    ) -> Vec<(String, f64)> {
+        let columns: Vec<Option<&BTreeMap<String, FieldValue>>> = keys
+            .iter()
+            .map(|key| match &key.target {
+                SortTarget::Score => None,
+                SortTarget::Field(field) => self.doc_values.get(field),
+            })
+            .collect();
+
+        // Look up every value once instead of on every comparison
+        let mut ranked: Vec<(String, f64, SortValues)> = results
+            .into_iter()
+            .map(|(url, score)| {
+                let values = keys
+                    .iter()
+                    .zip(&columns)
+                    .map(|(key, column)| match key.target {
+                        SortTarget::Score => Some(Cow::Owned(FieldValue::Number(score))),
+                        SortTarget::Field(_) => column
+                            .and_then(|column| column.get(&url))
+                            .map(Cow::Borrowed),
+                    })
+                    .collect();
+                (url, score, values)
+            })
+            .collect();
+
+        let compare = |a_values: &[Option<Cow<FieldValue>>],
+                       a_url: &str,
+                       b_values: &[Option<Cow<FieldValue>>],
+                       b_url: &str| {
+            keys.iter()
+                .zip(a_values.iter().zip(b_values))
+                .map(|(key, (a, b))| key.compare(a.as_deref(), b.as_deref()))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| a_url.cmp(b_url))
+        };
+        if let Some(after) = after {
+            let after_values: SortValues = after
+                .values
+                .iter()
+                .map(|v| v.as_ref().map(Cow::Borrowed))
+                .collect();
+            ranked
+                .retain(|(url, _, values)| compare(values, url, &after_values, &after.url).is_gt());
+        }
+        ranked.sort_by(|a, b| compare(&a.2, &a.0, &b.2, &b.0));
+        ranked
+            .into_iter()
+            .map(|(url, score, _)| (url, score))
+            .collect()
+    }
+
+    /// Returns the values a document is sorted by, as stored in a `search_after` cursor.
+    ///
+    /// **Arguments**
+    ///
+    /// * `url` - The URL of the document.
+    /// * `score` - The score of the document.
+    /// * `keys` - The sort keys.
+    ///
+    /// **Returns**
+    ///
+    /// One value per sort key, `None` if the document does not have the field.
+    pub fn sort_values(&self, url: &str, score: f64, keys: &[SortKey]) -> Vec<Option<FieldValue>> {
+        keys.iter()
+            .map(|key| match &key.target {
+                SortTarget::Score => Some(FieldValue::Number(score)),
+                SortTarget::Field(field) => self.field(url, field).cloned(),
+            })
+            .collect()
+    }
+
+    /// Returns the URLs of all documents passing every given filter.
+    ///
+    /// Filters are evaluated against the doc-value columns and the result of every
+    /// filter is cached until the index changes.
+    ///
+    /// **Arguments**
+    ///
+    /// * `filters` - The filters to apply.
+    ///
+    /// **Returns**
+    ///
+    /// The URLs of the documents passing all filters.
+    pub fn filter(&self, filters: &[Filter]) -> HashSet<String> {
+        let mut candidates: Option<HashSet<String>> = None;
+        for filter in filters {
+            let passing = self.filter_candidates(filter);
+            candidates = Some(match candidates {
+                Some(candidates) => candidates.intersection(&passing).cloned().collect(),
+                None => passing.as_ref().clone(),
+            });
+        }
+        candidates.unwrap_or_else(|| self.documents.keys().cloned().collect())
+    }
+
+    /// Checks that the values of filters can be compared with the fields they filter.
+    ///
+    /// **Arguments**
+    ///
+    /// * `filters` - The filters to check.
+    ///
+    /// **Returns**
+    ///
+    /// An error for the first filter comparing a numeric or date field with a value of another type.
+    pub fn validate_filters(&self, filters: &[Filter]) -> Result<(), QueryError> {
+        for filter in filters {
+            // A field holds values of one type, so its first value stands for all of them
+            let value = self
+                .doc_values
+                .get(&filter.field)
+                .and_then(|column| column.values().next());
+            if let Some(value) = value {
+                filter.check(value)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn filter_candidates(&self, filter: &Filter) -> Arc<HashSet<String>> {
+        let key = filter.to_string();
+        if let Some(cached) = self.filter_cache.lock().unwrap().get(&key) {
+            return cached.clone();
+        }
+
+        let column = self.doc_values.get(&filter.field);
+        let passing: HashSet<String> = if filter.op == FilterOp::Ne {
+            // Documents without the field pass as well, so every document is visited
+            self.documents
+                .keys()
+                .filter(|url| filter.matches(column.and_then(|c| c.get(*url))))
+                .cloned()
+                .collect()
+        } else {
+            column
+                .into_iter()
+                .flatten()
+                .filter(|(_, value)| filter.matches(Some(value)))
+                .map(|(url, _)| url.clone())
+                .collect()
+        };
+
+        let passing = Arc::new(passing);
+        self.filter_cache
+            .lock()
+            .unwrap()
+            .insert(key, passing.clone());
+        passing
+    }
+
+    /// Computes facets over a set of documents from the doc-value columns.
+    ///
+    /// **Arguments**
+    ///
+    /// * `urls` - The URLs of all matching documents, not only the returned page.
+    /// * `requests` - The facets to compute.
+    ///
+    /// **Returns**
+    ///
+    /// The facets by field name, or an error if a facet option does not fit its field.
+    pub fn facets<'a>(
+        &self,
+        urls: impl Iterator<Item = &'a String> + Clone,
+        requests: &[FacetRequest],
+    ) -> Result<BTreeMap<String, Facet>, QueryError> {
+        let mut facets = BTreeMap::new();
+        for request in requests {
+            let column = self.doc_values.get(&request.field);
+            let values = urls
+                .clone()
+                .map(|url| column.and_then(|column| column.get(url)));
+            facets.insert(request.field.clone(), request.compute(column, values)?);
+        }
+        Ok(facets)
+    }
+
+    /// Returns the title of the document with the given URL.
+    ///
+    /// **Arguments**
+    ///
+    /// * `url` - The URL of the document.
+    ///
+    /// **Returns**
+    ///
+    /// The title, or `None` if the document has no title.
+    pub fn title(&self, url: &str) -> Option<&str> {
+        self.titles.get(url).map(|t| t.as_str())
+    }
+
+    /// Suggests completions for a partially typed query.
+    ///
+    /// The last word of the prefix is completed from the term dictionary, weighted by
+    /// document frequency. Optionally whole document titles starting with the prefix are
+    /// suggested as well, weighted by the number of documents carrying them.
+    ///
+    /// **Arguments**
+    ///
+    /// * `prefix` - The partially typed query.
+    /// * `size` - The maximum number of suggestions.
+    /// * `include_titles` - Whether to also suggest document titles.
+    ///
+    /// **Returns**
+    ///
+    /// The suggestions, heaviest first.
+    pub fn suggest(&self, prefix: &str, size: usize, include_titles: bool) -> Vec<Suggestion> {
+        let mut words = analyze(prefix);
+        let Some(last_word) = words.pop() else {
+            return Vec::new();
+        };
+        let leading_words = words.iter().map(|w| format!("{} ", w)).collect::<String>();
+
+        let mut suggestions: Vec<Suggestion> = self
+            .term_completions
+            .complete(&last_word, size)
+            .into_iter()
+            .map(|(term, weight)| Suggestion {
+                text: format!("{}{}", leading_words, term),
+                weight,
+                source: SuggestionSource::Term,
+            })
+            .collect();
+
+        if include_titles {
+            let title_prefix = normalize_string(prefix);
+            let titles = self.title_completions.complete(&title_prefix, size);
+            suggestions.extend(titles.into_iter().map(|(key, weight)| Suggestion {
+                text: self.titles[self.title_index[key].first().unwrap()].clone(),
+                weight,
+                source: SuggestionSource::Title,
+            }));
+            suggestions.sort_by(Suggestion::rank);
+            suggestions.truncate(size);
+        }
+        suggestions
     }
 
     /// Bulk indexes multiple documents.
@@ -212,6 +1470,230 @@ impl SearchEngine {
         }
     }
 
+    /// Returns the stored content of the document with the given URL.
+    ///
+    /// **Arguments**
+    ///
+    /// * `url` - The URL of the document.
+    ///
+    /// **Returns**
+    ///
+    /// The document content, or `None` if the URL is not indexed.
+    pub fn document(&self, url: &str) -> Option<&str> {
+        self.documents.get(url).map(|d| d.as_str())
+    }
+
+    /// Finds the indexed documents that are near-duplicates of a document.
+    ///
+    /// Two documents are near-duplicates when their SimHash signatures differ in at most
+    /// `MAX_DUPLICATE_DISTANCE` bits, e.g. mirrored or syndicated copies of the same page.
+    ///
+    /// **Arguments**
+    ///
+    /// * `url` - The URL of the document.
+    ///
+    /// **Returns**
+    ///
+    /// The URLs of the near-duplicates in order, without the document itself.
+    pub fn near_duplicates(&self, url: &str) -> Vec<String> {
+        let Some(&signature) = self.signatures.get(url) else {
+            return Vec::new();
+        };
+        self.signatures
+            .iter()
+            .filter(|(other, &other_signature)| {
+                *other != url && is_near_duplicate(signature, other_signature)
+            })
+            .map(|(other, _)| other.clone())
+            .collect()
+    }
+
+    /// Keeps only the best hit of every cluster of near-duplicates.
+    ///
+    /// The hits are visited by descending score, ties broken by URL, and a hit is dropped
+    /// when it is a near-duplicate of a hit that was kept.
+    ///
+    /// **Arguments**
+    ///
+    /// * `results` - The hits with their scores, collapsed in place.
+    pub fn collapse_duplicates(&self, results: &mut HashMap<String, f64>) {
+        let mut ranked: Vec<(&String, f64)> = results.iter().map(|(u, s)| (u, *s)).collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+
+        // Near-duplicates agree on at least one band, so only hits sharing a band are compared
+        let mut kept: HashMap<(usize, u64), Vec<u64>> = HashMap::new();
+        let mut dropped = Vec::new();
+        for (url, _) in ranked {
+            let Some(&signature) = self.signatures.get(url) else {
+                continue;
+            };
+            let bands = bands(signature);
+            let duplicate = bands.iter().any(|band| {
+                kept.get(band).is_some_and(|signatures| {
+                    signatures.iter().any(|s| is_near_duplicate(*s, signature))
+                })
+            });
+            if duplicate {
+                dropped.push(url.clone());
+            } else {
+                for band in bands {
+                    kept.entry(band).or_default().push(signature);
+                }
+            }
+        }
+        for url in dropped {
+            results.remove(&url);
+        }
+    }
+
+    /// Picks the terms that best characterize a document, by TF-IDF.
+    ///
+    /// The terms are taken from the stored content and weighed by their frequency in the
+    /// postings of the document and their IDF. Terms no other document contains are skipped
+    /// because they cannot find related documents.
+    ///
+    /// **Arguments**
+    ///
+    /// * `url` - The URL of the document.
+    /// * `max_terms` - The maximum number of terms to pick.
+    ///
+    /// **Returns**
+    ///
+    /// The terms with their TF-IDF relative to the best term, best first,
+    /// or `None` if the URL is not indexed.
+    pub fn interesting_terms(&self, url: &str, max_terms: usize) -> Option<Vec<(String, f64)>> {
+        let content = self.documents.get(url)?;
+        let terms: BTreeSet<String> = analyze(content).into_iter().collect();
+        let mut weighted: Vec<(String, f64)> = terms
+            .into_iter()
+            .filter_map(|term| {
+                let postings = self.index.get(&term)?;
+                let freq = *postings.get(url)?;
+                (postings.len() > 1).then(|| {
+                    let weight = freq as f64 * self.idf(&term);
+                    (term, weight)
+                })
+            })
+            .collect();
+        weighted.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        weighted.truncate(max_terms);
+        let best = weighted.first().map_or(1.0, |(_, weight)| *weight);
+        Some(
+            weighted
+                .into_iter()
+                .map(|(term, weight)| (term, weight / best))
+                .collect(),
+        )
+    }
+
+    /// Finds documents similar to an indexed document (more like this).
+    ///
+    /// Runs the interesting terms of the document as a query, each term boosted by its
+    /// relative TF-IDF. The document itself is left out.
+    ///
+    /// **Arguments**
+    ///
+    /// * `url` - The URL of the source document.
+    /// * `max_terms` - The maximum number of terms to query for.
+    ///
+    /// **Returns**
+    ///
+    /// A HashMap containing the document URLs as keys and their relevance scores as values,
+    /// or `None` if the URL is not indexed.
+    pub fn similar(&self, url: &str, max_terms: usize) -> Option<HashMap<String, f64>> {
+        let mut url_scores = HashMap::new();
+        for (kw, boost) in self.interesting_terms(url, max_terms)? {
+            let mut kw_urls_score = self.bm25(&kw);
+            kw_urls_score.remove(url);
+            kw_urls_score.values_mut().for_each(|score| *score *= boost);
+            update_url_scores(&mut url_scores, &kw_urls_score);
+        }
+        Some(url_scores)
+    }
+
+    /// Returns the keywords of the given query that occur in the given document.
+    ///
+    /// **Arguments**
+    ///
+    /// * `query` - The parsed query.
+    /// * `url` - The URL of the document.
+    ///
+    /// **Returns**
+    ///
+    /// A sorted vector of the matched keywords without duplicates.
+    pub fn matched_terms(&self, query: &Query, url: &str) -> Vec<String> {
+        // A query that fails to expand matches nothing
+        let mut terms = self
+            .expand_query(query)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(kw, _)| kw)
+            .filter(|kw| {
+                self.index
+                    .get(kw)
+                    .is_some_and(|urls| urls.contains_key(url))
+            })
+            .collect::<Vec<String>>();
+        terms.sort();
+        terms.dedup();
+        terms
+    }
+
+    /// Builds highlighted fragments of a document around the terms of the given query.
+    ///
+    /// **Arguments**
+    ///
+    /// * `query` - The parsed query.
+    /// * `url` - The URL of the document.
+    /// * `options` - Tags and fragment sizes to use.
+    ///
+    /// **Returns**
+    ///
+    /// The highlighted fragments, best first. Empty if the document does not match.
+    pub fn highlight(&self, query: &Query, url: &str, options: &HighlightOptions) -> Vec<String> {
+        let terms: HashSet<String> = self.matched_terms(query, url).into_iter().collect();
+        match self.document(url) {
+            Some(content) if !terms.is_empty() => highlight(content, &terms, options),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Proposes a corrected query by replacing every word that is missing from the index
+    /// with its most likely correction.
+    ///
+    /// **Arguments**
+    ///
+    /// * `query` - The search query.
+    /// * `query_log` - Optional counts of terms from previous successful queries, to prefer
+    ///   corrections users actually search for.
+    ///
+    /// **Returns**
+    ///
+    /// The corrected query, or `None` if nothing could be corrected.
+    pub fn suggest_correction(
+        &self,
+        query: &str,
+        query_log: Option<&HashMap<String, u32>>,
+    ) -> Option<String> {
+        let mut corrected = false;
+        let words: Vec<String> = analyze(query)
+            .into_iter()
+            .map(|word| {
+                if self.index.contains_key(&word) {
+                    return word;
+                }
+                match correct_term(&self.index, &word, |urls| urls.len(), query_log) {
+                    Some(correction) => {
+                        corrected = true;
+                        correction
+                    }
+                    None => word,
+                }
+            })
+            .collect();
+        corrected.then(|| words.join(" "))
+    }
+
     /// Returns the URLs and frequencies of a given keyword in the search engine's index.
     ///
     /// **Arguments**
@@ -226,10 +1708,28 @@ impl SearchEngine {
         self.index.get(&keyword).cloned().unwrap_or(HashMap::new())
     }
 
+    /// Returns how often a term occurs in the content of a document.
+    ///
+    /// **Arguments**
+    ///
+    /// * `keyword` - The exact dictionary term.
+    /// * `url` - The URL of the document.
+    ///
+    /// **Returns**
+    ///
+    /// The number of occurrences, 0 if the document does not contain the term.
+    pub fn term_frequency(&self, keyword: &str, url: &str) -> i32 {
+        self.index
+            .get(keyword)
+            .and_then(|urls| urls.get(url))
+            .copied()
+            .unwrap_or(0)
+    }
+
     // Write the current index to disk as binary to the configured location
     fn write_index_to_disk(&self) {
-        let index_hash_map = self.index.clone();
-        let documents_hash_map = self.documents.clone();
+        let index_hash_map = self.index.as_ref().clone();
+        let documents_hash_map = self.documents.as_ref().clone();
 
         let btree_index: BTreeMap<_, _> = index_hash_map.into_iter().collect();
         let btree_documents: BTreeMap<_, _> = documents_hash_map.into_iter().collect();
@@ -237,10 +1737,38 @@ impl SearchEngine {
         let data = index::SavedIndex {
             index_btree_map: btree_index,
             documents_btree_map: btree_documents,
+            titles_btree_map: self.titles.as_ref().clone(),
+            synonym_rules: self.synonyms.rules().to_vec(),
+            doc_values: self.doc_values.as_ref().clone(),
+            vector_fields: self.vector_fields.as_ref().clone(),
+            signatures: self.signatures.as_ref().clone(),
+            boosts: self.boosts.as_ref().clone(),
+            query_rules: self.query_rules.as_ref().clone(),
+            ranking_models: self.ranking_models.as_ref().clone(),
+            percolator_queries: self.percolator.queries().clone(),
+            search_templates: self
+                .search_templates
+                .iter()
+                .map(|(id, template)| (id.clone(), serde_json::to_string(template).unwrap()))
+                .collect(),
         };
 
-        let mut file = BufWriter::new(File::create("/tmp/search.db").unwrap());
+        // Write to a temporary file first, so readers never see a partially written index
+        static WRITES: AtomicUsize = AtomicUsize::new(0);
+        let temporary = format!(
+            "{}.{}.{}.tmp",
+            INDEX_PATH,
+            std::process::id(),
+            WRITES.fetch_add(1, Ordering::Relaxed)
+        );
+        let mut file = BufWriter::new(File::create(&temporary).unwrap());
+        file.write_all(&index::INDEX_MAGIC).unwrap();
+        file.write_all(&index::INDEX_FORMAT_VERSION.to_le_bytes())
+            .unwrap();
         serialize_into(&mut file, &data).unwrap();
+        file.flush().unwrap();
+        drop(file);
+        fs::rename(&temporary, INDEX_PATH).unwrap();
         log::debug!("Wrote Index as BTreeMap to Disk");
     }
 
@@ -251,24 +1779,52 @@ impl SearchEngine {
     }
 }
 
-fn get_index_from_disk() -> index::SavedIndex {
-    let file = File::open("/tmp/search.db");
-    let mut data = index::SavedIndex {
-        documents_btree_map: BTreeMap::new(),
-        index_btree_map: BTreeMap::new(),
+fn get_index_from_disk() -> Result<SavedIndex, IndexError> {
+    let bytes = match fs::read(INDEX_PATH) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(SavedIndex::default()),
+        Err(e) => return Err(e.into()),
     };
+    // The same encoding as `serialize_into`, but the whole file must be used
+    let options = bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .reject_trailing_bytes();
+    let corrupt = |e: bincode::Error| IndexError::Corrupt(e.to_string());
 
-    if file.is_ok() {
-        let reader = BufReader::new(file.unwrap());
-        data = deserialize_from(reader).unwrap();
-        log::debug!("Got BTreeMap Index from Disk");
+    let Some(versioned) = bytes.strip_prefix(&index::INDEX_MAGIC) else {
+        let saved_index: SavedIndexV0 = options.deserialize(&bytes).map_err(corrupt)?;
+        log::info!("Migrating Index on Disk from the unversioned format");
+        return Ok(migrate_v0(saved_index));
+    };
+    let (version, data) = versioned
+        .split_first_chunk::<4>()
+        .ok_or_else(|| IndexError::Corrupt(String::from("missing format version")))?;
+    match u32::from_le_bytes(*version) {
+        index::INDEX_FORMAT_VERSION => {
+            log::debug!("Got BTreeMap Index from Disk");
+            options.deserialize(data).map_err(corrupt)
+        }
+        version => Err(IndexError::UnsupportedVersion(version)),
     }
+}
 
-    data
+// Unversioned files only held postings and documents, the signatures are computed from the content
+fn migrate_v0(saved_index: SavedIndexV0) -> SavedIndex {
+    let signatures = saved_index
+        .documents_btree_map
+        .iter()
+        .filter_map(|(url, content)| Some((url.clone(), simhash(content)?)))
+        .collect();
+    SavedIndex {
+        index_btree_map: saved_index.index_btree_map,
+        documents_btree_map: saved_index.documents_btree_map,
+        signatures,
+        ..SavedIndex::default()
+    }
 }
 
 pub fn remove_index_from_disk() {
-    let result = fs::remove_file("/tmp/search.db");
+    let result = fs::remove_file(INDEX_PATH);
     if result.is_ok() {
         log::info!("Index was deleted");
     }
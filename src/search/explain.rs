@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+/// A tree describing how a score was computed.
+///
+/// The value of every node is derived from the values of its details, as described
+/// by its description.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Explanation {
+    pub value: f64,
+    pub description: String,
+    #[serde(default)]
+    pub details: Vec<Explanation>,
+}
+
+impl Explanation {
+    pub fn new(value: f64, description: &str) -> Explanation {
+        Explanation {
+            value,
+            description: description.to_string(),
+            details: Vec::new(),
+        }
+    }
+
+    pub fn with_details(mut self, details: Vec<Explanation>) -> Explanation {
+        self.details = details;
+        self
+    }
+}
@@ -0,0 +1,210 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::search::query::QueryError;
+use crate::types::field::{civil_from_days, days_from_civil, FieldValue};
+
+/// The number of keyword values returned by a facet without an explicit size.
+pub const DEFAULT_FACET_SIZE: usize = 10;
+
+const MILLIS_PER_DAY: i64 = 86_400_000;
+
+/// A requested facet, written as `field` or `field:option`.
+///
+/// The meaning of the option depends on the type of the field: the number of values
+/// for keyword fields, the bucket width for numeric fields and `day`, `week`, `month`
+/// or `year` for date fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FacetRequest {
+    pub field: String,
+    pub option: Option<String>,
+}
+
+/// The buckets of a facet, computed over all matching documents.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Facet {
+    /// The bucket keys with their document counts. Keyword buckets are ordered by count,
+    /// histogram buckets by their lower bound.
+    pub buckets: Vec<(FieldValue, usize)>,
+    /// The number of documents in keyword buckets that were cut off by the size.
+    pub other: usize,
+    /// The number of documents without a value of the facet type.
+    pub missing: usize,
+}
+
+impl FacetRequest {
+    /// Parses facets separated by `,`, e.g. `tag, year:10, published:month`.
+    ///
+    /// **Arguments**
+    ///
+    /// * `text` - The facets.
+    ///
+    /// **Returns**
+    ///
+    /// The parsed facets, or an error if one of them has no field name.
+    pub fn parse_all(text: &str) -> Result<Vec<FacetRequest>, QueryError> {
+        text.split(',')
+            .filter(|f| !f.trim().is_empty())
+            .map(|facet| {
+                let (field, option) = match facet.split_once(':') {
+                    Some((field, option)) => (field.trim(), Some(option.trim().to_string())),
+                    None => (facet.trim(), None),
+                };
+                if field.is_empty() || option.as_deref() == Some("") {
+                    return Err(QueryError::InvalidFacet(facet.trim().to_string()));
+                }
+                Ok(FacetRequest {
+                    field: field.to_string(),
+                    option,
+                })
+            })
+            .collect()
+    }
+
+    /// Counts the values of a doc-value column into buckets.
+    ///
+    /// The type of the first value in the column decides the kind of facet, values of
+    /// other types are counted as missing.
+    ///
+    /// **Arguments**
+    ///
+    /// * `column` - The doc-value column of the facet field, if any document has it.
+    /// * `values` - The value of the facet field for every matching document.
+    ///
+    /// **Returns**
+    ///
+    /// The facet, or an error if the option does not fit the type of the field.
+    pub fn compute<'a>(
+        &self,
+        column: Option<&BTreeMap<String, FieldValue>>,
+        values: impl Iterator<Item = Option<&'a FieldValue>>,
+    ) -> Result<Facet, QueryError> {
+        let invalid = || {
+            let option = self.option.as_deref().unwrap_or_default();
+            QueryError::InvalidFacet(format!("{}:{}", self.field, option))
+        };
+        let mut missing = 0;
+        let facet = match column.and_then(|column| column.values().next()) {
+            None => Facet {
+                buckets: Vec::new(),
+                other: 0,
+                missing: values.count(),
+            },
+            Some(FieldValue::Keyword(_)) => {
+                let size = match &self.option {
+                    Some(option) => option.parse().ok().filter(|s| *s > 0).ok_or_else(invalid)?,
+                    None => DEFAULT_FACET_SIZE,
+                };
+                let mut counts: HashMap<&str, usize> = HashMap::new();
+                for value in values {
+                    match value {
+                        Some(FieldValue::Keyword(keyword)) => {
+                            *counts.entry(keyword).or_default() += 1
+                        }
+                        _ => missing += 1,
+                    }
+                }
+                let mut buckets: Vec<(&str, usize)> = counts.into_iter().collect();
+                buckets.sort_by_key(|(key, count)| (Reverse(*count), *key));
+                let other = buckets.iter().skip(size).map(|(_, count)| count).sum();
+                buckets.truncate(size);
+                Facet {
+                    buckets: buckets
+                        .into_iter()
+                        .map(|(key, count)| (FieldValue::Keyword(key.to_string()), count))
+                        .collect(),
+                    other,
+                    missing,
+                }
+            }
+            Some(FieldValue::Number(_)) => {
+                let interval = match &self.option {
+                    Some(option) => option
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|i| i.is_finite() && *i > 0.0)
+                        .ok_or_else(invalid)?,
+                    None => 1.0,
+                };
+                let mut counts: BTreeMap<i64, usize> = BTreeMap::new();
+                for value in values {
+                    match value {
+                        Some(FieldValue::Number(number)) => {
+                            *counts
+                                .entry((number / interval).floor() as i64)
+                                .or_default() += 1
+                        }
+                        _ => missing += 1,
+                    }
+                }
+                Facet {
+                    buckets: counts
+                        .into_iter()
+                        .map(|(bucket, count)| {
+                            (FieldValue::Number(bucket as f64 * interval), count)
+                        })
+                        .collect(),
+                    other: 0,
+                    missing,
+                }
+            }
+            Some(FieldValue::Date(_)) => {
+                let interval = match self.option.as_deref() {
+                    Some(option) => CalendarInterval::parse(option).ok_or_else(invalid)?,
+                    None => CalendarInterval::Month,
+                };
+                let mut counts: BTreeMap<i64, usize> = BTreeMap::new();
+                for value in values {
+                    match value {
+                        Some(FieldValue::Date(date)) => {
+                            *counts.entry(interval.bucket_start(*date)).or_default() += 1
+                        }
+                        _ => missing += 1,
+                    }
+                }
+                Facet {
+                    buckets: counts
+                        .into_iter()
+                        .map(|(start, count)| (FieldValue::Date(start), count))
+                        .collect(),
+                    other: 0,
+                    missing,
+                }
+            }
+        };
+        Ok(facet)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CalendarInterval {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl CalendarInterval {
+    fn parse(text: &str) -> Option<CalendarInterval> {
+        match text.to_lowercase().as_str() {
+            "day" => Some(CalendarInterval::Day),
+            "week" => Some(CalendarInterval::Week),
+            "month" => Some(CalendarInterval::Month),
+            "year" => Some(CalendarInterval::Year),
+            _ => None,
+        }
+    }
+
+    // Weeks start on Monday, 1970-01-01 was a Thursday
+    fn bucket_start(self, millis: i64) -> i64 {
+        let days = millis.div_euclid(MILLIS_PER_DAY);
+        let (year, month, _) = civil_from_days(days);
+        let start = match self {
+            CalendarInterval::Day => days,
+            CalendarInterval::Week => days - (days + 3).rem_euclid(7),
+            CalendarInterval::Month => days_from_civil(year, month, 1),
+            CalendarInterval::Year => days_from_civil(year, 1, 1),
+        };
+        start * MILLIS_PER_DAY
+    }
+}
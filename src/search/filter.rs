@@ -0,0 +1,297 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+
+use crate::search::query::QueryError;
+use crate::types::field::{parse_date, FieldValue};
+
+/// The maximum number of filters whose passing documents are cached.
+pub const MAX_CACHED_FILTERS: usize = 256;
+
+/// A comparison applied by a filter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    Between,
+}
+
+// Two character operators come first so `>=` is not read as `>`
+const OPERATORS: [(&str, FilterOp); 6] = [
+    (">=", FilterOp::Gte),
+    ("<=", FilterOp::Lte),
+    ("!=", FilterOp::Ne),
+    ("=", FilterOp::Eq),
+    (">", FilterOp::Gt),
+    ("<", FilterOp::Lt),
+];
+
+/// A filter clause that restricts the matching documents by a field value without affecting scores.
+///
+/// Values are compared by the type of the stored field: numerically for numbers,
+/// chronologically for dates and lexicographically for keywords.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    pub field: String,
+    pub op: FilterOp,
+    pub values: Vec<String>,
+}
+
+impl Filter {
+    /// Parses filters separated by `;`, e.g. `tag = rust; year >= 2020`.
+    ///
+    /// **Arguments**
+    ///
+    /// * `text` - The filters.
+    ///
+    /// **Returns**
+    ///
+    /// The parsed filters, or an error if one of them is malformed.
+    pub fn parse_all(text: &str) -> Result<Vec<Filter>, QueryError> {
+        let mut start = 0;
+        mask_quoted(text)
+            .split(';')
+            .map(|part| {
+                let filter = &text[start..start + part.len()];
+                start += part.len() + 1;
+                filter
+            })
+            .filter(|f| !f.trim().is_empty())
+            .map(Filter::parse)
+            .collect()
+    }
+
+    /// Parses a single filter like `year >= 2020` or `published between 2020-01-01 and 2020-12-31`.
+    ///
+    /// Values may be wrapped in double quotes, e.g. `title = "a; b"`, so `;`, operators and
+    /// `and` inside them are read as part of the value. Values cannot contain double quotes.
+    ///
+    /// **Arguments**
+    ///
+    /// * `text` - The filter.
+    ///
+    /// **Returns**
+    ///
+    /// The parsed filter, or an error if it is malformed.
+    pub fn parse(text: &str) -> Result<Filter, QueryError> {
+        let invalid = || QueryError::InvalidFilter(text.trim().to_string());
+        let masked = mask_quoted(text);
+
+        if let Some(position) = masked.find(" between ") {
+            let range = position + " between ".len();
+            let and = masked[range..].find(" and ").ok_or_else(invalid)? + range;
+            let (from, to) = (&text[range..and], &text[and + " and ".len()..]);
+            return Filter::new(&text[..position], FilterOp::Between, &[from, to])
+                .ok_or_else(invalid);
+        }
+
+        let (position, operator, op) = OPERATORS
+            .iter()
+            .filter_map(|(operator, op)| masked.find(operator).map(|p| (p, *operator, *op)))
+            .min_by_key(|(position, operator, _)| (*position, std::cmp::Reverse(operator.len())))
+            .ok_or_else(invalid)?;
+        let field = &text[..position];
+        let value = &text[position + operator.len()..];
+        Filter::new(field, op, &[value]).ok_or_else(invalid)
+    }
+
+    fn new(field: &str, op: FilterOp, values: &[&str]) -> Option<Filter> {
+        let field = field.trim();
+        let values: Vec<String> = values
+            .iter()
+            .map(|v| {
+                let v = v.trim();
+                let unquoted = v.strip_prefix('"').and_then(|v| v.strip_suffix('"'));
+                unquoted.unwrap_or(v).to_string()
+            })
+            .collect();
+        if field.is_empty() || values.iter().any(|v| v.is_empty()) {
+            return None;
+        }
+        Some(Filter {
+            field: field.to_string(),
+            op,
+            values,
+        })
+    }
+
+    /// Checks that the values of this filter can be compared with a field of the given type.
+    ///
+    /// **Arguments**
+    ///
+    /// * `value` - A stored value of the field.
+    ///
+    /// **Returns**
+    ///
+    /// An error if a value is not a number for a numeric field or not a date for a date field,
+    /// which would otherwise silently match no document.
+    pub fn check(&self, value: &FieldValue) -> Result<(), QueryError> {
+        let (expected, valid): (&str, fn(&str) -> bool) = match value {
+            FieldValue::Keyword(_) => return Ok(()),
+            FieldValue::Number(_) => ("a number", |raw| raw.parse::<f64>().is_ok()),
+            FieldValue::Date(_) => ("a date", |raw| parse_date(raw).is_some()),
+        };
+        match self.values.iter().find(|raw| !valid(raw)) {
+            Some(raw) => Err(QueryError::InvalidFilterValue {
+                field: self.field.clone(),
+                value: raw.clone(),
+                expected: expected.to_string(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks if a stored field value passes this filter.
+    ///
+    /// **Arguments**
+    ///
+    /// * `value` - The stored value, or `None` if the document does not have the field.
+    ///
+    /// **Returns**
+    ///
+    /// True if the value passes. Documents without the field only pass `!=` filters.
+    pub fn matches(&self, value: Option<&FieldValue>) -> bool {
+        let Some(value) = value else {
+            return self.op == FilterOp::Ne;
+        };
+        let compare = |raw: &str| -> Option<Ordering> {
+            match value {
+                FieldValue::Keyword(keyword) => Some(keyword.as_str().cmp(raw)),
+                FieldValue::Number(number) => number.partial_cmp(&raw.parse::<f64>().ok()?),
+                FieldValue::Date(date) => Some(date.cmp(&parse_date(raw)?)),
+            }
+        };
+        let ordering = compare(&self.values[0]);
+        match self.op {
+            FilterOp::Eq => ordering == Some(Ordering::Equal),
+            FilterOp::Ne => ordering != Some(Ordering::Equal),
+            FilterOp::Gt => ordering == Some(Ordering::Greater),
+            FilterOp::Gte => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+            FilterOp::Lt => ordering == Some(Ordering::Less),
+            FilterOp::Lte => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            FilterOp::Between => {
+                matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
+                    && matches!(
+                        compare(&self.values[1]),
+                        Some(Ordering::Less | Ordering::Equal)
+                    )
+            }
+        }
+    }
+}
+
+// Blanks out the text inside double quotes, keeping byte offsets, so separators and operators
+// are only found outside of quoted values
+fn mask_quoted(text: &str) -> String {
+    let mut quoted = false;
+    let mut masked = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c == '"' {
+            quoted = !quoted;
+            masked.push(c);
+        } else if quoted {
+            masked.extend(std::iter::repeat_n('_', c.len_utf8()));
+        } else {
+            masked.push(c);
+        }
+    }
+    masked
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.op {
+            FilterOp::Between => write!(
+                f,
+                "{} between {} and {}",
+                self.field,
+                quote(&self.values[0]),
+                quote(&self.values[1])
+            ),
+            op => {
+                let (operator, _) = OPERATORS.iter().find(|(_, o)| *o == op).unwrap();
+                write!(f, "{} {} {}", self.field, operator, quote(&self.values[0]))
+            }
+        }
+    }
+}
+
+// Quotes values that would otherwise be read differently, so the text parses back to the filter
+fn quote(value: &str) -> Cow<'_, str> {
+    if value.contains([';', '<', '>', '=', '!']) || value.contains(" and ") {
+        Cow::Owned(format!("\"{}\"", value))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// The documents passing recently used filters, keyed by the canonical filter text.
+///
+/// At most `MAX_CACHED_FILTERS` filters are kept, the least recently used one is evicted
+/// when another one is added.
+#[derive(Debug, Default)]
+pub struct FilterCache {
+    // The passing documents and when the filter was last used
+    entries: HashMap<String, (Arc<HashSet<String>>, u64)>,
+    clock: u64,
+}
+
+impl FilterCache {
+    /// Returns the documents passing a cached filter and marks it as recently used.
+    ///
+    /// **Arguments**
+    ///
+    /// * `key` - The canonical text of the filter.
+    ///
+    /// **Returns**
+    ///
+    /// The passing documents, or `None` if the filter is not cached.
+    pub fn get(&mut self, key: &str) -> Option<Arc<HashSet<String>>> {
+        self.clock += 1;
+        let (passing, last_used) = self.entries.get_mut(key)?;
+        *last_used = self.clock;
+        Some(passing.clone())
+    }
+
+    /// Caches the documents passing a filter, evicting the least recently used filter if full.
+    ///
+    /// **Arguments**
+    ///
+    /// * `key` - The canonical text of the filter.
+    /// * `passing` - The documents passing the filter.
+    pub fn insert(&mut self, key: String, passing: Arc<HashSet<String>>) {
+        self.clock += 1;
+        if self.entries.len() >= MAX_CACHED_FILTERS && !self.entries.contains_key(&key) {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(key, (passing, self.clock));
+    }
+
+    /// Returns the number of cached filters.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if no filter is cached.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns true if a filter is cached, without marking it as used.
+    pub fn contains(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+}
@@ -0,0 +1,334 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::search::engine::SearchEngine;
+use crate::search::explain::Explanation;
+use crate::search::query::QueryError;
+use crate::types::field::{parse_date, parse_duration, FieldValue};
+
+/// How the text score and the value of the functions are combined.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoostMode {
+    /// The text score times the functions.
+    #[default]
+    Multiply,
+    /// The text score plus the functions.
+    Sum,
+    /// Only the functions, the text score is ignored.
+    Replace,
+}
+
+/// The shape of a decay function.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DecayFunction {
+    Gauss,
+    Exp,
+    Linear,
+}
+
+/// A modifier applied to a field value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Modifier {
+    #[default]
+    None,
+    /// `log10(1 + value)`
+    Log1p,
+    /// `log10(2 + value)`
+    Log2p,
+    /// `ln(1 + value)`
+    Ln1p,
+    /// `ln(2 + value)`
+    Ln2p,
+    Sqrt,
+    Square,
+    Reciprocal,
+}
+
+impl Modifier {
+    fn apply(&self, value: f64) -> f64 {
+        match self {
+            Modifier::None => value,
+            Modifier::Log1p => (1.0 + value).log10(),
+            Modifier::Log2p => (2.0 + value).log10(),
+            Modifier::Ln1p => value.ln_1p(),
+            Modifier::Ln2p => (2.0 + value).ln(),
+            Modifier::Sqrt => value.sqrt(),
+            Modifier::Square => value * value,
+            Modifier::Reciprocal => 1.0 / value,
+        }
+    }
+}
+
+impl fmt::Display for Modifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Modifier::None => "none",
+            Modifier::Log1p => "log1p",
+            Modifier::Log2p => "log2p",
+            Modifier::Ln1p => "ln1p",
+            Modifier::Ln2p => "ln2p",
+            Modifier::Sqrt => "sqrt",
+            Modifier::Square => "square",
+            Modifier::Reciprocal => "reciprocal",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A function computing a factor for a document from its fields.
+///
+/// Documents that lack the field of a decay or field value function are not affected by it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoreFunction {
+    /// Decays from 1.0 at `origin` to `decay` at `scale` away from it, on a date field.
+    Decay {
+        field: String,
+        function: DecayFunction,
+        /// A date, or `now` if not given.
+        #[serde(default)]
+        origin: Option<String>,
+        /// A duration like `12h`, `7d` or `4w`.
+        scale: String,
+        /// Distances up to this duration are not decayed.
+        #[serde(default)]
+        offset: Option<String>,
+        #[serde(default = "default_decay")]
+        decay: f64,
+    },
+    /// The modified value of a numeric field, e.g. `log1p` of a popularity count.
+    FieldValueFactor {
+        field: String,
+        #[serde(default = "default_factor")]
+        factor: f64,
+        #[serde(default)]
+        modifier: Modifier,
+        /// The value used for documents without the field.
+        #[serde(default)]
+        missing: Option<f64>,
+    },
+    /// The static boost of the document set at index time, 1.0 if it has none.
+    DocumentBoost,
+}
+
+fn default_decay() -> f64 {
+    0.5
+}
+
+fn default_factor() -> f64 {
+    1.0
+}
+
+/// Functions adjusting the text score of every hit.
+///
+/// The values of all functions are multiplied and combined with the text score by the
+/// boost mode.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FunctionScore {
+    pub functions: Vec<ScoreFunction>,
+    #[serde(default)]
+    pub boost_mode: BoostMode,
+}
+
+impl FunctionScore {
+    /// Checks that the origins, scales, offsets and decays of the functions are valid.
+    ///
+    /// **Returns**
+    ///
+    /// An error describing the first invalid function.
+    pub fn validate(&self) -> Result<(), QueryError> {
+        for function in &self.functions {
+            let invalid = |reason: &str| QueryError::InvalidFunctionScore(reason.to_string());
+            match function {
+                ScoreFunction::Decay {
+                    origin,
+                    scale,
+                    offset,
+                    decay,
+                    ..
+                } => {
+                    if let Some(origin) = origin.as_deref().filter(|o| *o != "now") {
+                        parse_date(origin)
+                            .ok_or_else(|| invalid(&format!("origin '{}'", origin)))?;
+                    }
+                    parse_duration(scale)
+                        .filter(|scale| *scale > 0)
+                        .ok_or_else(|| invalid(&format!("scale '{}'", scale)))?;
+                    if let Some(offset) = offset {
+                        parse_duration(offset)
+                            .ok_or_else(|| invalid(&format!("offset '{}'", offset)))?;
+                    }
+                    if !(*decay > 0.0 && *decay < 1.0) {
+                        return Err(invalid(&format!(
+                            "decay {}, expected between 0 and 1",
+                            decay
+                        )));
+                    }
+                }
+                ScoreFunction::FieldValueFactor {
+                    factor, missing, ..
+                } => {
+                    if !factor.is_finite() || missing.is_some_and(|m| !m.is_finite()) {
+                        return Err(invalid("factor and missing must be finite"));
+                    }
+                }
+                ScoreFunction::DocumentBoost => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Computes the final score of a document.
+    ///
+    /// **Arguments**
+    ///
+    /// * `engine` - The engine holding the fields and boosts of the document.
+    /// * `url` - The URL of the document.
+    /// * `score` - The text score of the document.
+    /// * `now` - The current time in milliseconds since the Unix epoch, the default origin.
+    ///
+    /// **Returns**
+    ///
+    /// The final score. Functions must have been validated.
+    pub fn score(&self, engine: &SearchEngine, url: &str, score: f64, now: i64) -> f64 {
+        let value = self
+            .functions
+            .iter()
+            .map(|function| function.value(engine, url, now).unwrap_or(1.0))
+            .product();
+        self.combine(score, value)
+    }
+
+    /// Explains the final score of a document, wrapping the explanation of its text score.
+    ///
+    /// **Arguments**
+    ///
+    /// * `engine` - The engine holding the fields and boosts of the document.
+    /// * `url` - The URL of the document.
+    /// * `text` - The explanation of the text score.
+    /// * `now` - The current time in milliseconds since the Unix epoch, the default origin.
+    ///
+    /// **Returns**
+    ///
+    /// The explanation, whose value equals `score`.
+    pub fn explain(
+        &self,
+        engine: &SearchEngine,
+        url: &str,
+        text: Explanation,
+        now: i64,
+    ) -> Explanation {
+        let details: Vec<Explanation> = self
+            .functions
+            .iter()
+            .map(|function| match function.value(engine, url, now) {
+                Some(value) => Explanation::new(value, &function.to_string()),
+                None => Explanation::new(1.0, &format!("{}, field missing", function)),
+            })
+            .collect();
+        let value = details.iter().map(|d| d.value).product();
+        let functions = Explanation::new(value, "functions, product of:").with_details(details);
+        let description = match self.boost_mode {
+            BoostMode::Multiply => "function score, text score * functions:",
+            BoostMode::Sum => "function score, text score + functions:",
+            BoostMode::Replace => "function score, functions replace the text score:",
+        };
+        Explanation::new(self.combine(text.value, value), description)
+            .with_details(vec![text, functions])
+    }
+
+    fn combine(&self, score: f64, value: f64) -> f64 {
+        match self.boost_mode {
+            BoostMode::Multiply => score * value,
+            BoostMode::Sum => score + value,
+            BoostMode::Replace => value,
+        }
+    }
+}
+
+impl ScoreFunction {
+    // The value of the function, or `None` if the document lacks the field
+    fn value(&self, engine: &SearchEngine, url: &str, now: i64) -> Option<f64> {
+        match self {
+            ScoreFunction::Decay {
+                field,
+                function,
+                origin,
+                scale,
+                offset,
+                decay,
+            } => {
+                let Some(FieldValue::Date(date)) = engine.field(url, field) else {
+                    return None;
+                };
+                let origin = origin
+                    .as_deref()
+                    .filter(|o| *o != "now")
+                    .and_then(parse_date)
+                    .unwrap_or(now);
+                let scale = parse_duration(scale)? as f64;
+                let offset = offset.as_deref().and_then(parse_duration).unwrap_or(0);
+                let distance = ((date - origin).abs() - offset).max(0) as f64;
+                let value = match function {
+                    DecayFunction::Gauss => {
+                        (decay.ln() * distance * distance / (scale * scale)).exp()
+                    }
+                    DecayFunction::Exp => (decay.ln() * distance / scale).exp(),
+                    DecayFunction::Linear => {
+                        let width = scale / (1.0 - decay);
+                        ((width - distance) / width).max(0.0)
+                    }
+                };
+                Some(value)
+            }
+            ScoreFunction::FieldValueFactor {
+                field,
+                factor,
+                modifier,
+                missing,
+            } => {
+                let value = match engine.field(url, field) {
+                    Some(FieldValue::Number(number)) => *number,
+                    _ => (*missing)?,
+                };
+                // Modifiers of negative values may be undefined, those do not score at all
+                let value = modifier.apply(factor * value);
+                Some(if value.is_finite() {
+                    value.max(0.0)
+                } else {
+                    0.0
+                })
+            }
+            ScoreFunction::DocumentBoost => Some(engine.boost(url)),
+        }
+    }
+}
+
+impl fmt::Display for ScoreFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScoreFunction::Decay {
+                field,
+                function,
+                scale,
+                decay,
+                ..
+            } => write!(
+                f,
+                "{:?} decay of '{}' with scale {} and decay {}",
+                function, field, scale, decay
+            ),
+            ScoreFunction::FieldValueFactor {
+                field,
+                factor,
+                modifier,
+                ..
+            } => write!(f, "{}({} * '{}')", modifier, factor, field),
+            ScoreFunction::DocumentBoost => write!(f, "document boost"),
+        }
+    }
+}
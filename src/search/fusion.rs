@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::search::explain::Explanation;
+use crate::search::query::QueryError;
+
+/// The rank constant of reciprocal rank fusion used when none is given.
+pub const DEFAULT_RANK_CONSTANT: f64 = 60.0;
+
+/// How the rankings of the lexical and the vector retriever are combined.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Fusion {
+    /// Reciprocal rank fusion: every retriever adds `1 / (rank_constant + rank)`.
+    Rrf {
+        #[serde(default = "default_rank_constant")]
+        rank_constant: f64,
+    },
+    /// Weighted sum of the scores of both retrievers after min-max normalization.
+    Linear {
+        #[serde(default = "default_weight")]
+        lexical_weight: f64,
+        #[serde(default = "default_weight")]
+        vector_weight: f64,
+    },
+}
+
+impl Default for Fusion {
+    fn default() -> Self {
+        Fusion::Rrf {
+            rank_constant: DEFAULT_RANK_CONSTANT,
+        }
+    }
+}
+
+impl Fusion {
+    /// Checks that the rank constant is positive and the weights are not negative.
+    ///
+    /// **Returns**
+    ///
+    /// An error describing the first invalid setting.
+    pub fn validate(&self) -> Result<(), QueryError> {
+        let invalid = |reason: &str| Err(QueryError::InvalidFusion(reason.to_string()));
+        match self {
+            Fusion::Rrf { rank_constant }
+                if !(rank_constant.is_finite() && *rank_constant > 0.0) =>
+            {
+                invalid("rank_constant must be a positive number")
+            }
+            Fusion::Linear {
+                lexical_weight,
+                vector_weight,
+            } if [lexical_weight, vector_weight]
+                .iter()
+                .any(|weight| !(weight.is_finite() && **weight >= 0.0)) =>
+            {
+                invalid("weights must be finite and not negative")
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn default_rank_constant() -> f64 {
+    DEFAULT_RANK_CONSTANT
+}
+
+fn default_weight() -> f64 {
+    0.5
+}
+
+/// Fuses the rankings of the lexical and the vector retriever into one.
+///
+/// **Arguments**
+///
+/// * `lexical` - The lexical hits with their BM25 scores, best first.
+/// * `vector` - The vector hits with their similarity scores, best first.
+/// * `fusion` - The fusion method.
+///
+/// **Returns**
+///
+/// The fused hits, best first and ties broken by URL, with the contribution of every retriever.
+pub fn fuse(
+    lexical: &[(String, f64)],
+    vector: &[(String, f64)],
+    fusion: &Fusion,
+) -> Vec<(String, f64, Explanation)> {
+    let (lexical_weight, vector_weight) = match fusion {
+        Fusion::Rrf { .. } => (1.0, 1.0),
+        Fusion::Linear {
+            lexical_weight,
+            vector_weight,
+        } => (*lexical_weight, *vector_weight),
+    };
+    let mut contributions: HashMap<&str, Vec<Explanation>> = HashMap::new();
+    for (retriever, hits, weight) in [
+        ("lexical", lexical, lexical_weight),
+        ("vector", vector, vector_weight),
+    ] {
+        let (min, max) = hits
+            .iter()
+            .fold((f64::MAX, f64::MIN), |(min, max), (_, s)| {
+                (min.min(*s), max.max(*s))
+            });
+        for (rank, (url, score)) in hits.iter().enumerate() {
+            let rank = rank + 1;
+            let contribution = match fusion {
+                Fusion::Rrf { rank_constant } => Explanation::new(
+                    1.0 / (rank_constant + rank as f64),
+                    &format!(
+                        "{} rank {}, 1 / ({} + rank)",
+                        retriever, rank, rank_constant
+                    ),
+                )
+                .with_details(vec![Explanation::new(
+                    *score,
+                    &format!("{} score", retriever),
+                )]),
+                Fusion::Linear { .. } => {
+                    // A retriever whose hits all score the same gives each of them full weight
+                    let normalized = if max > min {
+                        (score - min) / (max - min)
+                    } else {
+                        1.0
+                    };
+                    Explanation::new(
+                        weight * normalized,
+                        &format!("{} weight {} * normalized score", retriever, weight),
+                    )
+                    .with_details(vec![Explanation::new(
+                        normalized,
+                        &format!(
+                            "min-max normalized {} score {} within [{}, {}]",
+                            retriever, score, min, max
+                        ),
+                    )])
+                }
+            };
+            contributions.entry(url).or_default().push(contribution);
+        }
+    }
+
+    let description = match fusion {
+        Fusion::Rrf { .. } => "reciprocal rank fusion, sum of:",
+        Fusion::Linear { .. } => "linear combination, sum of:",
+    };
+    let mut fused: Vec<(String, f64, Explanation)> = contributions
+        .into_iter()
+        .map(|(url, details)| {
+            let score = details.iter().map(|d| d.value).sum();
+            (
+                url.to_string(),
+                score,
+                Explanation::new(score, description).with_details(details),
+            )
+        })
+        .collect();
+    fused.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    fused
+}
@@ -0,0 +1,91 @@
+use std::collections::BTreeMap;
+use std::ops::Bound;
+use std::sync::OnceLock;
+
+use levenshtein_automata::{Distance, LevenshteinAutomatonBuilder, SINK_STATE};
+
+use crate::search::query::MAX_FUZZY_DISTANCE;
+
+// Building the automaton builders is expensive, so they are created once per distance
+fn automaton_builder(max_distance: u8) -> &'static LevenshteinAutomatonBuilder {
+    static BUILDERS: OnceLock<Vec<LevenshteinAutomatonBuilder>> = OnceLock::new();
+    let builders = BUILDERS.get_or_init(|| {
+        (0..=MAX_FUZZY_DISTANCE)
+            .map(|distance| LevenshteinAutomatonBuilder::new(distance, true))
+            .collect()
+    });
+    &builders[max_distance.min(MAX_FUZZY_DISTANCE) as usize]
+}
+
+/// Finds all terms of a sorted dictionary within the given edit distance of a term.
+///
+/// The dictionary is walked with a Levenshtein automaton. As soon as a key prefix can no
+/// longer match, all keys sharing that prefix are skipped with a range seek.
+/// Transpositions count as a single edit.
+///
+/// **Arguments**
+///
+/// * `dictionary` - The sorted term dictionary.
+/// * `term` - The term to look up.
+/// * `max_distance` - The maximum edit distance.
+///
+/// **Returns**
+///
+/// The matching terms with their edit distance, in dictionary order.
+pub fn fuzzy_terms<V>(
+    dictionary: &BTreeMap<String, V>,
+    term: &str,
+    max_distance: u8,
+) -> Vec<(String, u8)> {
+    let dfa = automaton_builder(max_distance).build_dfa(term);
+    let mut matches = Vec::new();
+    let mut lower: Bound<String> = Bound::Unbounded;
+
+    loop {
+        let bounds = (lower.as_ref().map(|s| s.as_str()), Bound::Unbounded);
+        let Some(key) = dictionary.range::<str, _>(bounds).next().map(|(k, _)| k) else {
+            break;
+        };
+
+        let mut state = dfa.initial_state();
+        let mut dead_prefix_end = None;
+        for (position, c) in key.char_indices() {
+            let mut buffer = [0; 4];
+            for byte in c.encode_utf8(&mut buffer).bytes() {
+                state = dfa.transition(state, byte);
+            }
+            if state == SINK_STATE {
+                dead_prefix_end = Some(position + c.len_utf8());
+                break;
+            }
+        }
+
+        match dead_prefix_end {
+            None => {
+                if let Distance::Exact(distance) = dfa.distance(state) {
+                    matches.push((key.clone(), distance));
+                }
+                lower = Bound::Excluded(key.clone());
+            }
+            Some(end) => match prefix_successor(&key[..end]) {
+                Some(successor) => lower = Bound::Included(successor),
+                None => break,
+            },
+        }
+    }
+
+    matches
+}
+
+// Returns the smallest string that is greater than every string starting with the given prefix
+fn prefix_successor(prefix: &str) -> Option<String> {
+    let mut chars: Vec<char> = prefix.chars().collect();
+    while let Some(last) = chars.pop() {
+        let next = (last as u32 + 1..=char::MAX as u32).find_map(char::from_u32);
+        if let Some(next) = next {
+            chars.push(next);
+            return Some(chars.into_iter().collect());
+        }
+    }
+    None
+}
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+/// The number of hits returned per group when no group size is given.
+pub const DEFAULT_GROUP_SIZE: usize = 3;
+
+/// What search hits are grouped by.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GroupBy {
+    /// The host of the document URL, written as `_domain`.
+    Domain,
+    /// The value of a keyword field.
+    Field(String),
+}
+
+impl GroupBy {
+    /// Parses `_domain` or the name of a keyword field.
+    ///
+    /// **Arguments**
+    ///
+    /// * `text` - The text to parse.
+    ///
+    /// **Returns**
+    ///
+    /// What to group by, or `None` if the text is empty.
+    pub fn parse(text: &str) -> Option<GroupBy> {
+        match text.trim() {
+            "" => None,
+            "_domain" => Some(GroupBy::Domain),
+            field => Some(GroupBy::Field(field.to_string())),
+        }
+    }
+}
+
+/// A group of hits sharing the same key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    /// The shared key, `None` for the hits without one.
+    pub key: Option<String>,
+    /// The number of hits in the group.
+    pub count: usize,
+    /// The best hits of the group, in ranking order.
+    pub hits: Vec<(String, f64)>,
+}
+
+/// Returns the host of a URL without the port and a leading `www.`, lowercased.
+///
+/// **Arguments**
+///
+/// * `url` - The URL.
+///
+/// **Returns**
+///
+/// The domain, or `None` if the URL has no host.
+pub fn domain(url: &str) -> Option<String> {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?;
+    let host = host.split(':').next()?.to_lowercase();
+    let host = host
+        .strip_prefix("www.")
+        .map(str::to_string)
+        .unwrap_or(host);
+    (!host.is_empty()).then_some(host)
+}
+
+/// Groups ranked hits by their keys.
+///
+/// **Arguments**
+///
+/// * `ranked` - The hits, best first.
+/// * `key` - Returns the key of a hit.
+/// * `group_size` - The maximum number of hits kept per group.
+///
+/// **Returns**
+///
+/// The groups, ordered by their best hit.
+pub fn group(
+    ranked: Vec<(String, f64)>,
+    key: impl Fn(&str) -> Option<String>,
+    group_size: usize,
+) -> Vec<Group> {
+    let mut groups: Vec<Group> = Vec::new();
+    let mut positions: HashMap<Option<String>, usize> = HashMap::new();
+    for (url, score) in ranked {
+        let key = key(&url);
+        let position = *positions.entry(key.clone()).or_insert_with(|| {
+            groups.push(Group {
+                key,
+                count: 0,
+                hits: Vec::new(),
+            });
+            groups.len() - 1
+        });
+        let group = &mut groups[position];
+        group.count += 1;
+        if group.hits.len() < group_size {
+            group.hits.push((url, score));
+        }
+    }
+    groups
+}
@@ -0,0 +1,161 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::search::analyzer::{tokenize, Token};
+
+/// How the content inside fragments is encoded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoder {
+    /// Escapes HTML, so fragments can be inserted into pages as they are.
+    #[default]
+    Html,
+    /// Copies the content as it is, for clients that do not render HTML.
+    None,
+}
+
+/// Options controlling how highlighted fragments are built.
+#[derive(Debug, Clone)]
+pub struct HighlightOptions {
+    /// Inserted before each matched term.
+    pub pre_tag: String,
+    /// Inserted after each matched term.
+    pub post_tag: String,
+    /// Maximum length of a fragment in bytes.
+    pub fragment_size: usize,
+    /// Maximum number of fragments returned per document.
+    pub number_of_fragments: usize,
+    /// The encoding of the content, the tags are inserted as they are.
+    pub encoder: Encoder,
+}
+
+impl Default for HighlightOptions {
+    fn default() -> Self {
+        HighlightOptions {
+            pre_tag: String::from("<em>"),
+            post_tag: String::from("</em>"),
+            fragment_size: 100,
+            number_of_fragments: 3,
+            encoder: Encoder::Html,
+        }
+    }
+}
+
+/// Builds highlighted fragments of the given content around the given terms.
+///
+/// Every match is the center of a candidate fragment. Candidates are ranked by
+/// the number of matches they contain and the best non-overlapping ones are returned.
+///
+/// **Arguments**
+///
+/// * `content` - The stored document content.
+/// * `terms` - The analyzed terms to highlight.
+/// * `options` - Tags and fragment sizes to use.
+///
+/// **Returns**
+///
+/// The fragments, best first.
+pub fn highlight(
+    content: &str,
+    terms: &HashSet<String>,
+    options: &HighlightOptions,
+) -> Vec<String> {
+    let tokens = tokenize(content);
+    let is_match: Vec<bool> = tokens.iter().map(|t| terms.contains(&t.term)).collect();
+
+    let mut candidates: Vec<(usize, usize, usize)> = Vec::new();
+    for (position, _) in is_match.iter().enumerate().filter(|(_, m)| **m) {
+        let (lo, hi) = expand_window(&tokens, position, options.fragment_size);
+        let density = is_match[lo..=hi].iter().filter(|m| **m).count();
+        candidates.push((density, lo, hi));
+    }
+    // Densest first, earlier fragments win ties
+    candidates.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+    let mut selected: Vec<(usize, usize)> = Vec::new();
+    for (_, lo, hi) in candidates {
+        if selected.len() >= options.number_of_fragments {
+            break;
+        }
+        if selected.iter().all(|(s_lo, s_hi)| hi < *s_lo || lo > *s_hi) {
+            selected.push((lo, hi));
+        }
+    }
+
+    selected
+        .into_iter()
+        .map(|(lo, hi)| render(content, &tokens[lo..=hi], &is_match[lo..=hi], options))
+        .collect()
+}
+
+// Grows a window of tokens around the given position, alternating left and right,
+// as long as the covered text fits into the fragment size.
+fn expand_window(tokens: &[Token], position: usize, fragment_size: usize) -> (usize, usize) {
+    let (mut lo, mut hi) = (position, position);
+    loop {
+        let mut grown = false;
+        if hi + 1 < tokens.len() && tokens[hi + 1].end - tokens[lo].start <= fragment_size {
+            hi += 1;
+            grown = true;
+        }
+        if lo > 0 && tokens[hi].end - tokens[lo - 1].start <= fragment_size {
+            lo -= 1;
+            grown = true;
+        }
+        if !grown {
+            return (lo, hi);
+        }
+    }
+}
+
+fn render(
+    content: &str,
+    tokens: &[Token],
+    is_match: &[bool],
+    options: &HighlightOptions,
+) -> String {
+    let mut fragment = String::new();
+    let mut cursor = tokens[0].start;
+    for (token, matched) in tokens.iter().zip(is_match) {
+        if *matched {
+            encode(
+                &mut fragment,
+                &content[cursor..token.start],
+                options.encoder,
+            );
+            fragment.push_str(&options.pre_tag);
+            encode(
+                &mut fragment,
+                &content[token.start..token.end],
+                options.encoder,
+            );
+            fragment.push_str(&options.post_tag);
+            cursor = token.end;
+        }
+    }
+    encode(
+        &mut fragment,
+        &content[cursor..tokens[tokens.len() - 1].end],
+        options.encoder,
+    );
+    fragment
+}
+
+// Appends stored content to a fragment, escaping the characters with a meaning in HTML
+fn encode(fragment: &mut String, text: &str, encoder: Encoder) {
+    if encoder == Encoder::None {
+        fragment.push_str(text);
+        return;
+    }
+    for c in text.chars() {
+        match c {
+            '&' => fragment.push_str("&amp;"),
+            '<' => fragment.push_str("&lt;"),
+            '>' => fragment.push_str("&gt;"),
+            '"' => fragment.push_str("&quot;"),
+            '\'' => fragment.push_str("&#x27;"),
+            c => fragment.push(c),
+        }
+    }
+}
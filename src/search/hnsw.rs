@@ -0,0 +1,439 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use serde::{Deserialize, Serialize};
+
+/// The number of links a node keeps per layer above the base layer.
+const M: usize = 16;
+
+/// The number of candidates considered while linking a new node.
+const EF_CONSTRUCTION: usize = 100;
+
+/// The number of candidates a k-NN search considers by default.
+pub const DEFAULT_NUM_CANDIDATES: usize = 100;
+
+/// The largest number of candidates a k-NN search may consider.
+pub const MAX_NUM_CANDIDATES: usize = 10_000;
+
+/// The graph is rebuilt once this share of its nodes are replaced or removed vectors.
+const MAX_DELETED_RATIO: f64 = 0.25;
+
+/// Graphs with fewer replaced or removed vectors are never rebuilt.
+const MIN_DELETED_FOR_REBUILD: usize = 16;
+
+/// The largest supported vector dimension.
+pub const MAX_DIMENSION: usize = 4096;
+
+/// How the similarity of two vectors is measured.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    /// The cosine of the angle between the vectors, scored as `(1 + cos) / 2`.
+    #[default]
+    Cosine,
+    /// The dot product of the vectors, scored as is.
+    DotProduct,
+    /// The Euclidean distance between the vectors, scored as `1 / (1 + d²)`.
+    L2,
+}
+
+impl Metric {
+    // Smaller is closer, cosine vectors are normalized when they are added
+    fn distance(self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            Metric::Cosine => 1.0 - dot(a, b),
+            Metric::DotProduct => -dot(a, b),
+            Metric::L2 => a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum(),
+        }
+    }
+
+    fn score(self, distance: f32) -> f64 {
+        let distance = distance as f64;
+        match self {
+            Metric::Cosine => (2.0 - distance) / 2.0,
+            Metric::DotProduct => -distance,
+            Metric::L2 => 1.0 / (1.0 + distance),
+        }
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn normalize(vector: &[f32]) -> Vec<f32> {
+    let norm = dot(vector, vector).sqrt();
+    if norm == 0.0 {
+        return vector.to_vec();
+    }
+    vector.iter().map(|x| x / norm).collect()
+}
+
+/// Errors that can occur while defining vector fields or using vectors.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VectorError {
+    UnknownField(String),
+    FieldExists(String),
+    InvalidDimension(usize),
+    DimensionMismatch {
+        field: String,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl fmt::Display for VectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VectorError::UnknownField(field) => write!(f, "Unknown vector field '{}'", field),
+            VectorError::FieldExists(field) => write!(
+                f,
+                "Vector field '{}' already exists with a different dimension or metric",
+                field
+            ),
+            VectorError::InvalidDimension(dimension) => write!(
+                f,
+                "Invalid vector dimension {}, expected 1 to {}",
+                dimension, MAX_DIMENSION
+            ),
+            VectorError::DimensionMismatch {
+                field,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Vector field '{}' expects {} dimensions, got {}",
+                field, expected, actual
+            ),
+        }
+    }
+}
+
+impl std::error::Error for VectorError {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Node {
+    url: String,
+    vector: Vec<f32>,
+    // The links of the node on every layer it is part of, base layer first
+    neighbors: Vec<Vec<usize>>,
+    // Replaced or removed nodes stay in the graph to keep it connected until it is rebuilt,
+    // but are never returned
+    deleted: bool,
+}
+
+// A node with its distance to the query, ordered by distance
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Candidate(f32, usize);
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0).then(self.1.cmp(&other.1))
+    }
+}
+
+/// A vector field indexed as a hierarchical navigable small world (HNSW) graph
+/// for approximate nearest-neighbor search.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hnsw {
+    dimension: usize,
+    metric: Metric,
+    nodes: Vec<Node>,
+    // The live node of every document
+    ids: HashMap<String, usize>,
+    entry_point: Option<usize>,
+    max_level: usize,
+}
+
+impl Hnsw {
+    /// Creates an empty vector field.
+    ///
+    /// **Arguments**
+    ///
+    /// * `dimension` - The number of dimensions of every vector.
+    /// * `metric` - The similarity metric.
+    ///
+    /// **Returns**
+    ///
+    /// The vector field, or an error if the dimension is out of range.
+    pub fn new(dimension: usize, metric: Metric) -> Result<Hnsw, VectorError> {
+        if dimension == 0 || dimension > MAX_DIMENSION {
+            return Err(VectorError::InvalidDimension(dimension));
+        }
+        Ok(Hnsw {
+            dimension,
+            metric,
+            nodes: Vec::new(),
+            ids: HashMap::new(),
+            entry_point: None,
+            max_level: 0,
+        })
+    }
+
+    /// Returns the number of dimensions of every vector.
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Returns the similarity metric.
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    /// Returns the number of documents with a vector.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Returns true if no document has a vector.
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Returns the stored vector of a document, normalized for the cosine metric.
+    pub fn vector(&self, url: &str) -> Option<&[f32]> {
+        self.ids
+            .get(url)
+            .map(|id| self.nodes[*id].vector.as_slice())
+    }
+
+    /// Returns the number of replaced or removed vectors still in the graph.
+    pub fn deleted(&self) -> usize {
+        self.nodes.len() - self.ids.len()
+    }
+
+    /// Removes the vector of a document.
+    ///
+    /// The node stays in the graph, which is rebuilt from the remaining vectors once
+    /// `MAX_DELETED_RATIO` of its nodes are removed.
+    pub fn remove(&mut self, url: &str) {
+        if let Some(id) = self.ids.remove(url) {
+            self.nodes[id].deleted = true;
+            let deleted = self.deleted();
+            if deleted >= MIN_DELETED_FOR_REBUILD
+                && deleted as f64 > self.nodes.len() as f64 * MAX_DELETED_RATIO
+            {
+                self.rebuild();
+            }
+        }
+    }
+
+    /// Adds or replaces the vector of a document and links it into the graph.
+    ///
+    /// **Arguments**
+    ///
+    /// * `url` - The URL of the document.
+    /// * `vector` - The vector, which must have the dimension of the field.
+    pub fn insert(&mut self, url: &str, vector: &[f32]) {
+        let vector = match self.metric {
+            Metric::Cosine => normalize(vector),
+            _ => vector.to_vec(),
+        };
+        // Reindexing a document with the same vector keeps its node
+        if self.vector(url) == Some(vector.as_slice()) {
+            return;
+        }
+        self.remove(url);
+        self.link(url, vector);
+    }
+
+    // Builds a new graph from the live vectors, dropping the removed ones
+    fn rebuild(&mut self) {
+        let nodes = std::mem::take(&mut self.nodes);
+        self.ids.clear();
+        self.entry_point = None;
+        self.max_level = 0;
+        for node in nodes.into_iter().filter(|node| !node.deleted) {
+            self.link(&node.url, node.vector);
+        }
+    }
+
+    // Adds a node with an already normalized vector and links it into the graph
+    fn link(&mut self, url: &str, vector: Vec<f32>) {
+        let id = self.nodes.len();
+        let level = random_level(url, id);
+        self.nodes.push(Node {
+            url: url.to_string(),
+            vector,
+            neighbors: vec![Vec::new(); level + 1],
+            deleted: false,
+        });
+        self.ids.insert(url.to_string(), id);
+
+        let Some(entry_point) = self.entry_point else {
+            self.entry_point = Some(id);
+            self.max_level = level;
+            return;
+        };
+
+        let query = self.nodes[id].vector.clone();
+        let mut entry_points = vec![entry_point];
+        for layer in (level + 1..=self.max_level).rev() {
+            entry_points = self.greedy_closest(&query, &entry_points, layer);
+        }
+        for layer in (0..=level.min(self.max_level)).rev() {
+            let found = self.search_layer(&query, &entry_points, EF_CONSTRUCTION, layer, |_| true);
+            let neighbors: Vec<usize> = found
+                .iter()
+                .map(|Candidate(_, n)| *n)
+                .take(max_links(layer))
+                .collect();
+            for neighbor in &neighbors {
+                self.nodes[*neighbor].neighbors[layer].push(id);
+                self.prune(*neighbor, layer);
+            }
+            self.nodes[id].neighbors[layer] = neighbors;
+            entry_points = found.into_iter().map(|Candidate(_, n)| n).collect();
+        }
+        if level > self.max_level {
+            self.entry_point = Some(id);
+            self.max_level = level;
+        }
+    }
+
+    /// Finds the nearest neighbors of a vector among the documents accepted by a filter.
+    ///
+    /// Rejected documents are still traversed, so a restrictive filter widens the search
+    /// instead of returning fewer neighbors.
+    ///
+    /// **Arguments**
+    ///
+    /// * `query` - The query vector, which must have the dimension of the field.
+    /// * `k` - The number of neighbors to return.
+    /// * `num_candidates` - The size of the candidate list, larger is more accurate but slower.
+    /// * `accept` - Decides whether a document may be returned.
+    ///
+    /// **Returns**
+    ///
+    /// The URLs and similarity scores of the nearest neighbors, most similar first.
+    pub fn search(
+        &self,
+        query: &[f32],
+        k: usize,
+        num_candidates: usize,
+        accept: impl Fn(&str) -> bool,
+    ) -> Vec<(String, f64)> {
+        let Some(entry_point) = self.entry_point else {
+            return Vec::new();
+        };
+        let query = match self.metric {
+            Metric::Cosine => normalize(query),
+            _ => query.to_vec(),
+        };
+
+        let mut entry_points = vec![entry_point];
+        for layer in (1..=self.max_level).rev() {
+            entry_points = self.greedy_closest(&query, &entry_points, layer);
+        }
+        let accept = |id: usize| !self.nodes[id].deleted && accept(&self.nodes[id].url);
+        self.search_layer(&query, &entry_points, num_candidates.max(k), 0, accept)
+            .into_iter()
+            .take(k)
+            .map(|Candidate(distance, id)| {
+                (self.nodes[id].url.clone(), self.metric.score(distance))
+            })
+            .collect()
+    }
+
+    fn greedy_closest(&self, query: &[f32], entry_points: &[usize], layer: usize) -> Vec<usize> {
+        self.search_layer(query, entry_points, 1, layer, |_| true)
+            .into_iter()
+            .map(|Candidate(_, id)| id)
+            .collect()
+    }
+
+    // Best-first search on one layer, returns up to `ef` accepted nodes closest first
+    fn search_layer(
+        &self,
+        query: &[f32],
+        entry_points: &[usize],
+        ef: usize,
+        layer: usize,
+        accept: impl Fn(usize) -> bool,
+    ) -> Vec<Candidate> {
+        let mut visited: HashSet<usize> = HashSet::new();
+        let mut candidates: BinaryHeap<Reverse<Candidate>> = BinaryHeap::new();
+        let mut results: BinaryHeap<Candidate> = BinaryHeap::new();
+
+        for &id in entry_points {
+            if visited.insert(id) {
+                let candidate = Candidate(self.metric.distance(query, &self.nodes[id].vector), id);
+                candidates.push(Reverse(candidate));
+                if accept(id) {
+                    results.push(candidate);
+                }
+            }
+        }
+        while let Some(Reverse(Candidate(distance, id))) = candidates.pop() {
+            if results.len() >= ef && results.peek().is_some_and(|worst| distance > worst.0) {
+                break;
+            }
+            let Some(neighbors) = self.nodes[id].neighbors.get(layer) else {
+                continue;
+            };
+            for &neighbor in neighbors {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+                let candidate = Candidate(
+                    self.metric.distance(query, &self.nodes[neighbor].vector),
+                    neighbor,
+                );
+                if results.len() < ef || results.peek().is_some_and(|worst| candidate < *worst) {
+                    candidates.push(Reverse(candidate));
+                    if accept(neighbor) {
+                        results.push(candidate);
+                        if results.len() > ef {
+                            results.pop();
+                        }
+                    }
+                }
+            }
+        }
+        results.into_sorted_vec()
+    }
+
+    // Keeps only the closest links of a node once it has too many
+    fn prune(&mut self, id: usize, layer: usize) {
+        if self.nodes[id].neighbors[layer].len() <= max_links(layer) {
+            return;
+        }
+        let vector = &self.nodes[id].vector;
+        let mut links: Vec<Candidate> = self.nodes[id].neighbors[layer]
+            .iter()
+            .map(|&n| Candidate(self.metric.distance(vector, &self.nodes[n].vector), n))
+            .collect();
+        links.sort();
+        links.truncate(max_links(layer));
+        self.nodes[id].neighbors[layer] = links.into_iter().map(|Candidate(_, n)| n).collect();
+    }
+}
+
+fn max_links(layer: usize) -> usize {
+    if layer == 0 {
+        2 * M
+    } else {
+        M
+    }
+}
+
+// Draws the top layer of a node from an exponential distribution, derived from a hash so
+// that building the same index twice gives the same graph
+fn random_level(url: &str, id: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    (url, id).hash(&mut hasher);
+    let uniform = (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64;
+    let level = -(1.0 - uniform).ln() / (M as f64).ln();
+    level.floor() as usize
+}
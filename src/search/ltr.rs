@@ -0,0 +1,441 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::search::analyzer::analyze;
+use crate::search::engine::SearchEngine;
+use crate::search::query::{Query, QueryError};
+use crate::search::rescore::proximity;
+use crate::types::field::FieldValue;
+
+const MILLIS_PER_DAY: f64 = 24.0 * 60.0 * 60.0 * 1000.0;
+
+/// A ranking feature of a hit, logged for offline training and read by ranking models.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Feature {
+    /// The score of the first pass, written as `score`.
+    Score,
+    /// BM25 of the query terms on the content alone, written as `bm25_content`.
+    Bm25Content,
+    /// BM25 of the query terms on the title alone, written as `bm25_title`.
+    Bm25Title,
+    /// The matched query terms divided by the length of the shortest passage containing
+    /// all of them, written as `proximity`.
+    Proximity,
+    /// The number of words of the content, written as `document_length`.
+    DocumentLength,
+    /// The age in days of a date field, written as `freshness:<field>`.
+    Freshness(String),
+    /// The value of a numeric field like a view count, written as `popularity:<field>`.
+    Popularity(String),
+}
+
+impl Feature {
+    /// Parses features separated by `,`, e.g. `bm25_content,bm25_title,freshness:published`.
+    ///
+    /// **Arguments**
+    ///
+    /// * `text` - The features.
+    ///
+    /// **Returns**
+    ///
+    /// The parsed features, or an error if one of them is unknown.
+    pub fn parse_all(text: &str) -> Result<Vec<Feature>, QueryError> {
+        text.split(',')
+            .filter(|f| !f.trim().is_empty())
+            .map(Feature::parse)
+            .collect()
+    }
+
+    /// Parses a single feature.
+    ///
+    /// **Arguments**
+    ///
+    /// * `text` - The feature.
+    ///
+    /// **Returns**
+    ///
+    /// The parsed feature, or an error if it is unknown.
+    pub fn parse(text: &str) -> Result<Feature, QueryError> {
+        let text = text.trim();
+        let feature = match text.split_once(':') {
+            None => match text {
+                "score" => Some(Feature::Score),
+                "bm25_content" => Some(Feature::Bm25Content),
+                "bm25_title" => Some(Feature::Bm25Title),
+                "proximity" => Some(Feature::Proximity),
+                "document_length" => Some(Feature::DocumentLength),
+                _ => None,
+            },
+            Some((_, "")) => None,
+            Some(("freshness", field)) => Some(Feature::Freshness(field.to_string())),
+            Some(("popularity", field)) => Some(Feature::Popularity(field.to_string())),
+            Some(_) => None,
+        };
+        feature.ok_or_else(|| QueryError::InvalidFeature(text.to_string()))
+    }
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Feature::Score => write!(f, "score"),
+            Feature::Bm25Content => write!(f, "bm25_content"),
+            Feature::Bm25Title => write!(f, "bm25_title"),
+            Feature::Proximity => write!(f, "proximity"),
+            Feature::DocumentLength => write!(f, "document_length"),
+            Feature::Freshness(field) => write!(f, "freshness:{}", field),
+            Feature::Popularity(field) => write!(f, "popularity:{}", field),
+        }
+    }
+}
+
+/// Computes the features of the hits of one query.
+///
+/// The statistics shared by all hits are computed once, so extracting the features of a
+/// whole window of hits stays cheap.
+pub struct FeatureExtractor<'a> {
+    engine: &'a SearchEngine,
+    // The expanded query terms with their boosts and IDFs
+    terms: Vec<(String, f64, f64)>,
+    term_set: HashSet<String>,
+    avdl: f64,
+    average_title_length: f64,
+    now: i64,
+}
+
+impl<'a> FeatureExtractor<'a> {
+    /// Creates an extractor for a query that already ran.
+    ///
+    /// **Arguments**
+    ///
+    /// * `engine` - The engine holding the documents.
+    /// * `query` - The parsed query.
+    /// * `now` - The current time in milliseconds since the Unix epoch, for freshness.
+    pub fn new(engine: &'a SearchEngine, query: &Query, now: i64) -> FeatureExtractor<'a> {
+        // The query already ran, so its expansion cannot fail here
+        let terms: Vec<(String, f64, f64)> = engine
+            .expand_query(query)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(term, boost)| {
+                let idf = engine.idf(&term);
+                (term, boost, idf)
+            })
+            .collect();
+        FeatureExtractor {
+            engine,
+            term_set: terms.iter().map(|(term, _, _)| term.clone()).collect(),
+            terms,
+            avdl: engine.avdl(),
+            average_title_length: engine.average_title_length(),
+            now,
+        }
+    }
+
+    /// Returns the exact dictionary terms the query matches, including expansions.
+    pub fn terms(&self) -> &HashSet<String> {
+        &self.term_set
+    }
+
+    /// Computes features of a hit.
+    ///
+    /// **Arguments**
+    ///
+    /// * `features` - The features to compute.
+    /// * `url` - The URL of the document.
+    /// * `score` - The score of the first pass.
+    ///
+    /// **Returns**
+    ///
+    /// The values by feature name. Features of fields the document lacks are left out.
+    pub fn extract(&self, features: &[Feature], url: &str, score: f64) -> BTreeMap<String, f64> {
+        let needs_tokens = features
+            .iter()
+            .any(|f| matches!(f, Feature::Proximity | Feature::DocumentLength));
+        let tokens = if needs_tokens {
+            analyze(self.engine.document(url).unwrap_or_default())
+        } else {
+            Vec::new()
+        };
+        features
+            .iter()
+            .filter_map(|feature| {
+                let value = self.value(feature, url, score, &tokens)?;
+                Some((feature.to_string(), value))
+            })
+            .collect()
+    }
+
+    fn value(&self, feature: &Feature, url: &str, score: f64, tokens: &[String]) -> Option<f64> {
+        match feature {
+            Feature::Score => Some(score),
+            Feature::Bm25Content => {
+                let length = self.engine.document(url)?.len();
+                let value = self
+                    .terms
+                    .iter()
+                    .map(
+                        |(term, boost, idf)| match self.engine.term_frequency(term, url) {
+                            0 => 0.0,
+                            freq => boost * self.engine.term_score(*idf, freq, length, self.avdl),
+                        },
+                    )
+                    .sum();
+                Some(value)
+            }
+            Feature::Bm25Title => {
+                let title = self.engine.title(url).unwrap_or_default();
+                let words = analyze(title);
+                let value = self
+                    .terms
+                    .iter()
+                    .map(
+                        |(term, boost, idf)| match words.iter().filter(|w| *w == term).count() {
+                            0 => 0.0,
+                            freq => {
+                                let average = self.average_title_length;
+                                boost
+                                    * self.engine.term_score(
+                                        *idf,
+                                        freq as i32,
+                                        title.len(),
+                                        average,
+                                    )
+                            }
+                        },
+                    )
+                    .sum();
+                Some(value)
+            }
+            Feature::Proximity => Some(proximity(tokens, &self.term_set)),
+            Feature::DocumentLength => Some(tokens.len() as f64),
+            Feature::Freshness(field) => match self.engine.field(url, field) {
+                Some(FieldValue::Date(date)) => Some((self.now - date) as f64 / MILLIS_PER_DAY),
+                _ => None,
+            },
+            Feature::Popularity(field) => match self.engine.field(url, field) {
+                Some(FieldValue::Number(number)) => Some(*number),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// The branch of a split taken by hits that lack its feature.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Branch {
+    #[default]
+    Left,
+    Right,
+}
+
+/// A node of a regression tree.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TreeNode {
+    /// Goes left if the feature is below the threshold, otherwise right.
+    Split {
+        feature: String,
+        threshold: f64,
+        #[serde(default)]
+        missing: Branch,
+        left: Box<TreeNode>,
+        right: Box<TreeNode>,
+    },
+    /// The value of the hits reaching this node.
+    Leaf(f64),
+}
+
+impl TreeNode {
+    fn validate(&self) -> Result<(), ModelError> {
+        match self {
+            TreeNode::Split {
+                feature,
+                threshold,
+                left,
+                right,
+                ..
+            } => {
+                check_feature(feature)?;
+                check_number(*threshold)?;
+                left.validate()?;
+                right.validate()
+            }
+            TreeNode::Leaf(value) => check_number(*value),
+        }
+    }
+
+    fn evaluate(&self, features: &BTreeMap<String, f64>) -> f64 {
+        let mut node = self;
+        loop {
+            match node {
+                TreeNode::Split {
+                    feature,
+                    threshold,
+                    missing,
+                    left,
+                    right,
+                } => {
+                    let branch = match features.get(feature) {
+                        Some(value) if value < threshold => Branch::Left,
+                        Some(_) => Branch::Right,
+                        None => *missing,
+                    };
+                    node = match branch {
+                        Branch::Left => left,
+                        Branch::Right => right,
+                    };
+                }
+                TreeNode::Leaf(value) => return *value,
+            }
+        }
+    }
+
+    fn collect_features<'a>(&'a self, names: &mut BTreeSet<&'a str>) {
+        if let TreeNode::Split {
+            feature,
+            left,
+            right,
+            ..
+        } = self
+        {
+            names.insert(feature);
+            left.collect_features(names);
+            right.collect_features(names);
+        }
+    }
+}
+
+/// A trained model scoring hits from their features, referring to features by name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RankingModel {
+    /// `bias + sum of weight * feature`, missing features count as 0.
+    Linear {
+        #[serde(default)]
+        bias: f64,
+        weights: BTreeMap<String, f64>,
+    },
+    /// A gradient-boosted ensemble, `base_score + sum of the leaves the hit reaches`.
+    Trees {
+        #[serde(default)]
+        base_score: f64,
+        trees: Vec<TreeNode>,
+    },
+}
+
+/// Errors that can occur when a ranking model is stored.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModelError {
+    Empty,
+    UnknownFeature(String),
+    InvalidNumber(f64),
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelError::Empty => write!(f, "A model needs at least one weight or tree"),
+            ModelError::UnknownFeature(feature) => write!(f, "Unknown feature '{}'", feature),
+            ModelError::InvalidNumber(number) => {
+                write!(f, "Invalid number {}, expected a finite number", number)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ModelError {}
+
+fn check_feature(name: &str) -> Result<(), ModelError> {
+    match Feature::parse(name) {
+        Ok(feature) if feature.to_string() == name => Ok(()),
+        _ => Err(ModelError::UnknownFeature(name.to_string())),
+    }
+}
+
+fn check_number(number: f64) -> Result<(), ModelError> {
+    if number.is_finite() {
+        Ok(())
+    } else {
+        Err(ModelError::InvalidNumber(number))
+    }
+}
+
+impl RankingModel {
+    /// Checks that the model has weights or trees, known features and finite numbers.
+    pub fn validate(&self) -> Result<(), ModelError> {
+        match self {
+            RankingModel::Linear { bias, weights } => {
+                if weights.is_empty() {
+                    return Err(ModelError::Empty);
+                }
+                check_number(*bias)?;
+                for (feature, weight) in weights {
+                    check_feature(feature)?;
+                    check_number(*weight)?;
+                }
+            }
+            RankingModel::Trees { base_score, trees } => {
+                if trees.is_empty() {
+                    return Err(ModelError::Empty);
+                }
+                check_number(*base_score)?;
+                for tree in trees {
+                    tree.validate()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the features the model reads.
+    ///
+    /// **Returns**
+    ///
+    /// The features, the model must have been validated.
+    pub fn features(&self) -> Vec<Feature> {
+        let mut names = BTreeSet::new();
+        match self {
+            RankingModel::Linear { weights, .. } => {
+                names.extend(weights.keys().map(String::as_str))
+            }
+            RankingModel::Trees { trees, .. } => {
+                for tree in trees {
+                    tree.collect_features(&mut names);
+                }
+            }
+        }
+        names
+            .into_iter()
+            .filter_map(|name| Feature::parse(name).ok())
+            .collect()
+    }
+
+    /// Scores a hit.
+    ///
+    /// **Arguments**
+    ///
+    /// * `features` - The features of the hit by name.
+    ///
+    /// **Returns**
+    ///
+    /// The score of the model.
+    pub fn score(&self, features: &BTreeMap<String, f64>) -> f64 {
+        match self {
+            RankingModel::Linear { bias, weights } => {
+                let sum: f64 = weights
+                    .iter()
+                    .map(|(feature, weight)| weight * features.get(feature).unwrap_or(&0.0))
+                    .sum();
+                bias + sum
+            }
+            RankingModel::Trees { base_score, trees } => {
+                let sum: f64 = trees.iter().map(|tree| tree.evaluate(features)).sum();
+                base_score + sum
+            }
+        }
+    }
+}
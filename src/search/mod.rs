@@ -1 +1,27 @@
-pub mod engine;
\ No newline at end of file
+pub mod analyzer;
+pub mod cursor;
+pub mod engine;
+pub mod explain;
+pub mod facets;
+pub mod filter;
+pub mod function_score;
+pub mod fusion;
+pub mod fuzzy;
+pub mod group;
+pub mod highlight;
+pub mod hnsw;
+pub mod ltr;
+pub mod percolator;
+pub mod pit;
+pub mod query;
+pub mod query_log;
+pub mod rescore;
+pub mod rules;
+pub mod script;
+pub mod simhash;
+pub mod sort;
+pub mod spelling;
+pub mod suggest;
+pub mod synonyms;
+pub mod template;
+pub mod terms;
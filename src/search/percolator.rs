@@ -0,0 +1,201 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::search::filter::Filter;
+use crate::search::fuzzy::fuzzy_terms;
+use crate::search::query::{Clause, Query, QueryError};
+use crate::search::terms::{prefix_terms, regex_terms, wildcard_terms};
+use crate::types::field::FieldValue;
+
+/// A stored query that new documents are matched against.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PercolatorQuery {
+    /// The query in the syntax of `/search`.
+    pub query: String,
+    /// Filters a document must pass as well, like `lang = en`, separated by `;`.
+    #[serde(default)]
+    pub filter: Option<String>,
+}
+
+impl PercolatorQuery {
+    /// Parses the query and its filters.
+    ///
+    /// **Returns**
+    ///
+    /// The parsed query and filters, or an error if one of them is malformed.
+    pub fn parse(&self) -> Result<(Query, Vec<Filter>), QueryError> {
+        let query = Query::parse(&self.query)?;
+        // Regular expressions are only compiled on expansion, so they are checked up front
+        for clause in &query.clauses {
+            if let Clause::Regex(pattern) = clause {
+                regex_terms(&BTreeMap::<String, ()>::new(), pattern, 1)?;
+            }
+        }
+        let filters = Filter::parse_all(self.filter.as_deref().unwrap_or_default())?;
+        Ok((query, filters))
+    }
+}
+
+/// The stored queries with an index over their terms.
+///
+/// A query matches a document as soon as one of its clauses does, so it only has to be
+/// checked against documents containing one of its terms. Queries with fuzzy, prefix,
+/// wildcard or regex clauses cannot be pruned that way and are checked against every document.
+#[derive(Debug, Clone, Default)]
+pub struct Percolator {
+    queries: BTreeMap<String, PercolatorQuery>,
+    // The parsed queries with synonyms applied, and their filters
+    compiled: HashMap<String, (Query, Vec<Filter>)>,
+    // Exact terms to the queries containing them
+    term_index: HashMap<String, BTreeSet<String>>,
+    // Queries that are checked against every document
+    unindexed: BTreeSet<String>,
+}
+
+impl Percolator {
+    /// Returns the stored queries by ID.
+    pub fn queries(&self) -> &BTreeMap<String, PercolatorQuery> {
+        &self.queries
+    }
+
+    /// Stores a query, replacing the one with the same ID.
+    ///
+    /// **Arguments**
+    ///
+    /// * `id` - The ID of the query.
+    /// * `stored` - The query as given by the user.
+    /// * `query` - The parsed query with synonyms applied.
+    /// * `filters` - The parsed filters.
+    ///
+    /// **Returns**
+    ///
+    /// True if the query was created, false if it replaced one.
+    pub fn insert(
+        &mut self,
+        id: &str,
+        stored: PercolatorQuery,
+        query: Query,
+        filters: Vec<Filter>,
+    ) -> bool {
+        let created = !self.remove(id);
+        let keys: Option<Vec<Vec<String>>> = query.clauses.iter().map(index_terms).collect();
+        match keys {
+            Some(keys) => {
+                for key in keys.into_iter().flatten() {
+                    self.term_index
+                        .entry(key)
+                        .or_default()
+                        .insert(id.to_string());
+                }
+            }
+            None => {
+                self.unindexed.insert(id.to_string());
+            }
+        }
+        self.queries.insert(id.to_string(), stored);
+        self.compiled.insert(id.to_string(), (query, filters));
+        created
+    }
+
+    /// Removes a query.
+    ///
+    /// **Arguments**
+    ///
+    /// * `id` - The ID of the query.
+    ///
+    /// **Returns**
+    ///
+    /// True if the query existed.
+    pub fn remove(&mut self, id: &str) -> bool {
+        if self.queries.remove(id).is_none() {
+            return false;
+        }
+        self.compiled.remove(id);
+        self.unindexed.remove(id);
+        self.term_index.retain(|_, ids| {
+            ids.remove(id);
+            !ids.is_empty()
+        });
+        true
+    }
+
+    /// Finds the stored queries matching a document.
+    ///
+    /// **Arguments**
+    ///
+    /// * `tokens` - The analyzed content of the document.
+    /// * `field` - Returns the value of a field of the document.
+    ///
+    /// **Returns**
+    ///
+    /// The IDs of the matching queries in order.
+    pub fn percolate<'a>(
+        &self,
+        tokens: &[String],
+        field: impl Fn(&str) -> Option<&'a FieldValue>,
+    ) -> Vec<String> {
+        let dictionary: BTreeMap<String, ()> = tokens.iter().map(|t| (t.clone(), ())).collect();
+        let mut candidates: BTreeSet<&String> = self.unindexed.iter().collect();
+        for term in dictionary.keys() {
+            candidates.extend(self.term_index.get(term).into_iter().flatten());
+        }
+        candidates
+            .into_iter()
+            .filter(|id| {
+                let (query, filters) = &self.compiled[*id];
+                filters.iter().all(|f| f.matches(field(&f.field)))
+                    && query
+                        .clauses
+                        .iter()
+                        .any(|c| matches(c, tokens, &dictionary, query.max_expansions))
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+// The terms of which a document must contain one for the clause to match,
+// `None` if the clause can match without any exact term
+fn index_terms(clause: &Clause) -> Option<Vec<String>> {
+    match clause {
+        Clause::Term(term) => Some(vec![term.clone()]),
+        // Every term of a phrase is required, the longest one is likely the rarest
+        Clause::Phrase(phrase) => phrase
+            .iter()
+            .max_by_key(|t| t.len())
+            .cloned()
+            .map(|t| vec![t]),
+        Clause::Synonyms(alternatives) => {
+            let keys: Option<Vec<Vec<String>>> =
+                alternatives.iter().flatten().map(index_terms).collect();
+            keys.map(|keys| keys.concat())
+        }
+        _ => None,
+    }
+}
+
+fn matches(
+    clause: &Clause,
+    tokens: &[String],
+    dictionary: &BTreeMap<String, ()>,
+    max_expansions: usize,
+) -> bool {
+    // Too many expansions means there certainly was a match
+    let any = |terms: Result<Vec<String>, QueryError>| terms.map_or(true, |t| !t.is_empty());
+    match clause {
+        Clause::Term(term) => dictionary.contains_key(term),
+        Clause::Fuzzy { term, fuzziness } => {
+            !fuzzy_terms(dictionary, term, fuzziness.max_distance(term)).is_empty()
+        }
+        Clause::Prefix(prefix) => any(prefix_terms(dictionary, prefix, max_expansions)),
+        Clause::Wildcard(pattern) => any(wildcard_terms(dictionary, pattern, max_expansions)),
+        // The expression was checked when the query was stored
+        Clause::Regex(pattern) => any(regex_terms(dictionary, pattern, max_expansions)),
+        Clause::Phrase(phrase) => tokens.windows(phrase.len()).any(|window| window == phrase),
+        Clause::Synonyms(alternatives) => alternatives
+            .iter()
+            .flatten()
+            .any(|clause| matches(clause, tokens, dictionary, max_expansions)),
+    }
+}
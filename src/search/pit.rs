@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::search::engine::SearchEngine;
+use crate::search::query::QueryError;
+use crate::types::field::parse_duration;
+
+/// The longest time a point in time may be kept alive without being used.
+pub const MAX_KEEP_ALIVE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The maximum number of points in time that can be open at once.
+pub const MAX_OPEN_POINTS_IN_TIME: usize = 1000;
+
+struct PointInTime {
+    snapshot: Arc<SearchEngine>,
+    keep_alive: Duration,
+    expires_at: Instant,
+}
+
+/// Open points in time, each holding a snapshot of the index that later writes do not change.
+///
+/// A point in time expires when it was not used for its keep-alive, every use extends it.
+/// Expired points in time are dropped whenever the store is accessed.
+#[derive(Default)]
+pub struct PointInTimeStore {
+    points_in_time: Mutex<HashMap<String, PointInTime>>,
+    counter: AtomicU64,
+}
+
+impl PointInTimeStore {
+    /// Opens a point in time.
+    ///
+    /// **Arguments**
+    ///
+    /// * `snapshot` - The snapshot of the index to keep.
+    /// * `keep_alive` - How long the point in time is kept without being used.
+    ///
+    /// **Returns**
+    ///
+    /// The ID of the point in time, or `None` if too many are open.
+    pub fn open(&self, snapshot: Arc<SearchEngine>, keep_alive: Duration) -> Option<String> {
+        let mut points_in_time = self.points_in_time.lock().unwrap();
+        let now = Instant::now();
+        points_in_time.retain(|_, pit| pit.expires_at > now);
+        if points_in_time.len() >= MAX_OPEN_POINTS_IN_TIME {
+            return None;
+        }
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        let id = format!(
+            "{:x}-{:x}",
+            nanos,
+            self.counter.fetch_add(1, Ordering::Relaxed)
+        );
+        points_in_time.insert(
+            id.clone(),
+            PointInTime {
+                snapshot,
+                keep_alive,
+                expires_at: now + keep_alive,
+            },
+        );
+        Some(id)
+    }
+
+    /// Returns the snapshot of a point in time and extends its keep-alive.
+    ///
+    /// **Arguments**
+    ///
+    /// * `id` - The ID of the point in time.
+    /// * `keep_alive` - A new keep-alive, or `None` to keep the current one.
+    ///
+    /// **Returns**
+    ///
+    /// The snapshot, or `None` if the point in time does not exist or has expired.
+    pub fn get(&self, id: &str, keep_alive: Option<Duration>) -> Option<Arc<SearchEngine>> {
+        let mut points_in_time = self.points_in_time.lock().unwrap();
+        let now = Instant::now();
+        points_in_time.retain(|_, pit| pit.expires_at > now);
+
+        let pit = points_in_time.get_mut(id)?;
+        if let Some(keep_alive) = keep_alive {
+            pit.keep_alive = keep_alive;
+        }
+        pit.expires_at = now + pit.keep_alive;
+        Some(pit.snapshot.clone())
+    }
+
+    /// Closes a point in time and releases its snapshot.
+    ///
+    /// **Arguments**
+    ///
+    /// * `id` - The ID of the point in time.
+    ///
+    /// **Returns**
+    ///
+    /// True if the point in time was open.
+    pub fn close(&self, id: &str) -> bool {
+        let mut points_in_time = self.points_in_time.lock().unwrap();
+        let now = Instant::now();
+        points_in_time.retain(|_, pit| pit.expires_at > now);
+        points_in_time.remove(id).is_some()
+    }
+}
+
+/// Parses a keep-alive like `30s`, `5m` or `1h` with `parse_duration`.
+///
+/// **Arguments**
+///
+/// * `text` - The keep-alive.
+///
+/// **Returns**
+///
+/// The duration, or an error if it is malformed, zero or longer than `MAX_KEEP_ALIVE`.
+pub fn parse_keep_alive(text: &str) -> Result<Duration, QueryError> {
+    parse_duration(text)
+        .filter(|millis| *millis > 0)
+        .map(|millis| Duration::from_millis(millis as u64))
+        .filter(|keep_alive| *keep_alive <= MAX_KEEP_ALIVE)
+        .ok_or_else(|| QueryError::InvalidKeepAlive(text.to_string()))
+}
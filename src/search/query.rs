@@ -0,0 +1,270 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::search::analyzer::analyze;
+
+/// The largest edit distance supported by fuzzy queries.
+pub const MAX_FUZZY_DISTANCE: u8 = 2;
+
+/// The default limit on the number of terms a prefix, wildcard or regex clause may expand to.
+pub const DEFAULT_MAX_EXPANSIONS: usize = 1024;
+
+/// How many edits a fuzzy term may be away from a dictionary term.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fuzziness {
+    /// A fixed maximum edit distance.
+    Distance(u8),
+    /// Picks the distance from the term length: 0 up to two characters, 1 up to five, 2 above.
+    Auto,
+}
+
+impl Fuzziness {
+    /// Returns the maximum edit distance allowed for the given term.
+    pub fn max_distance(&self, term: &str) -> u8 {
+        match self {
+            Fuzziness::Distance(distance) => *distance,
+            Fuzziness::Auto => match term.chars().count() {
+                0..=2 => 0,
+                3..=5 => 1,
+                _ => 2,
+            },
+        }
+    }
+}
+
+impl FromStr for Fuzziness {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() || s.eq_ignore_ascii_case("auto") {
+            return Ok(Fuzziness::Auto);
+        }
+        match s.parse::<u8>() {
+            Ok(distance) if distance <= MAX_FUZZY_DISTANCE => Ok(Fuzziness::Distance(distance)),
+            _ => Err(QueryError::InvalidFuzziness(s.to_string())),
+        }
+    }
+}
+
+/// A single clause of a parsed query.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Clause {
+    /// Matches the exact term.
+    Term(String),
+    /// Matches all dictionary terms within the given edit distance, written as `term~1`.
+    Fuzzy { term: String, fuzziness: Fuzziness },
+    /// Matches all dictionary terms starting with the prefix, written as `term*`.
+    Prefix(String),
+    /// Matches dictionary terms against `?` and `*` wildcards, written as `r?st*`.
+    Wildcard(String),
+    /// Matches dictionary terms against a regular expression, written as `/rust(y|ic)/`.
+    Regex(String),
+    /// Matches the terms in this exact order, written as `"rust programming"`.
+    Phrase(Vec<String>),
+    /// Matches any of the alternatives, each being a group of clauses whose scores are summed.
+    /// A document is scored by its best alternative. Produced by synonym expansion.
+    Synonyms(Vec<Vec<Clause>>),
+}
+
+/// Errors that can occur while parsing or expanding a query.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryError {
+    InvalidFuzziness(String),
+    InvalidRegex(String),
+    InvalidFilter(String),
+    InvalidFilterValue {
+        field: String,
+        value: String,
+        expected: String,
+    },
+    InvalidFacet(String),
+    InvalidSort(String),
+    InvalidCursor(String),
+    InvalidKeepAlive(String),
+    InvalidFunctionScore(String),
+    InvalidRescore(String),
+    InvalidFusion(String),
+    InvalidFeature(String),
+    UnknownModel(String),
+    TooManyExpansions {
+        pattern: String,
+        max_expansions: usize,
+    },
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::InvalidFuzziness(fuzziness) => write!(
+                f,
+                "Invalid fuzziness '{}', expected 'auto' or a distance up to {}",
+                fuzziness, MAX_FUZZY_DISTANCE
+            ),
+            QueryError::InvalidRegex(pattern) => {
+                write!(f, "Invalid regular expression '{}'", pattern)
+            }
+            QueryError::InvalidFilter(filter) => write!(f, "Invalid filter '{}'", filter),
+            QueryError::InvalidFilterValue {
+                field,
+                value,
+                expected,
+            } => write!(
+                f,
+                "Invalid filter value '{}' for field '{}', expected {}",
+                value, field, expected
+            ),
+            QueryError::InvalidFacet(facet) => write!(f, "Invalid facet '{}'", facet),
+            QueryError::InvalidSort(sort) => write!(f, "Invalid sort '{}'", sort),
+            QueryError::InvalidKeepAlive(keep_alive) => {
+                write!(
+                    f,
+                    "Invalid keep_alive '{}', expected e.g. '30s' or '5m'",
+                    keep_alive
+                )
+            }
+            QueryError::InvalidFunctionScore(reason) => {
+                write!(f, "Invalid function score: {}", reason)
+            }
+            QueryError::InvalidRescore(reason) => write!(f, "Invalid rescore: {}", reason),
+            QueryError::InvalidFusion(reason) => write!(f, "Invalid fusion: {}", reason),
+            QueryError::InvalidFeature(feature) => write!(f, "Unknown feature '{}'", feature),
+            QueryError::UnknownModel(name) => {
+                write!(f, "Ranking model '{}' does not exist", name)
+            }
+            QueryError::InvalidCursor(cursor) => {
+                write!(f, "Invalid search_after cursor '{}'", cursor)
+            }
+            QueryError::TooManyExpansions {
+                pattern,
+                max_expansions,
+            } => write!(
+                f,
+                "'{}' matches more than {} terms, use a more specific pattern",
+                pattern, max_expansions
+            ),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// A parsed search query. Its clauses are combined with OR.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub clauses: Vec<Clause>,
+    /// The maximum number of terms a prefix, wildcard or regex clause may expand to.
+    pub max_expansions: usize,
+}
+
+impl Default for Query {
+    fn default() -> Self {
+        Query {
+            clauses: Vec::new(),
+            max_expansions: DEFAULT_MAX_EXPANSIONS,
+        }
+    }
+}
+
+impl Query {
+    /// Parses a query string.
+    ///
+    /// Words are analyzed like indexed content. A word followed by `~`, `~1` or `~2`
+    /// becomes a fuzzy clause, where a bare `~` uses automatic fuzziness. Words containing
+    /// `*` or `?` become prefix or wildcard clauses and words wrapped in `/` become regex clauses.
+    /// Words wrapped in double quotes form a phrase.
+    ///
+    /// **Arguments**
+    ///
+    /// * `query` - The query string.
+    ///
+    /// **Returns**
+    ///
+    /// The parsed query, or an error if a clause is malformed.
+    pub fn parse(query: &str) -> Result<Query, QueryError> {
+        let mut clauses = Vec::new();
+        // Every second part of the query is enclosed in double quotes
+        for (i, part) in query.split('"').enumerate() {
+            if i % 2 == 1 {
+                let mut phrase = analyze(part);
+                match phrase.len() {
+                    0 => {}
+                    1 => clauses.push(Clause::Term(phrase.remove(0))),
+                    _ => clauses.push(Clause::Phrase(phrase)),
+                }
+            } else {
+                parse_words(part, &mut clauses)?;
+            }
+        }
+        Ok(Query {
+            clauses,
+            ..Query::default()
+        })
+    }
+
+    /// Sets the maximum number of terms a prefix, wildcard or regex clause may expand to.
+    ///
+    /// **Arguments**
+    ///
+    /// * `max_expansions` - The new limit.
+    ///
+    /// **Returns**
+    ///
+    /// The query with the new limit.
+    pub fn with_max_expansions(mut self, max_expansions: usize) -> Query {
+        self.max_expansions = max_expansions;
+        self
+    }
+
+    /// Turns every exact term clause into a fuzzy clause with the given fuzziness.
+    ///
+    /// **Arguments**
+    ///
+    /// * `fuzziness` - The fuzziness to apply.
+    ///
+    /// **Returns**
+    ///
+    /// The query with fuzzy term clauses.
+    pub fn with_fuzziness(mut self, fuzziness: Fuzziness) -> Query {
+        for clause in self.clauses.iter_mut() {
+            if let Clause::Term(term) = clause {
+                *clause = Clause::Fuzzy {
+                    term: std::mem::take(term),
+                    fuzziness,
+                };
+            }
+        }
+        self
+    }
+}
+
+// Parses the words outside of phrases into clauses
+fn parse_words(text: &str, clauses: &mut Vec<Clause>) -> Result<(), QueryError> {
+    for word in text.split_whitespace() {
+        if word.len() > 2 && word.starts_with('/') && word.ends_with('/') {
+            clauses.push(Clause::Regex(word[1..word.len() - 1].to_string()));
+        } else if let Some((term, fuzziness)) = word.rsplit_once('~') {
+            let fuzziness = fuzziness.parse::<Fuzziness>()?;
+            for term in analyze(term) {
+                clauses.push(Clause::Fuzzy { term, fuzziness });
+            }
+        } else if word.contains(['*', '?']) {
+            clauses.push(parse_wildcard(word));
+        } else {
+            clauses.extend(analyze(word).into_iter().map(Clause::Term));
+        }
+    }
+    Ok(())
+}
+
+// Normalizes a word like the analyzer does while keeping its wildcards
+fn parse_wildcard(word: &str) -> Clause {
+    let pattern: String = word
+        .chars()
+        .filter(|&c| !c.is_ascii_punctuation() || c == '*' || c == '?')
+        .collect::<String>()
+        .to_lowercase();
+    match pattern.strip_suffix('*') {
+        Some(prefix) if !prefix.contains(['*', '?']) => Clause::Prefix(prefix.to_string()),
+        _ => Clause::Wildcard(pattern),
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{RwLock, RwLockReadGuard};
+
+use bincode::serialize_into;
+
+use crate::search::query::{Clause, Query};
+
+/// The maximum number of distinct terms kept in the query log.
+pub const MAX_QUERY_LOG_TERMS: usize = 10_000;
+
+/// The number of recorded queries after which a persisted log is written to disk.
+const WRITE_EVERY: usize = 100;
+
+/// How often each term occurred in queries that returned results, used to correct spelling.
+///
+/// The log lives next to the search engine rather than in it, so recording a query neither
+/// takes the lock on the engine nor copies a log shared with snapshots. Only about the
+/// `MAX_QUERY_LOG_TERMS` most frequent terms are kept.
+#[derive(Default)]
+pub struct QueryLog {
+    counts: RwLock<HashMap<String, u32>>,
+    // Queries recorded since the log was last written
+    unwritten: AtomicUsize,
+    path: Option<String>,
+}
+
+impl QueryLog {
+    /// Opens the query log stored at a path, which recorded queries are written back to.
+    ///
+    /// **Arguments**
+    ///
+    /// * `path` - The file of the log, which does not need to exist yet.
+    ///
+    /// **Returns**
+    ///
+    /// The query log, or an error if the file exists but cannot be read.
+    pub fn open(path: &str) -> io::Result<QueryLog> {
+        let counts = match fs::read(path) {
+            Ok(bytes) => bincode::deserialize(&bytes)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(QueryLog {
+            counts: RwLock::new(counts),
+            unwritten: AtomicUsize::new(0),
+            path: Some(path.to_string()),
+        })
+    }
+
+    /// Counts the plain terms of a query.
+    ///
+    /// **Arguments**
+    ///
+    /// * `query` - The parsed query that returned results.
+    pub fn record(&self, query: &Query) {
+        let mut counts = self.counts.write().unwrap();
+        for clause in &query.clauses {
+            if let Clause::Term(term) = clause {
+                *counts.entry(term.clone()).or_insert(0) += 1;
+            }
+        }
+        if counts.len() > MAX_QUERY_LOG_TERMS {
+            evict(&mut counts);
+        }
+        if self.unwritten.fetch_add(1, Ordering::Relaxed) + 1 >= WRITE_EVERY {
+            self.write(&counts);
+        }
+    }
+
+    /// Returns the term counts, recording waits while the guard is held.
+    pub fn counts(&self) -> RwLockReadGuard<'_, HashMap<String, u32>> {
+        self.counts.read().unwrap()
+    }
+
+    /// Writes the queries recorded since the last write to disk, if the log is persisted.
+    pub fn flush(&self) {
+        if self.unwritten.load(Ordering::Relaxed) > 0 {
+            self.write(&self.counts.read().unwrap());
+        }
+    }
+
+    fn write(&self, counts: &HashMap<String, u32>) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        // Write to a temporary file first, so a crash never leaves a partial log behind
+        let temporary = format!("{}.tmp", path);
+        let result = File::create(&temporary).and_then(|file| {
+            let mut file = BufWriter::new(file);
+            serialize_into(&mut file, counts).map_err(io::Error::other)?;
+            file.flush()?;
+            drop(file);
+            fs::rename(&temporary, path)
+        });
+        match result {
+            Ok(()) => self.unwritten.store(0, Ordering::Relaxed),
+            Err(e) => log::warn!("Could not write the query log to {}: {}", path, e),
+        }
+    }
+}
+
+// Drops the least frequent terms, leaving room so eviction does not run on every query
+fn evict(counts: &mut HashMap<String, u32>) {
+    let mut ranked: Vec<(u32, String)> =
+        counts.drain().map(|(term, count)| (count, term)).collect();
+    ranked.sort_unstable_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    ranked.truncate(MAX_QUERY_LOG_TERMS * 9 / 10);
+    counts.extend(ranked.into_iter().map(|(count, term)| (term, count)));
+}
@@ -0,0 +1,347 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::search::analyzer::analyze;
+use crate::search::engine::SearchEngine;
+use crate::search::explain::Explanation;
+use crate::search::function_score::FunctionScore;
+use crate::search::ltr::FeatureExtractor;
+use crate::search::query::{Query, QueryError};
+use crate::types::field::FieldValue;
+
+/// The number of top hits rescored when no window size is given.
+pub const DEFAULT_WINDOW_SIZE: usize = 100;
+
+/// The largest window that may be rescored.
+pub const MAX_WINDOW_SIZE: usize = 10_000;
+
+/// How the original score and the rescore score of a hit are combined.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoreMode {
+    /// The weighted sum of both scores.
+    #[default]
+    Total,
+    /// The product of both weighted scores.
+    Multiply,
+    /// The average of both weighted scores.
+    Avg,
+    /// The larger of both weighted scores.
+    Max,
+    /// The smaller of both weighted scores.
+    Min,
+}
+
+impl fmt::Display for ScoreMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScoreMode::Total => write!(f, "total"),
+            ScoreMode::Multiply => write!(f, "multiply"),
+            ScoreMode::Avg => write!(f, "avg"),
+            ScoreMode::Max => write!(f, "max"),
+            ScoreMode::Min => write!(f, "min"),
+        }
+    }
+}
+
+/// An expensive scorer only run on the rescore window.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rescorer {
+    /// Rewards documents whose matched query terms occur close together in the content:
+    /// `boost * matched terms / length of the shortest span containing all of them`.
+    Proximity {
+        #[serde(default = "default_boost")]
+        boost: f64,
+    },
+    /// Rewards query terms occurring in the title or a keyword field:
+    /// `boost * number of distinct query terms in the field`.
+    FieldMatch {
+        field: String,
+        #[serde(default = "default_boost")]
+        boost: f64,
+    },
+    /// A user-supplied function score, given the original score as its text score.
+    FunctionScore(FunctionScore),
+    /// A stored ranking model, scoring the features of the hit.
+    Model { name: String },
+}
+
+fn default_boost() -> f64 {
+    1.0
+}
+
+fn default_window_size() -> usize {
+    DEFAULT_WINDOW_SIZE
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+/// A second ranking pass over the top hits of the first one.
+///
+/// The rescore score of a hit is the sum of its scorers. The hits in the window are
+/// reordered by their combined score and stay ahead of the hits outside of it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rescore {
+    #[serde(default = "default_window_size")]
+    pub window_size: usize,
+    #[serde(default = "default_weight")]
+    pub query_weight: f64,
+    #[serde(default = "default_weight")]
+    pub rescore_query_weight: f64,
+    #[serde(default)]
+    pub score_mode: ScoreMode,
+    pub scorers: Vec<Rescorer>,
+}
+
+impl Rescore {
+    /// Checks the window size, the weights and the scorers.
+    ///
+    /// **Returns**
+    ///
+    /// An error describing the first invalid setting.
+    pub fn validate(&self) -> Result<(), QueryError> {
+        let invalid = |reason: String| QueryError::InvalidRescore(reason);
+        if self.window_size > MAX_WINDOW_SIZE {
+            return Err(invalid(format!(
+                "window_size must not exceed {}",
+                MAX_WINDOW_SIZE
+            )));
+        }
+        if !self.query_weight.is_finite() || !self.rescore_query_weight.is_finite() {
+            return Err(invalid("weights must be finite".to_string()));
+        }
+        for scorer in &self.scorers {
+            match scorer {
+                Rescorer::Proximity { boost } | Rescorer::FieldMatch { boost, .. }
+                    if !boost.is_finite() =>
+                {
+                    return Err(invalid("boosts must be finite".to_string()));
+                }
+                Rescorer::FunctionScore(function_score) => function_score.validate()?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the ranking models of the scorers exist.
+    ///
+    /// **Arguments**
+    ///
+    /// * `engine` - The engine holding the models.
+    ///
+    /// **Returns**
+    ///
+    /// An error naming the first missing model.
+    pub fn check_models(&self, engine: &SearchEngine) -> Result<(), QueryError> {
+        for scorer in &self.scorers {
+            match scorer {
+                Rescorer::Model { name } if engine.ranking_model(name).is_none() => {
+                    return Err(QueryError::UnknownModel(name.clone()));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Rescores the top window of ranked hits.
+    ///
+    /// **Arguments**
+    ///
+    /// * `engine` - The engine holding the documents.
+    /// * `query` - The parsed query.
+    /// * `ranked` - The hits ranked by the first pass, best first.
+    /// * `now` - The current time in milliseconds since the Unix epoch, for function scores.
+    ///
+    /// **Returns**
+    ///
+    /// The rescored window ordered by its new scores, ties broken by URL, followed by the
+    /// other hits in their original order.
+    pub fn rescore(
+        &self,
+        engine: &SearchEngine,
+        query: &Query,
+        mut ranked: Vec<(String, f64)>,
+        now: i64,
+    ) -> Vec<(String, f64)> {
+        let extractor = FeatureExtractor::new(engine, query, now);
+        let window = self.window_size.min(ranked.len());
+        for (url, score) in &mut ranked[..window] {
+            let rescore = self
+                .scorers
+                .iter()
+                .map(|scorer| scorer.score(engine, &extractor, url, *score, now))
+                .sum();
+            *score = self.combine(*score, rescore);
+        }
+        ranked[..window].sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        ranked
+    }
+
+    /// Explains the combined score of a rescored hit.
+    ///
+    /// **Arguments**
+    ///
+    /// * `engine` - The engine holding the documents.
+    /// * `query` - The parsed query.
+    /// * `url` - The URL of the document.
+    /// * `original` - The explanation of the score of the first pass.
+    /// * `now` - The current time in milliseconds since the Unix epoch, for function scores.
+    ///
+    /// **Returns**
+    ///
+    /// The explanation, whose value equals the score given by `rescore`.
+    pub fn explain(
+        &self,
+        engine: &SearchEngine,
+        query: &Query,
+        url: &str,
+        original: Explanation,
+        now: i64,
+    ) -> Explanation {
+        let extractor = FeatureExtractor::new(engine, query, now);
+        let details: Vec<Explanation> = self
+            .scorers
+            .iter()
+            .map(|scorer| {
+                let value = scorer.score(engine, &extractor, url, original.value, now);
+                Explanation::new(value, &scorer.to_string())
+            })
+            .collect();
+        let rescore = details.iter().map(|d| d.value).sum();
+        let value = self.combine(original.value, rescore);
+        let description = format!(
+            "rescored with score_mode {}, query_weight {} and rescore_query_weight {}:",
+            self.score_mode, self.query_weight, self.rescore_query_weight
+        );
+        Explanation::new(value, &description).with_details(vec![
+            original,
+            Explanation::new(rescore, "rescore, sum of:").with_details(details),
+        ])
+    }
+
+    fn combine(&self, score: f64, rescore: f64) -> f64 {
+        let score = self.query_weight * score;
+        let rescore = self.rescore_query_weight * rescore;
+        match self.score_mode {
+            ScoreMode::Total => score + rescore,
+            ScoreMode::Multiply => score * rescore,
+            ScoreMode::Avg => (score + rescore) / 2.0,
+            ScoreMode::Max => score.max(rescore),
+            ScoreMode::Min => score.min(rescore),
+        }
+    }
+}
+
+impl Rescorer {
+    fn score(
+        &self,
+        engine: &SearchEngine,
+        extractor: &FeatureExtractor,
+        url: &str,
+        score: f64,
+        now: i64,
+    ) -> f64 {
+        let terms = extractor.terms();
+        match self {
+            Rescorer::Proximity { boost } => {
+                let tokens = analyze(engine.document(url).unwrap_or_default());
+                boost * proximity(&tokens, terms)
+            }
+            Rescorer::FieldMatch { field, boost } => {
+                let text = match (field.as_str(), engine.field(url, field)) {
+                    (_, Some(FieldValue::Keyword(keyword))) => keyword.as_str(),
+                    ("title", _) => engine.title(url).unwrap_or_default(),
+                    _ => "",
+                };
+                let words: HashSet<String> = analyze(text).into_iter().collect();
+                boost * words.intersection(terms).count() as f64
+            }
+            Rescorer::FunctionScore(function_score) => {
+                function_score.score(engine, url, score, now)
+            }
+            Rescorer::Model { name } => match engine.ranking_model(name) {
+                Some(model) => {
+                    let features = extractor.extract(&model.features(), url, score);
+                    model.score(&features)
+                }
+                None => 0.0,
+            },
+        }
+    }
+}
+
+impl fmt::Display for Rescorer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rescorer::Proximity { boost } => {
+                write!(f, "proximity of the query terms, boost {}", boost)
+            }
+            Rescorer::FieldMatch { field, boost } => {
+                write!(f, "query terms in '{}', boost {}", field, boost)
+            }
+            Rescorer::FunctionScore(_) => write!(f, "function score"),
+            Rescorer::Model { name } => write!(f, "ranking model '{}'", name),
+        }
+    }
+}
+
+/// Scores how close together the query terms occur in a document.
+///
+/// **Arguments**
+///
+/// * `tokens` - The tokens of the document.
+/// * `terms` - The query terms.
+///
+/// **Returns**
+///
+/// The number of distinct matched terms divided by the length of the shortest run of
+/// tokens containing all of them, or 0.0 if fewer than two terms match.
+pub fn proximity(tokens: &[String], terms: &HashSet<String>) -> f64 {
+    let matched: HashSet<&String> = tokens.iter().filter(|t| terms.contains(*t)).collect();
+    match min_span(tokens, &matched) {
+        Some(span) if matched.len() > 1 => matched.len() as f64 / span as f64,
+        _ => 0.0,
+    }
+}
+
+/// Returns the length of the shortest run of tokens that contains all of the given terms.
+///
+/// **Arguments**
+///
+/// * `tokens` - The tokens of a document.
+/// * `terms` - The terms to find.
+///
+/// **Returns**
+///
+/// The length of the run, or `None` if a term does not occur.
+pub fn min_span(tokens: &[String], terms: &HashSet<&String>) -> Option<usize> {
+    let mut counts: HashMap<&String, usize> = HashMap::new();
+    let mut best: Option<usize> = None;
+    let mut start = 0;
+    for (end, token) in tokens.iter().enumerate() {
+        if !terms.contains(token) {
+            continue;
+        }
+        *counts.entry(token).or_insert(0) += 1;
+        while counts.len() == terms.len() {
+            let span = end - start + 1;
+            best = Some(best.map_or(span, |best| best.min(span)));
+            let first = &tokens[start];
+            if let Some(count) = counts.get_mut(first) {
+                *count -= 1;
+                if *count == 0 {
+                    counts.remove(first);
+                }
+            }
+            start += 1;
+        }
+    }
+    best.filter(|_| !terms.is_empty())
+}
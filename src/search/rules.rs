@@ -0,0 +1,236 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::search::analyzer::analyze;
+use crate::search::explain::Explanation;
+
+/// How the criteria of a rule are matched against the query.
+///
+/// Both are compared word by word after normalization.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    /// The query consists of exactly the words of the criteria.
+    Exact,
+    /// The query starts with the words of the criteria.
+    Prefix,
+    /// The query contains the words of the criteria in order.
+    Contains,
+}
+
+/// An action a rule takes when it matches.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleAction {
+    /// Places the documents above all other hits, in this order.
+    Pin { urls: Vec<String> },
+    /// Removes the documents from the hits.
+    Hide { urls: Vec<String> },
+    /// Multiplies the scores of the documents.
+    Boost { urls: Vec<String>, factor: f64 },
+    /// Sends the user to a landing page instead.
+    Redirect { url: String },
+}
+
+/// A curation rule applied to the results of matching queries.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryRule {
+    #[serde(rename = "match")]
+    pub kind: MatchKind,
+    pub query: String,
+    pub actions: Vec<RuleAction>,
+}
+
+/// Errors that can occur when a rule is stored.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuleError {
+    EmptyQuery,
+    NoActions,
+    NoUrls,
+    InvalidBoost(f64),
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleError::EmptyQuery => write!(f, "The query of a rule must contain a word"),
+            RuleError::NoActions => write!(f, "A rule needs at least one action"),
+            RuleError::NoUrls => write!(f, "Pin, hide and boost actions need at least one URL"),
+            RuleError::InvalidBoost(factor) => {
+                write!(
+                    f,
+                    "Invalid boost factor {}, expected a positive number",
+                    factor
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for RuleError {}
+
+impl QueryRule {
+    /// Checks that the rule has a query, actions and valid parameters.
+    pub fn validate(&self) -> Result<(), RuleError> {
+        if analyze(&self.query).is_empty() {
+            return Err(RuleError::EmptyQuery);
+        }
+        if self.actions.is_empty() {
+            return Err(RuleError::NoActions);
+        }
+        for action in &self.actions {
+            match action {
+                RuleAction::Pin { urls } | RuleAction::Hide { urls } if urls.is_empty() => {
+                    return Err(RuleError::NoUrls);
+                }
+                RuleAction::Boost { urls, .. } if urls.is_empty() => return Err(RuleError::NoUrls),
+                RuleAction::Boost { factor, .. } if !(factor.is_finite() && *factor > 0.0) => {
+                    return Err(RuleError::InvalidBoost(*factor));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns whether the rule applies to a query.
+    ///
+    /// **Arguments**
+    ///
+    /// * `words` - The analyzed words of the query.
+    pub fn matches(&self, words: &[String]) -> bool {
+        let criteria = analyze(&self.query);
+        match self.kind {
+            MatchKind::Exact => words == criteria.as_slice(),
+            MatchKind::Prefix => words.starts_with(&criteria),
+            MatchKind::Contains => words.windows(criteria.len()).any(|w| w == criteria),
+        }
+    }
+}
+
+/// The combined actions of all rules matching a query.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleOutcome {
+    /// The pinned documents in order, without duplicates and hidden documents.
+    pub pinned: Vec<String>,
+    pub hidden: HashSet<String>,
+    pub boosts: HashMap<String, f64>,
+    /// The first redirect of the matching rules.
+    pub redirect: Option<String>,
+}
+
+impl RuleOutcome {
+    /// Combines the actions of the rules matching a query, in the given order.
+    ///
+    /// **Arguments**
+    ///
+    /// * `rules` - All rules.
+    /// * `query` - The query text.
+    ///
+    /// **Returns**
+    ///
+    /// The outcome. Hiding wins over pinning and boosts of the same document multiply.
+    pub fn evaluate<'a>(rules: impl Iterator<Item = &'a QueryRule>, query: &str) -> RuleOutcome {
+        let words = analyze(query);
+        let mut outcome = RuleOutcome::default();
+        for rule in rules.filter(|rule| rule.matches(&words)) {
+            for action in &rule.actions {
+                match action {
+                    RuleAction::Pin { urls } => outcome.pinned.extend(urls.iter().cloned()),
+                    RuleAction::Hide { urls } => outcome.hidden.extend(urls.iter().cloned()),
+                    RuleAction::Boost { urls, factor } => {
+                        for url in urls {
+                            *outcome.boosts.entry(url.clone()).or_insert(1.0) *= factor;
+                        }
+                    }
+                    RuleAction::Redirect { url } => {
+                        outcome.redirect.get_or_insert_with(|| url.clone());
+                    }
+                }
+            }
+        }
+        let mut seen = HashSet::new();
+        let hidden = &outcome.hidden;
+        outcome
+            .pinned
+            .retain(|url| !hidden.contains(url) && seen.insert(url.clone()));
+        outcome
+    }
+
+    /// Applies the outcome to the filtered and scored hits of a query.
+    ///
+    /// Hidden documents are removed and boosted ones rescored. Pinned documents are added
+    /// with a score of 0.0 if they did not match or were filtered out, as long as they exist.
+    ///
+    /// **Arguments**
+    ///
+    /// * `results` - The scored hits, changed in place.
+    /// * `exists` - Returns whether a document is indexed.
+    pub fn apply(&self, results: &mut HashMap<String, f64>, exists: impl Fn(&str) -> bool) {
+        results.retain(|url, _| !self.hidden.contains(url));
+        for (url, factor) in &self.boosts {
+            if let Some(score) = results.get_mut(url) {
+                *score *= factor;
+            }
+        }
+        for url in &self.pinned {
+            if exists(url) {
+                results.entry(url.clone()).or_insert(0.0);
+            }
+        }
+    }
+
+    /// Adds the boost of the rules to the explanation of a hit.
+    ///
+    /// **Arguments**
+    ///
+    /// * `url` - The URL of the hit.
+    /// * `score` - The explanation of the score before the rules, `None` if it did not match.
+    ///
+    /// **Returns**
+    ///
+    /// The explanation of the final score, which is 0.0 for pinned documents that did not match.
+    pub fn explain(&self, url: &str, score: Option<Explanation>) -> Option<Explanation> {
+        let Some(score) = score else {
+            return self
+                .pinned
+                .iter()
+                .any(|pinned| pinned == url)
+                .then(|| Explanation::new(0.0, "pinned by a query rule without matching"));
+        };
+        match self.boosts.get(url) {
+            Some(factor) => Some(
+                Explanation::new(score.value * factor, "query rule boost, score * factor:")
+                    .with_details(vec![score, Explanation::new(*factor, "factor")]),
+            ),
+            None => Some(score),
+        }
+    }
+
+    /// Moves the pinned documents to the top of ranked hits.
+    ///
+    /// **Arguments**
+    ///
+    /// * `ranked` - The ranked hits.
+    /// * `keep_pinned` - Whether to keep the pinned documents, false for later pages.
+    ///
+    /// **Returns**
+    ///
+    /// The pinned hits in rule order, followed by the other hits in their order.
+    pub fn pin(&self, ranked: Vec<(String, f64)>, keep_pinned: bool) -> Vec<(String, f64)> {
+        if self.pinned.is_empty() {
+            return ranked;
+        }
+        let (mut pinned, rest): (Vec<_>, Vec<_>) = ranked
+            .into_iter()
+            .partition(|(url, _)| self.pinned.contains(url));
+        if !keep_pinned {
+            return rest;
+        }
+        pinned.sort_by_key(|(url, _)| self.pinned.iter().position(|p| p == url));
+        pinned.extend(rest);
+        pinned
+    }
+}
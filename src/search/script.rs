@@ -0,0 +1,606 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::search::engine::SearchEngine;
+use crate::search::explain::Explanation;
+use crate::types::field::{parse_duration, FieldValue};
+
+/// The longest script source accepted.
+pub const MAX_SCRIPT_LENGTH: usize = 1024;
+
+/// The largest number of operations a script may consist of.
+pub const MAX_SCRIPT_NODES: usize = 256;
+
+/// The deepest nesting of parentheses, calls and signs a script may use.
+const MAX_SCRIPT_DEPTH: usize = 32;
+
+/// How long scoring all hits with a script may take when no timeout is given.
+pub const DEFAULT_SCRIPT_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// The longest timeout a script may be given.
+pub const MAX_SCRIPT_TIMEOUT: Duration = Duration::from_secs(5);
+
+const MILLIS_PER_DAY: f64 = 24.0 * 60.0 * 60.0 * 1000.0;
+
+/// Errors that can occur when a script is compiled or run.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptError {
+    TooLong,
+    TooComplex,
+    Syntax { position: usize, message: String },
+    UnknownFunction(String),
+    WrongArity { function: String, expected: usize },
+    InvalidTimeout(String),
+    Timeout(Duration),
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptError::TooLong => {
+                write!(
+                    f,
+                    "Scripts must not be longer than {} characters",
+                    MAX_SCRIPT_LENGTH
+                )
+            }
+            ScriptError::TooComplex => write!(
+                f,
+                "Scripts must not have more than {} operations or be nested deeper than {}",
+                MAX_SCRIPT_NODES, MAX_SCRIPT_DEPTH
+            ),
+            ScriptError::Syntax { position, message } => {
+                write!(f, "Invalid script at position {}: {}", position, message)
+            }
+            ScriptError::UnknownFunction(name) => write!(f, "Unknown script function '{}'", name),
+            ScriptError::WrongArity { function, expected } => write!(
+                f,
+                "Script function '{}' takes {} argument(s)",
+                function, expected
+            ),
+            ScriptError::InvalidTimeout(timeout) => write!(
+                f,
+                "Invalid script timeout '{}', expected e.g. '50ms' up to {}s",
+                timeout,
+                MAX_SCRIPT_TIMEOUT.as_secs()
+            ),
+            ScriptError::Timeout(timeout) => {
+                write!(f, "Script did not finish within {}ms", timeout.as_millis())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+/// A script replacing the score of every hit, as given in a search request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScriptScore {
+    pub source: String,
+    /// How long scoring all hits may take, like `50ms`.
+    #[serde(default)]
+    pub timeout: Option<String>,
+}
+
+impl ScriptScore {
+    /// Compiles the script and applies the timeout.
+    ///
+    /// **Returns**
+    ///
+    /// The compiled script, or an error if the source or the timeout is invalid.
+    pub fn compile(&self) -> Result<Script, ScriptError> {
+        let script = Script::compile(&self.source)?;
+        match &self.timeout {
+            Some(timeout) => {
+                let millis = parse_duration(timeout)
+                    .filter(|millis| *millis > 0)
+                    .map(|millis| Duration::from_millis(millis as u64))
+                    .filter(|timeout| *timeout <= MAX_SCRIPT_TIMEOUT)
+                    .ok_or_else(|| ScriptError::InvalidTimeout(timeout.clone()))?;
+                Ok(script.with_timeout(millis))
+            }
+            None => Ok(script),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Function {
+    Log,
+    Log10,
+    Sqrt,
+    Abs,
+    Exp,
+    Min,
+    Max,
+    Pow,
+}
+
+impl Function {
+    fn parse(name: &str) -> Option<Function> {
+        match name {
+            "log" => Some(Function::Log),
+            "log10" => Some(Function::Log10),
+            "sqrt" => Some(Function::Sqrt),
+            "abs" => Some(Function::Abs),
+            "exp" => Some(Function::Exp),
+            "min" => Some(Function::Min),
+            "max" => Some(Function::Max),
+            "pow" => Some(Function::Pow),
+            _ => None,
+        }
+    }
+
+    fn arity(&self) -> usize {
+        match self {
+            Function::Min | Function::Max | Function::Pow => 2,
+            _ => 1,
+        }
+    }
+
+    fn apply(&self, arguments: &[f64]) -> f64 {
+        match self {
+            Function::Log => arguments[0].ln(),
+            Function::Log10 => arguments[0].log10(),
+            Function::Sqrt => arguments[0].sqrt(),
+            Function::Abs => arguments[0].abs(),
+            Function::Exp => arguments[0].exp(),
+            Function::Min => arguments[0].min(arguments[1]),
+            Function::Max => arguments[0].max(arguments[1]),
+            Function::Pow => arguments[0].powf(arguments[1]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Score,
+    Boost,
+    Field(String),
+    Recency(String),
+    Negate(Box<Expr>),
+    Binary(Operator, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Identifier(String),
+    Text(String),
+    Symbol(char),
+}
+
+// Splits the source into tokens with their positions
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, ScriptError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some(&(position, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut text = String::new();
+            while let Some(&(_, c)) = chars
+                .peek()
+                .filter(|(_, c)| c.is_ascii_digit() || *c == '.')
+            {
+                text.push(c);
+                chars.next();
+            }
+            let number = text.parse().map_err(|_| ScriptError::Syntax {
+                position,
+                message: format!("invalid number '{}'", text),
+            })?;
+            tokens.push((position, Token::Number(number)));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut name = String::new();
+            while let Some(&(_, c)) = chars
+                .peek()
+                .filter(|(_, c)| c.is_ascii_alphanumeric() || *c == '_')
+            {
+                name.push(c);
+                chars.next();
+            }
+            tokens.push((position, Token::Identifier(name)));
+        } else if c == '\'' || c == '"' {
+            chars.next();
+            let mut text = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, end)) if end == c => break,
+                    Some((_, other)) => text.push(other),
+                    None => {
+                        return Err(ScriptError::Syntax {
+                            position,
+                            message: "unterminated string".to_string(),
+                        })
+                    }
+                }
+            }
+            tokens.push((position, Token::Text(text)));
+        } else if "+-*/%()[],".contains(c) {
+            chars.next();
+            tokens.push((position, Token::Symbol(c)));
+        } else {
+            return Err(ScriptError::Syntax {
+                position,
+                message: format!("unexpected character '{}'", c),
+            });
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    end: usize,
+    nodes: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(_, token)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.next)
+            .map_or(self.end, |(position, _)| *position)
+    }
+
+    fn error(&self, message: &str) -> ScriptError {
+        ScriptError::Syntax {
+            position: self.position(),
+            message: message.to_string(),
+        }
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), ScriptError> {
+        match self.peek() {
+            Some(Token::Symbol(c)) if *c == symbol => {
+                self.next += 1;
+                Ok(())
+            }
+            _ => Err(self.error(&format!("expected '{}'", symbol))),
+        }
+    }
+
+    // Counts every node against the limit, so evaluation time is bounded by the script size
+    fn node(&mut self, expr: Expr) -> Result<Expr, ScriptError> {
+        self.nodes += 1;
+        if self.nodes > MAX_SCRIPT_NODES {
+            return Err(ScriptError::TooComplex);
+        }
+        Ok(expr)
+    }
+
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Parser) -> Result<T, ScriptError>,
+    ) -> Result<T, ScriptError> {
+        self.depth += 1;
+        if self.depth > MAX_SCRIPT_DEPTH {
+            return Err(ScriptError::TooComplex);
+        }
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn expression(&mut self) -> Result<Expr, ScriptError> {
+        let mut left = self.term()?;
+        while let Some(Token::Symbol(c @ ('+' | '-'))) = self.peek() {
+            let operator = if *c == '+' {
+                Operator::Add
+            } else {
+                Operator::Subtract
+            };
+            self.next += 1;
+            let right = self.term()?;
+            left = self.node(Expr::Binary(operator, Box::new(left), Box::new(right)))?;
+        }
+        Ok(left)
+    }
+
+    fn term(&mut self) -> Result<Expr, ScriptError> {
+        let mut left = self.unary()?;
+        while let Some(Token::Symbol(c @ ('*' | '/' | '%'))) = self.peek() {
+            let operator = match c {
+                '*' => Operator::Multiply,
+                '/' => Operator::Divide,
+                _ => Operator::Remainder,
+            };
+            self.next += 1;
+            let right = self.unary()?;
+            left = self.node(Expr::Binary(operator, Box::new(left), Box::new(right)))?;
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ScriptError> {
+        match self.peek() {
+            Some(Token::Symbol('-')) => {
+                self.next += 1;
+                let operand = self.nested(Parser::unary)?;
+                self.node(Expr::Negate(Box::new(operand)))
+            }
+            Some(Token::Symbol('+')) => {
+                self.next += 1;
+                self.nested(Parser::unary)
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, ScriptError> {
+        let Some((_, token)) = self.tokens.get(self.next).cloned() else {
+            return Err(self.error("unexpected end of script"));
+        };
+        self.next += 1;
+        let expr = match token {
+            Token::Number(number) => Expr::Number(number),
+            Token::Symbol('(') => {
+                let expr = self.nested(Parser::expression)?;
+                self.expect(')')?;
+                return Ok(expr);
+            }
+            Token::Identifier(name) => match name.as_str() {
+                "_score" => Expr::Score,
+                "_boost" => Expr::Boost,
+                "doc" => {
+                    self.expect('[')?;
+                    let field = self.text()?;
+                    self.expect(']')?;
+                    Expr::Field(field)
+                }
+                "recency" => {
+                    self.expect('(')?;
+                    let field = self.text()?;
+                    self.expect(')')?;
+                    Expr::Recency(field)
+                }
+                _ => {
+                    let function =
+                        Function::parse(&name).ok_or(ScriptError::UnknownFunction(name.clone()))?;
+                    self.expect('(')?;
+                    let arguments = self.nested(Parser::arguments)?;
+                    if arguments.len() != function.arity() {
+                        return Err(ScriptError::WrongArity {
+                            function: name,
+                            expected: function.arity(),
+                        });
+                    }
+                    Expr::Call(function, arguments)
+                }
+            },
+            _ => {
+                self.next -= 1;
+                return Err(self.error("expected a number, a variable or a function"));
+            }
+        };
+        self.node(expr)
+    }
+
+    fn arguments(&mut self) -> Result<Vec<Expr>, ScriptError> {
+        let mut arguments = Vec::new();
+        if let Some(Token::Symbol(')')) = self.peek() {
+            self.next += 1;
+            return Ok(arguments);
+        }
+        loop {
+            arguments.push(self.expression()?);
+            match self.peek() {
+                Some(Token::Symbol(',')) => self.next += 1,
+                _ => break,
+            }
+        }
+        self.expect(')')?;
+        Ok(arguments)
+    }
+
+    fn text(&mut self) -> Result<String, ScriptError> {
+        match self.peek().cloned() {
+            Some(Token::Text(text)) => {
+                self.next += 1;
+                Ok(text)
+            }
+            _ => Err(self.error("expected a quoted field name")),
+        }
+    }
+}
+
+/// A compiled scoring script.
+///
+/// Scripts are arithmetic expressions over the score of a hit, its document boost and the
+/// numeric and date fields of its document. They cannot loop, call out or change anything,
+/// so their cost is bounded by their size, and a timeout bounds the cost of a whole request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Script {
+    source: String,
+    expr: Expr,
+    timeout: Duration,
+}
+
+impl Script {
+    /// Compiles a script like `_score * log(1 + doc['views']) + 0.1 * recency('published')`.
+    ///
+    /// Scripts support numbers, `+`, `-`, `*`, `/`, `%`, parentheses and:
+    ///
+    /// * `_score` - The score of the hit.
+    /// * `_boost` - The boost of the document, 1.0 if it has none.
+    /// * `doc['field']` - A numeric field, or a date field in milliseconds since the Unix
+    ///   epoch. 0.0 if the document lacks it.
+    /// * `recency('field')` - `1 / (1 + age in days)` of a date field, 0.0 if the document
+    ///   lacks it.
+    /// * `log`, `log10`, `sqrt`, `abs`, `exp`, `min`, `max` and `pow`.
+    ///
+    /// **Arguments**
+    ///
+    /// * `source` - The source of the script.
+    ///
+    /// **Returns**
+    ///
+    /// The compiled script, or an error if it is malformed, too long or too complex.
+    pub fn compile(source: &str) -> Result<Script, ScriptError> {
+        if source.len() > MAX_SCRIPT_LENGTH {
+            return Err(ScriptError::TooLong);
+        }
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            next: 0,
+            end: source.len(),
+            nodes: 0,
+            depth: 0,
+        };
+        let expr = parser.expression()?;
+        if parser.next < parser.tokens.len() {
+            return Err(parser.error("unexpected input"));
+        }
+        Ok(Script {
+            source: source.to_string(),
+            expr,
+            timeout: DEFAULT_SCRIPT_TIMEOUT,
+        })
+    }
+
+    /// Sets how long scoring all hits may take.
+    pub fn with_timeout(mut self, timeout: Duration) -> Script {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Returns the source of the script.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Scores a hit.
+    ///
+    /// **Arguments**
+    ///
+    /// * `engine` - The engine holding the fields and boosts of the document.
+    /// * `url` - The URL of the document.
+    /// * `score` - The score of the hit.
+    /// * `now` - The current time in milliseconds since the Unix epoch, for `recency`.
+    ///
+    /// **Returns**
+    ///
+    /// The new score, 0.0 if the result is not a finite number.
+    pub fn evaluate(&self, engine: &SearchEngine, url: &str, score: f64, now: i64) -> f64 {
+        let value = self.evaluate_expr(&self.expr, engine, url, score, now);
+        if value.is_finite() {
+            value
+        } else {
+            0.0
+        }
+    }
+
+    /// Replaces the scores of all hits with the result of the script.
+    ///
+    /// **Arguments**
+    ///
+    /// * `engine` - The engine holding the fields and boosts of the documents.
+    /// * `results` - The scored hits, changed in place.
+    /// * `now` - The current time in milliseconds since the Unix epoch, for `recency`.
+    ///
+    /// **Returns**
+    ///
+    /// An error if scoring took longer than the timeout.
+    pub fn score_all(
+        &self,
+        engine: &SearchEngine,
+        results: &mut HashMap<String, f64>,
+        now: i64,
+    ) -> Result<(), ScriptError> {
+        let start = Instant::now();
+        for (url, score) in results.iter_mut() {
+            if start.elapsed() >= self.timeout {
+                return Err(ScriptError::Timeout(self.timeout));
+            }
+            *score = self.evaluate(engine, url, *score, now);
+        }
+        Ok(())
+    }
+
+    /// Explains the script score of a hit, wrapping the explanation of its previous score.
+    ///
+    /// **Arguments**
+    ///
+    /// * `engine` - The engine holding the fields and boosts of the document.
+    /// * `url` - The URL of the document.
+    /// * `score` - The explanation of the previous score.
+    /// * `now` - The current time in milliseconds since the Unix epoch, for `recency`.
+    ///
+    /// **Returns**
+    ///
+    /// The explanation, whose value equals `evaluate`.
+    pub fn explain(
+        &self,
+        engine: &SearchEngine,
+        url: &str,
+        score: Explanation,
+        now: i64,
+    ) -> Explanation {
+        let value = self.evaluate(engine, url, score.value, now);
+        let description = format!("script score, computed as {} from:", self.source);
+        Explanation::new(value, &description).with_details(vec![score])
+    }
+
+    fn evaluate_expr(
+        &self,
+        expr: &Expr,
+        engine: &SearchEngine,
+        url: &str,
+        score: f64,
+        now: i64,
+    ) -> f64 {
+        let evaluate = |expr: &Expr| self.evaluate_expr(expr, engine, url, score, now);
+        match expr {
+            Expr::Number(number) => *number,
+            Expr::Score => score,
+            Expr::Boost => engine.boost(url),
+            Expr::Field(field) => match engine.field(url, field) {
+                Some(FieldValue::Number(number)) => *number,
+                Some(FieldValue::Date(date)) => *date as f64,
+                _ => 0.0,
+            },
+            Expr::Recency(field) => match engine.field(url, field) {
+                Some(FieldValue::Date(date)) => {
+                    let age = (now - date).max(0) as f64 / MILLIS_PER_DAY;
+                    1.0 / (1.0 + age)
+                }
+                _ => 0.0,
+            },
+            Expr::Negate(operand) => -evaluate(operand),
+            Expr::Binary(operator, left, right) => {
+                let (left, right) = (evaluate(left), evaluate(right));
+                match operator {
+                    Operator::Add => left + right,
+                    Operator::Subtract => left - right,
+                    Operator::Multiply => left * right,
+                    Operator::Divide => left / right,
+                    Operator::Remainder => left % right,
+                }
+            }
+            Expr::Call(function, arguments) => {
+                let arguments: Vec<f64> = arguments.iter().map(evaluate).collect();
+                function.apply(&arguments)
+            }
+        }
+    }
+}
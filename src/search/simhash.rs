@@ -0,0 +1,68 @@
+use crate::search::analyzer::analyze;
+
+/// The number of consecutive words hashed together as one feature of a document.
+pub const SHINGLE_SIZE: usize = 3;
+
+/// The maximum number of differing bits between the signatures of near-duplicates.
+pub const MAX_DUPLICATE_DISTANCE: u32 = 7;
+
+/// The number of bands a signature is split into to find candidate duplicates.
+///
+/// Signatures at most `MAX_DUPLICATE_DISTANCE` bits apart agree on at least one band.
+pub const NUMBER_OF_BANDS: usize = MAX_DUPLICATE_DISTANCE as usize + 1;
+
+const BAND_BITS: usize = 64 / NUMBER_OF_BANDS;
+
+/// Computes the SimHash signature of a text from its word shingles.
+///
+/// Similar texts get signatures that differ in few bits. Texts shorter than a shingle are
+/// hashed as a single shingle.
+///
+/// **Arguments**
+///
+/// * `text` - The text to sign.
+///
+/// **Returns**
+///
+/// The signature, or `None` if the text has no words.
+pub fn simhash(text: &str) -> Option<u64> {
+    let tokens = analyze(text);
+    if tokens.is_empty() {
+        return None;
+    }
+    let mut counts = [0i64; 64];
+    for shingle in tokens.windows(SHINGLE_SIZE.min(tokens.len())) {
+        let hash = fnv1a(&shingle.join(" "));
+        for (bit, count) in counts.iter_mut().enumerate() {
+            if hash >> bit & 1 == 1 {
+                *count += 1;
+            } else {
+                *count -= 1;
+            }
+        }
+    }
+    let signature = counts
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count > 0)
+        .fold(0u64, |signature, (bit, _)| signature | 1 << bit);
+    Some(signature)
+}
+
+/// Returns whether two signatures belong to near-duplicate texts.
+pub fn is_near_duplicate(a: u64, b: u64) -> bool {
+    (a ^ b).count_ones() <= MAX_DUPLICATE_DISTANCE
+}
+
+/// Splits a signature into its bands, each tagged with its position.
+pub fn bands(signature: u64) -> [(usize, u64); NUMBER_OF_BANDS] {
+    let mask = (1u64 << BAND_BITS) - 1;
+    std::array::from_fn(|band| (band, signature >> (band * BAND_BITS) & mask))
+}
+
+// The signatures are stored with the index, so the hash must not change between builds
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
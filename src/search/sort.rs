@@ -0,0 +1,152 @@
+use std::cmp::Ordering;
+
+use crate::search::query::QueryError;
+use crate::types::field::FieldValue;
+
+/// The direction of a sort key.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// Where documents without a value for a sort field are placed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Missing {
+    First,
+    Last,
+}
+
+/// What a sort key orders by.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SortTarget {
+    /// The relevance score.
+    Score,
+    /// The value of a doc-value field.
+    Field(String),
+}
+
+/// A single key of a sort, written as `score`, `field`, `field:desc` or `field:desc:first`.
+///
+/// The score sorts descending by default, fields ascending. Documents without the
+/// field come last unless `first` is given, independent of the order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortKey {
+    pub target: SortTarget,
+    pub order: SortOrder,
+    pub missing: Missing,
+}
+
+impl SortKey {
+    /// Sorts by descending score, the order used when no sort is given.
+    pub fn score() -> SortKey {
+        SortKey {
+            target: SortTarget::Score,
+            order: SortOrder::Desc,
+            missing: Missing::Last,
+        }
+    }
+
+    /// Parses sort keys separated by `,`, e.g. `published:desc,score`.
+    ///
+    /// **Arguments**
+    ///
+    /// * `text` - The sort keys, the first one has the highest priority.
+    ///
+    /// **Returns**
+    ///
+    /// The parsed sort keys, or an error if one of them is malformed.
+    pub fn parse_all(text: &str) -> Result<Vec<SortKey>, QueryError> {
+        text.split(',')
+            .filter(|k| !k.trim().is_empty())
+            .map(SortKey::parse)
+            .collect()
+    }
+
+    /// Parses a single sort key.
+    ///
+    /// **Arguments**
+    ///
+    /// * `text` - The sort key.
+    ///
+    /// **Returns**
+    ///
+    /// The parsed sort key, or an error if it is malformed.
+    pub fn parse(text: &str) -> Result<SortKey, QueryError> {
+        let invalid = || QueryError::InvalidSort(text.trim().to_string());
+        let mut parts = text.split(':').map(str::trim);
+
+        let mut key = match parts.next() {
+            Some("score" | "_score") => SortKey::score(),
+            Some(field) if !field.is_empty() => SortKey {
+                target: SortTarget::Field(field.to_string()),
+                order: SortOrder::Asc,
+                missing: Missing::Last,
+            },
+            _ => return Err(invalid()),
+        };
+        if let Some(order) = parts.next() {
+            key.order = match order.to_lowercase().as_str() {
+                "asc" => SortOrder::Asc,
+                "desc" => SortOrder::Desc,
+                _ => return Err(invalid()),
+            };
+        }
+        if let Some(missing) = parts.next() {
+            key.missing = match missing.to_lowercase().as_str() {
+                "first" => Missing::First,
+                "last" => Missing::Last,
+                _ => return Err(invalid()),
+            };
+        }
+        match parts.next() {
+            Some(_) => Err(invalid()),
+            None => Ok(key),
+        }
+    }
+
+    /// Compares the values two documents have for this key.
+    ///
+    /// Values of different types are ordered numbers before dates before keywords.
+    ///
+    /// **Arguments**
+    ///
+    /// * `a` - The value of the first document, `None` if it is missing.
+    /// * `b` - The value of the second document, `None` if it is missing.
+    ///
+    /// **Returns**
+    ///
+    /// The ordering of the first document relative to the second one.
+    pub fn compare(&self, a: Option<&FieldValue>, b: Option<&FieldValue>) -> Ordering {
+        match (a, b) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) if self.missing == Missing::First => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(_), None) => self.compare(b, a).reverse(),
+            (Some(a), Some(b)) => {
+                let ordering = compare_values(a, b);
+                match self.order {
+                    SortOrder::Asc => ordering,
+                    SortOrder::Desc => ordering.reverse(),
+                }
+            }
+        }
+    }
+}
+
+fn compare_values(a: &FieldValue, b: &FieldValue) -> Ordering {
+    match (a, b) {
+        (FieldValue::Number(a), FieldValue::Number(b)) => a.total_cmp(b),
+        (FieldValue::Date(a), FieldValue::Date(b)) => a.cmp(b),
+        (FieldValue::Keyword(a), FieldValue::Keyword(b)) => a.cmp(b),
+        _ => type_rank(a).cmp(&type_rank(b)),
+    }
+}
+
+fn type_rank(value: &FieldValue) -> u8 {
+    match value {
+        FieldValue::Number(_) => 0,
+        FieldValue::Date(_) => 1,
+        FieldValue::Keyword(_) => 2,
+    }
+}
@@ -0,0 +1,56 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::search::fuzzy::fuzzy_terms;
+use crate::search::query::Fuzziness;
+
+/// Probability that a user makes a single typing error, used by the noisy-channel model.
+const EDIT_PROBABILITY: f64 = 0.01;
+
+/// Weight of a logged query occurrence compared to a document containing the term.
+const QUERY_LOG_WEIGHT: f64 = 2.0;
+
+/// Finds the most likely correction of a term that is missing from the dictionary.
+///
+/// Candidates within the automatic fuzziness of the term are ranked with a noisy-channel
+/// model: the prior of a candidate grows with its document frequency (and, if given, with
+/// how often it was searched for) and every edit multiplies the likelihood by `EDIT_PROBABILITY`.
+///
+/// **Arguments**
+///
+/// * `dictionary` - The sorted term dictionary.
+/// * `term` - The term to correct.
+/// * `document_frequency` - Returns the document frequency of a dictionary entry.
+/// * `query_log` - Optional counts of terms from previous successful queries.
+///
+/// **Returns**
+///
+/// The best correction, or `None` if no dictionary term is close enough.
+pub fn correct_term<V>(
+    dictionary: &BTreeMap<String, V>,
+    term: &str,
+    document_frequency: impl Fn(&V) -> usize,
+    query_log: Option<&HashMap<String, u32>>,
+) -> Option<String> {
+    let max_distance = Fuzziness::Auto.max_distance(term);
+    if max_distance == 0 {
+        return None;
+    }
+
+    let score = |candidate: &str, distance: u8| {
+        let logged = query_log
+            .and_then(|log| log.get(candidate))
+            .map_or(0.0, |count| *count as f64 * QUERY_LOG_WEIGHT);
+        let prior = (document_frequency(&dictionary[candidate]) as f64 + logged + 1.0).ln();
+        prior + distance as f64 * EDIT_PROBABILITY.ln()
+    };
+
+    fuzzy_terms(dictionary, term, max_distance)
+        .into_iter()
+        .filter(|(_, distance)| *distance > 0)
+        .map(|(candidate, distance)| {
+            let score = score(&candidate, distance);
+            (candidate, score)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+        .map(|(candidate, _)| candidate)
+}
@@ -0,0 +1,196 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use std::ops::Bound;
+
+use serde::{Deserialize, Serialize};
+
+/// The number of completions kept for every short prefix.
+pub const MAX_PRECOMPUTED_COMPLETIONS: usize = 50;
+
+/// Prefixes of up to this many characters have their completions precomputed.
+const PRECOMPUTED_PREFIX_LENGTH: usize = 3;
+
+/// Where a suggestion was taken from.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionSource {
+    Term,
+    Title,
+}
+
+/// A single completion returned by the suggester.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Suggestion {
+    pub text: String,
+    pub weight: usize,
+    pub source: SuggestionSource,
+}
+
+impl Suggestion {
+    /// Orders suggestions by weight, heaviest first, and then alphabetically.
+    pub fn rank(a: &Suggestion, b: &Suggestion) -> Ordering {
+        b.weight.cmp(&a.weight).then_with(|| a.text.cmp(&b.text))
+    }
+}
+
+/// Returns the heaviest keys of a sorted dictionary that start with the given prefix.
+///
+/// Only the range of keys sharing the prefix is visited and a bounded heap keeps
+/// the best `size` entries, so short prefixes stay cheap on large dictionaries.
+///
+/// **Arguments**
+///
+/// * `dictionary` - The sorted dictionary.
+/// * `prefix` - The prefix to complete.
+/// * `size` - The maximum number of completions.
+/// * `weight` - Computes the weight of a dictionary entry.
+///
+/// **Returns**
+///
+/// The completions with their weight, heaviest first.
+pub fn top_completions<'a, V>(
+    dictionary: &'a BTreeMap<String, V>,
+    prefix: &str,
+    size: usize,
+    weight: impl Fn(&V) -> usize,
+) -> Vec<(&'a String, usize)> {
+    let mut heap = BinaryHeap::with_capacity(size + 1);
+    let completions = dictionary
+        .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+        .take_while(|(key, _)| key.starts_with(prefix));
+    for (key, value) in completions {
+        // The heap holds the best entries seen so far with the worst one on top
+        heap.push((Reverse(weight(value)), key));
+        if heap.len() > size {
+            heap.pop();
+        }
+    }
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|(Reverse(weight), key)| (key, weight))
+        .collect()
+}
+
+/// A completion dictionary with precomputed weights, kept up to date as the index changes.
+///
+/// Short prefixes match large parts of the dictionary, so their heaviest completions are
+/// precomputed and answered without a scan. Longer prefixes only match a few keys and are
+/// completed from the sorted keys.
+#[derive(Debug, Clone, Default)]
+pub struct Completions {
+    // Every key with its weight, sorted for prefix range scans
+    weights: BTreeMap<String, usize>,
+    // The heaviest completions of every short prefix, heaviest first. A list shorter than
+    // MAX_PRECOMPUTED_COMPLETIONS holds all keys with the prefix.
+    best: HashMap<String, Vec<(String, usize)>>,
+}
+
+impl Completions {
+    /// Builds the completions of a dictionary.
+    ///
+    /// **Arguments**
+    ///
+    /// * `entries` - The keys with their weights.
+    ///
+    /// **Returns**
+    ///
+    /// The completions.
+    pub fn build<'a>(entries: impl IntoIterator<Item = (&'a String, usize)>) -> Completions {
+        let mut completions = Completions::default();
+        for (key, weight) in entries {
+            completions.set(key, weight);
+        }
+        completions
+    }
+
+    /// Sets the weight of a key.
+    ///
+    /// **Arguments**
+    ///
+    /// * `key` - The key.
+    /// * `weight` - The new weight, 0 removes the key.
+    pub fn set(&mut self, key: &str, weight: usize) {
+        let old_weight = if weight == 0 {
+            self.weights.remove(key)
+        } else {
+            self.weights.insert(key.to_string(), weight)
+        }
+        .unwrap_or(0);
+        if old_weight == weight {
+            return;
+        }
+
+        let weights = &self.weights;
+        for (length, _) in key.char_indices().skip(1).take(PRECOMPUTED_PREFIX_LENGTH) {
+            update_best(
+                &mut self.best,
+                weights,
+                &key[..length],
+                key,
+                weight,
+                old_weight,
+            );
+        }
+        if key.chars().count() <= PRECOMPUTED_PREFIX_LENGTH {
+            update_best(&mut self.best, weights, key, key, weight, old_weight);
+        }
+    }
+
+    /// Returns the heaviest keys starting with a prefix.
+    ///
+    /// **Arguments**
+    ///
+    /// * `prefix` - The prefix to complete.
+    /// * `size` - The maximum number of completions.
+    ///
+    /// **Returns**
+    ///
+    /// The completions with their weight, heaviest first.
+    pub fn complete(&self, prefix: &str, size: usize) -> Vec<(&str, usize)> {
+        let precomputed = !prefix.is_empty()
+            && prefix.chars().count() <= PRECOMPUTED_PREFIX_LENGTH
+            && size <= MAX_PRECOMPUTED_COMPLETIONS;
+        if precomputed {
+            return self.best.get(prefix).map_or_else(Vec::new, |best| {
+                best.iter()
+                    .take(size)
+                    .map(|(key, weight)| (key.as_str(), *weight))
+                    .collect()
+            });
+        }
+        top_completions(&self.weights, prefix, size, |weight| *weight)
+            .into_iter()
+            .map(|(key, weight)| (key.as_str(), weight))
+            .collect()
+    }
+}
+
+// Moves a key whose weight changed within the precomputed completions of a prefix
+fn update_best(
+    best: &mut HashMap<String, Vec<(String, usize)>>,
+    weights: &BTreeMap<String, usize>,
+    prefix: &str,
+    key: &str,
+    weight: usize,
+    old_weight: usize,
+) {
+    let completions = best.entry(prefix.to_string()).or_default();
+    let was_full = completions.len() == MAX_PRECOMPUTED_COMPLETIONS;
+    completions.retain(|(completion, _)| completion != key);
+    if weight < old_weight && was_full {
+        // A key left out so far may now be heavier, so the list is recomputed
+        *completions = top_completions(weights, prefix, MAX_PRECOMPUTED_COMPLETIONS, |w| *w)
+            .into_iter()
+            .map(|(key, weight)| (key.clone(), weight))
+            .collect();
+    } else if weight > 0 {
+        let position = completions.partition_point(|(other, other_weight)| {
+            (Reverse(*other_weight), other.as_str()) < (Reverse(weight), key)
+        });
+        completions.insert(position, (key.to_string(), weight));
+        completions.truncate(MAX_PRECOMPUTED_COMPLETIONS);
+    }
+    if completions.is_empty() {
+        best.remove(prefix);
+    }
+}
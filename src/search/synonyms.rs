@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::search::analyzer::analyze;
+
+/// A synonym rule that could not be parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidSynonymRule {
+    pub line: usize,
+    pub rule: String,
+}
+
+impl fmt::Display for InvalidSynonymRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid synonym rule on line {}: '{}'",
+            self.line, self.rule
+        )
+    }
+}
+
+impl std::error::Error for InvalidSynonymRule {}
+
+/// A run of query tokens together with the token sequences it may be replaced with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SynonymSpan {
+    pub original: Vec<String>,
+    pub alternatives: Vec<Vec<String>>,
+}
+
+/// A set of synonym rules in the Solr/WordNet text format.
+///
+/// Each line is either a list of equivalent phrases (`rs, rustlang, rust programming language`)
+/// or an explicit mapping that replaces the left side with the right side (`rustc => rust compiler`).
+/// Empty lines and lines starting with `#` are ignored. Phrases are analyzed like indexed content.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SynonymSet {
+    rules: Vec<String>,
+    mappings: HashMap<Vec<String>, Vec<Vec<String>>>,
+    longest_input: usize,
+}
+
+impl SynonymSet {
+    /// Parses a synonym file.
+    ///
+    /// **Arguments**
+    ///
+    /// * `text` - The content of the synonym file.
+    ///
+    /// **Returns**
+    ///
+    /// The synonym set, or the first rule that could not be parsed.
+    pub fn parse(text: &str) -> Result<SynonymSet, InvalidSynonymRule> {
+        let rules: Vec<String> = text.lines().map(|l| l.to_string()).collect();
+        SynonymSet::from_rules(&rules)
+    }
+
+    /// Builds a synonym set from individual rules.
+    ///
+    /// **Arguments**
+    ///
+    /// * `rules` - The rules, one per entry.
+    ///
+    /// **Returns**
+    ///
+    /// The synonym set, or the first rule that could not be parsed.
+    pub fn from_rules(rules: &[String]) -> Result<SynonymSet, InvalidSynonymRule> {
+        let mut set = SynonymSet::default();
+        for (number, rule) in rules.iter().enumerate() {
+            let rule = rule.trim();
+            if rule.is_empty() || rule.starts_with('#') {
+                continue;
+            }
+            let invalid = || InvalidSynonymRule {
+                line: number + 1,
+                rule: rule.to_string(),
+            };
+            match rule.split_once("=>") {
+                Some((inputs, outputs)) => {
+                    let inputs = parse_phrases(inputs).ok_or_else(invalid)?;
+                    let outputs = parse_phrases(outputs).ok_or_else(invalid)?;
+                    for input in inputs {
+                        set.add_mapping(input, &outputs);
+                    }
+                }
+                None => {
+                    let phrases = parse_phrases(rule).filter(|p| p.len() > 1);
+                    let phrases = phrases.ok_or_else(invalid)?;
+                    for phrase in &phrases {
+                        set.add_mapping(phrase.clone(), &phrases);
+                    }
+                }
+            }
+            set.rules.push(rule.to_string());
+        }
+        Ok(set)
+    }
+
+    fn add_mapping(&mut self, input: Vec<String>, outputs: &[Vec<String>]) {
+        self.longest_input = self.longest_input.max(input.len());
+        let alternatives = self.mappings.entry(input).or_default();
+        for output in outputs {
+            if !alternatives.contains(output) {
+                alternatives.push(output.clone());
+            }
+        }
+    }
+
+    /// Returns the rules of this set without comments and empty lines.
+    pub fn rules(&self) -> &[String] {
+        &self.rules
+    }
+
+    /// Returns true if the set contains no rules.
+    pub fn is_empty(&self) -> bool {
+        self.mappings.is_empty()
+    }
+
+    /// Splits a token sequence into spans and attaches the synonyms of every span.
+    ///
+    /// At each position the longest sequence of tokens with synonyms wins, so multi-word
+    /// synonyms form a single span whose alternatives may have different lengths. Together
+    /// the spans describe every path through the synonym graph of the tokens.
+    ///
+    /// **Arguments**
+    ///
+    /// * `tokens` - The analyzed tokens.
+    ///
+    /// **Returns**
+    ///
+    /// The spans in token order. Spans without synonyms only have the original tokens as alternative.
+    pub fn graph(&self, tokens: &[String]) -> Vec<SynonymSpan> {
+        let mut spans = Vec::new();
+        let mut position = 0;
+        while position < tokens.len() {
+            let longest = self.longest_input.min(tokens.len() - position);
+            let matched = (1..=longest).rev().find_map(|length| {
+                let input = &tokens[position..position + length];
+                self.mappings.get(input).map(|outputs| (length, outputs))
+            });
+            let (length, alternatives) = match matched {
+                Some((length, outputs)) => (length, outputs.clone()),
+                None => (1, vec![vec![tokens[position].clone()]]),
+            };
+            spans.push(SynonymSpan {
+                original: tokens[position..position + length].to_vec(),
+                alternatives,
+            });
+            position += length;
+        }
+        spans
+    }
+}
+
+fn parse_phrases(list: &str) -> Option<Vec<Vec<String>>> {
+    let phrases: Vec<Vec<String>> = list.split(',').map(analyze).collect();
+    (!phrases.is_empty() && phrases.iter().all(|p| !p.is_empty())).then_some(phrases)
+}
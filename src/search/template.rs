@@ -0,0 +1,210 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// A stored search body with `{{name}}` placeholders, rendered with the parameters of a request.
+///
+/// A string consisting of a single placeholder is replaced by the parameter value itself, so
+/// numbers, booleans and arrays keep their type, and an object field whose value renders to
+/// `null` is left out. Placeholders inside longer strings are replaced by the text of the
+/// parameter, which must then be a string, number or boolean. Strings inserted into text only
+/// ever add literal words: every word is quoted inside a `query`, the whole value is quoted
+/// inside a `filter`, and elsewhere they must be a single word.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchTemplate {
+    /// The search body in the format of `POST /search`.
+    pub source: Value,
+    /// Values of parameters the request does not pass.
+    #[serde(default)]
+    pub params: BTreeMap<String, Value>,
+}
+
+/// Errors that can occur when a template is stored or rendered.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateError {
+    NotAnObject,
+    InvalidPlaceholder(String),
+    MissingParam(String),
+    InvalidParam(String),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::NotAnObject => write!(f, "The source of a template must be an object"),
+            TemplateError::InvalidPlaceholder(text) => {
+                write!(f, "Invalid placeholder in '{}'", text)
+            }
+            TemplateError::MissingParam(name) => write!(f, "Missing template parameter '{}'", name),
+            TemplateError::InvalidParam(name) => write!(
+                f,
+                "Template parameter '{}' must be a string, number or boolean inside text, \
+                 and a single word outside of queries and filters",
+                name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+// A piece of a string in the source
+enum Part<'a> {
+    Text(&'a str),
+    Param(&'a str),
+}
+
+// The syntax of a string in the source, which decides how parameters are escaped inside it
+#[derive(Clone, Copy)]
+enum Syntax {
+    Query,
+    Filter,
+    Other,
+}
+
+impl Syntax {
+    fn of(key: &str) -> Syntax {
+        match key {
+            "query" => Syntax::Query,
+            "filter" => Syntax::Filter,
+            _ => Syntax::Other,
+        }
+    }
+}
+
+impl SearchTemplate {
+    /// Checks that the source is an object and all its placeholders are well-formed.
+    pub fn validate(&self) -> Result<(), TemplateError> {
+        if !self.source.is_object() {
+            return Err(TemplateError::NotAnObject);
+        }
+        check(&self.source)
+    }
+
+    /// Fills in the placeholders of the source.
+    ///
+    /// **Arguments**
+    ///
+    /// * `params` - The parameter values, taking precedence over the defaults of the template.
+    ///
+    /// **Returns**
+    ///
+    /// The rendered search body, or an error if a parameter is missing or cannot be inserted.
+    pub fn render(&self, params: &BTreeMap<String, Value>) -> Result<Value, TemplateError> {
+        let lookup = |name: &str| {
+            params
+                .get(name)
+                .or_else(|| self.params.get(name))
+                .ok_or_else(|| TemplateError::MissingParam(name.to_string()))
+        };
+        render(&self.source, Syntax::Other, &lookup)
+    }
+}
+
+fn check(value: &Value) -> Result<(), TemplateError> {
+    match value {
+        Value::String(text) => parse(text).map(|_| ()),
+        Value::Array(values) => values.iter().try_for_each(check),
+        Value::Object(map) => map.values().try_for_each(check),
+        _ => Ok(()),
+    }
+}
+
+fn render<'a>(
+    value: &Value,
+    syntax: Syntax,
+    lookup: &impl Fn(&str) -> Result<&'a Value, TemplateError>,
+) -> Result<Value, TemplateError> {
+    match value {
+        Value::String(text) => render_string(text, syntax, lookup),
+        Value::Array(values) => values.iter().map(|v| render(v, syntax, lookup)).collect(),
+        Value::Object(map) => {
+            let mut rendered = Map::new();
+            for (key, value) in map {
+                match render(value, Syntax::of(key), lookup)? {
+                    Value::Null if !value.is_null() => {}
+                    value => {
+                        rendered.insert(key.clone(), value);
+                    }
+                }
+            }
+            Ok(Value::Object(rendered))
+        }
+        _ => Ok(value.clone()),
+    }
+}
+
+fn render_string<'a>(
+    text: &str,
+    syntax: Syntax,
+    lookup: &impl Fn(&str) -> Result<&'a Value, TemplateError>,
+) -> Result<Value, TemplateError> {
+    let parts = parse(text)?;
+    if let [Part::Param(name)] = parts.as_slice() {
+        return lookup(name).cloned();
+    }
+    let mut rendered = String::new();
+    for part in parts {
+        match part {
+            Part::Text(text) => rendered.push_str(text),
+            Part::Param(name) => match lookup(name)? {
+                Value::String(value) => {
+                    let literal = escape(value, syntax)
+                        .ok_or_else(|| TemplateError::InvalidParam(name.to_string()))?;
+                    rendered.push_str(&literal);
+                }
+                value @ (Value::Number(_) | Value::Bool(_)) => {
+                    rendered.push_str(&value.to_string())
+                }
+                _ => return Err(TemplateError::InvalidParam(name.to_string())),
+            },
+        }
+    }
+    Ok(Value::String(rendered))
+}
+
+// Turns a string parameter into literal text, since neither queries nor filters can escape quotes
+fn escape(value: &str, syntax: Syntax) -> Option<String> {
+    match syntax {
+        Syntax::Query => Some(
+            value
+                .split_whitespace()
+                .map(|word| format!("\"{}\"", word.replace('"', "")))
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+        Syntax::Filter => Some(format!("\"{}\"", value.replace('"', ""))),
+        Syntax::Other => value
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+            .then(|| value.to_string()),
+    }
+}
+
+// Splits a string into text and placeholders, whose names are letters, digits and underscores
+fn parse(text: &str) -> Result<Vec<Part<'_>>, TemplateError> {
+    let invalid = || TemplateError::InvalidPlaceholder(text.to_string());
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            parts.push(Part::Text(&rest[..start]));
+        }
+        let end = rest[start..].find("}}").ok_or_else(invalid)? + start;
+        let name = rest[start + 2..end].trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(invalid());
+        }
+        parts.push(Part::Param(name));
+        rest = &rest[end + 2..];
+    }
+    if rest.contains("}}") {
+        return Err(invalid());
+    }
+    if !rest.is_empty() {
+        parts.push(Part::Text(rest));
+    }
+    Ok(parts)
+}
@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+use regex::Regex;
+
+use crate::search::query::QueryError;
+
+/// Finds all terms of a sorted dictionary that start with the given prefix.
+///
+/// **Arguments**
+///
+/// * `dictionary` - The sorted term dictionary.
+/// * `prefix` - The prefix to look up.
+/// * `max_expansions` - The maximum number of terms to return.
+///
+/// **Returns**
+///
+/// The matching terms in dictionary order, or an error if there are more than `max_expansions`.
+pub fn prefix_terms<V>(
+    dictionary: &BTreeMap<String, V>,
+    prefix: &str,
+    max_expansions: usize,
+) -> Result<Vec<String>, QueryError> {
+    let terms = dictionary
+        .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+        .map(|(term, _)| term)
+        .take_while(|term| term.starts_with(prefix));
+    collect_limited(terms, &format!("{}*", prefix), max_expansions)
+}
+
+/// Finds all terms of a sorted dictionary that match a wildcard pattern.
+///
+/// `?` matches a single character and `*` matches any number of characters.
+/// Only the terms starting with the literal part before the first wildcard are visited.
+///
+/// **Arguments**
+///
+/// * `dictionary` - The sorted term dictionary.
+/// * `pattern` - The wildcard pattern.
+/// * `max_expansions` - The maximum number of terms to return.
+///
+/// **Returns**
+///
+/// The matching terms in dictionary order, or an error if there are more than `max_expansions`.
+pub fn wildcard_terms<V>(
+    dictionary: &BTreeMap<String, V>,
+    pattern: &str,
+    max_expansions: usize,
+) -> Result<Vec<String>, QueryError> {
+    let literal_prefix = &pattern[..pattern.find(['*', '?']).unwrap_or(pattern.len())];
+    let pattern_chars: Vec<char> = pattern.chars().collect();
+    let terms = dictionary
+        .range::<str, _>((Bound::Included(literal_prefix), Bound::Unbounded))
+        .map(|(term, _)| term)
+        .take_while(|term| term.starts_with(literal_prefix))
+        .filter(|term| wildcard_match(&pattern_chars, &term.chars().collect::<Vec<char>>()));
+    collect_limited(terms, pattern, max_expansions)
+}
+
+/// Finds all terms of a sorted dictionary that fully match a regular expression.
+///
+/// **Arguments**
+///
+/// * `dictionary` - The sorted term dictionary.
+/// * `pattern` - The regular expression.
+/// * `max_expansions` - The maximum number of terms to return.
+///
+/// **Returns**
+///
+/// The matching terms in dictionary order, or an error if the expression is invalid
+/// or there are more than `max_expansions` matches.
+pub fn regex_terms<V>(
+    dictionary: &BTreeMap<String, V>,
+    pattern: &str,
+    max_expansions: usize,
+) -> Result<Vec<String>, QueryError> {
+    let regex = Regex::new(&format!("^(?:{})$", pattern))
+        .map_err(|_| QueryError::InvalidRegex(pattern.to_string()))?;
+    let terms = dictionary.keys().filter(|term| regex.is_match(term));
+    collect_limited(terms, &format!("/{}/", pattern), max_expansions)
+}
+
+fn collect_limited<'a>(
+    terms: impl Iterator<Item = &'a String>,
+    pattern: &str,
+    max_expansions: usize,
+) -> Result<Vec<String>, QueryError> {
+    let mut expansions = Vec::new();
+    for term in terms {
+        if expansions.len() == max_expansions {
+            return Err(QueryError::TooManyExpansions {
+                pattern: pattern.to_string(),
+                max_expansions,
+            });
+        }
+        expansions.push(term.clone());
+    }
+    Ok(expansions)
+}
+
+// Matches `?` and `*` wildcards by backtracking to the last `*`
+fn wildcard_match(pattern: &[char], text: &[char]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut last_star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            last_star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = last_star {
+            p = star_p + 1;
+            t = star_t + 1;
+            last_star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
@@ -1,7 +1,58 @@
 use std::sync::Mutex;
 
 use crate::search::engine::SearchEngine;
+use crate::search::pit::PointInTimeStore;
+use crate::search::query_log::QueryLog;
+
+/// Called with the URL of every added document and the IDs of the percolator queries it matches.
+pub type PercolatorHook = Box<dyn Fn(&str, &[String]) + Send + Sync>;
 
 pub struct AppStateWithSearchEngine {
     pub search_engine: Mutex<SearchEngine>, // <- Mutex is necessary to mutate safely across threads
-}
\ No newline at end of file
+    pub points_in_time: PointInTimeStore,
+    pub query_log: QueryLog,
+    pub percolator_hook: Option<PercolatorHook>,
+}
+
+impl AppStateWithSearchEngine {
+    pub fn new(search_engine: SearchEngine) -> AppStateWithSearchEngine {
+        AppStateWithSearchEngine {
+            search_engine: Mutex::new(search_engine),
+            points_in_time: PointInTimeStore::default(),
+            query_log: QueryLog::default(),
+            percolator_hook: None,
+        }
+    }
+
+    /// Sets the query log that successful searches are recorded in, instead of one kept in memory.
+    ///
+    /// **Arguments**
+    ///
+    /// * `query_log` - The query log, e.g. opened from disk.
+    ///
+    /// **Returns**
+    ///
+    /// The app state with the query log.
+    pub fn with_query_log(mut self, query_log: QueryLog) -> AppStateWithSearchEngine {
+        self.query_log = query_log;
+        self
+    }
+
+    /// Sets the hook notified when an added document matches percolator queries.
+    ///
+    /// **Arguments**
+    ///
+    /// * `hook` - Called with the URL of the document and the IDs of the matching queries on
+    ///   the blocking thread pool, without delaying the response.
+    ///
+    /// **Returns**
+    ///
+    /// The app state with the hook.
+    pub fn with_percolator_hook(
+        mut self,
+        hook: impl Fn(&str, &[String]) + Send + Sync + 'static,
+    ) -> AppStateWithSearchEngine {
+        self.percolator_hook = Some(Box::new(hook));
+        self
+    }
+}
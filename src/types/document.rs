@@ -0,0 +1,49 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::field::FieldValue;
+
+/// A document to be added to the index.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Document {
+    pub url: String,
+    pub content: String,
+    pub title: Option<String>,
+    /// Keyword, numeric and date fields stored as doc values for filtering.
+    pub fields: BTreeMap<String, FieldValue>,
+    /// Dense vectors by vector field, used for nearest-neighbor search.
+    pub vectors: BTreeMap<String, Vec<f32>>,
+    /// Static boost applied by the `document_boost` score function.
+    pub boost: Option<f64>,
+}
+
+impl Document {
+    pub fn new(url: &str, content: &str) -> Document {
+        Document {
+            url: url.to_string(),
+            content: content.to_string(),
+            ..Document::default()
+        }
+    }
+
+    pub fn with_title(mut self, title: &str) -> Document {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    pub fn with_field(mut self, name: &str, value: FieldValue) -> Document {
+        self.fields.insert(name.to_string(), value);
+        self
+    }
+
+    pub fn with_boost(mut self, boost: f64) -> Document {
+        self.boost = Some(boost);
+        self
+    }
+
+    pub fn with_vector(mut self, name: &str, vector: Vec<f32>) -> Document {
+        self.vectors.insert(name.to_string(), vector);
+        self
+    }
+}
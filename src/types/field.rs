@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+
+/// A typed value of a document field, stored in a doc-value column.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum FieldValue {
+    Keyword(String),
+    Number(f64),
+    /// Milliseconds since the Unix epoch.
+    Date(i64),
+}
+
+/// A field value as sent by clients.
+///
+/// Numbers become numeric fields, strings become date fields if they parse as an
+/// RFC 3339 date and keyword fields otherwise.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FieldInput {
+    Number(f64),
+    Text(String),
+}
+
+impl From<FieldInput> for FieldValue {
+    fn from(input: FieldInput) -> Self {
+        match input {
+            FieldInput::Number(number) => FieldValue::Number(number),
+            FieldInput::Text(text) => match parse_date(&text) {
+                Some(date) => FieldValue::Date(date),
+                None => FieldValue::Keyword(text),
+            },
+        }
+    }
+}
+
+impl FieldValue {
+    /// Converts the value to JSON, dates are formatted as ISO 8601.
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            FieldValue::Keyword(keyword) => serde_json::Value::from(keyword.as_str()),
+            FieldValue::Number(number) => serde_json::Value::from(*number),
+            FieldValue::Date(date) => serde_json::Value::from(format_date(*date)),
+        }
+    }
+}
+
+/// Parses an RFC 3339 date like `2024-03-01`, `2024-03-01T12:30:00Z` or
+/// `2024-03-01T12:30:00.250+02:00`. Times without an offset are taken as UTC and
+/// fractions of a second are truncated to milliseconds.
+///
+/// **Arguments**
+///
+/// * `text` - The date to parse.
+///
+/// **Returns**
+///
+/// Milliseconds since the Unix epoch, or `None` if the text is not a date.
+pub fn parse_date(text: &str) -> Option<i64> {
+    let (date, time) = match text.split_once(['T', 't', ' ']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (text, None),
+    };
+
+    let mut parts = date.splitn(3, '-');
+    let year: i64 = parse_digits(parts.next()?, 4)?;
+    let month: i64 = parse_digits(parts.next()?, 2)?;
+    let day: i64 = parse_digits(parts.next()?, 2)?;
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return None;
+    }
+
+    let mut millis = days_from_civil(year, month, day) * 86_400_000;
+    if let Some(time) = time {
+        let (time, offset_minutes) = split_offset(time)?;
+        let (time, fraction) = match time.split_once('.') {
+            Some((time, fraction)) => (time, Some(fraction)),
+            None => (time, None),
+        };
+        let mut parts = time.splitn(3, ':');
+        let hours: i64 = parse_digits(parts.next()?, 2)?;
+        let minutes: i64 = parse_digits(parts.next()?, 2)?;
+        let secs: i64 = match (parts.next(), fraction) {
+            (Some(secs), _) => parse_digits(secs, 2)?,
+            (None, None) => 0,
+            // A fraction needs seconds
+            (None, Some(_)) => return None,
+        };
+        if hours > 23 || minutes > 59 || secs > 59 {
+            return None;
+        }
+        let fraction_millis: i64 = match fraction {
+            Some(fraction) => {
+                if fraction.is_empty() || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                // Digits beyond milliseconds are dropped
+                format!("{:0<3}", &fraction[..fraction.len().min(3)])
+                    .parse()
+                    .ok()?
+            }
+            None => 0,
+        };
+        millis +=
+            (hours * 3600 + minutes * 60 + secs - offset_minutes * 60) * 1000 + fraction_millis;
+    }
+    Some(millis)
+}
+
+// Splits the UTC offset off a time, returning the offset in minutes
+fn split_offset(time: &str) -> Option<(&str, i64)> {
+    if let Some(time) = time.strip_suffix(['Z', 'z']) {
+        return Some((time, 0));
+    }
+    let Some(position) = time.rfind(['+', '-']) else {
+        return Some((time, 0));
+    };
+    let (time, offset) = time.split_at(position);
+    let (hours, minutes) = offset[1..].split_once(':')?;
+    let hours = parse_digits(hours, 2)?;
+    let minutes = parse_digits(minutes, 2)?;
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    let sign = if offset.starts_with('-') { -1 } else { 1 };
+    Some((time, sign * (hours * 60 + minutes)))
+}
+
+/// Formats milliseconds since the Unix epoch as an RFC 3339 date in UTC.
+///
+/// **Arguments**
+///
+/// * `millis` - Milliseconds since the Unix epoch.
+///
+/// **Returns**
+///
+/// The date, with a time part only if it is not midnight and milliseconds only if there are any.
+pub fn format_date(millis: i64) -> String {
+    let seconds = millis.div_euclid(1000);
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+    let time = seconds.rem_euclid(86_400);
+    let fraction = millis.rem_euclid(1000);
+    if time == 0 && fraction == 0 {
+        return format!("{:04}-{:02}-{:02}", year, month, day);
+    }
+    let fraction = if fraction == 0 {
+        String::new()
+    } else {
+        format!(".{:03}", fraction)
+    };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60,
+        fraction
+    )
+}
+
+/// Parses a duration like `500ms`, `30s`, `15m`, `12h`, `7d` or `4w`, used for decay
+/// scales, keep-alives and timeouts.
+///
+/// **Arguments**
+///
+/// * `text` - The duration.
+///
+/// **Returns**
+///
+/// The duration in milliseconds, or `None` if it is malformed.
+pub fn parse_duration(text: &str) -> Option<i64> {
+    let text = text.trim();
+    let split = text.find(|c: char| !c.is_ascii_digit())?;
+    let amount: i64 = text[..split].parse().ok()?;
+    let unit: i64 = match &text[split..] {
+        "ms" => 1,
+        "s" => 1000,
+        "m" => 60 * 1000,
+        "h" => 60 * 60 * 1000,
+        "d" => 24 * 60 * 60 * 1000,
+        "w" => 7 * 24 * 60 * 60 * 1000,
+        _ => return None,
+    };
+    amount.checked_mul(unit)
+}
+
+fn parse_digits(text: &str, length: usize) -> Option<i64> {
+    if text.len() != length || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01, see http://howardhinnant.github.io/date_algorithms.html
+pub(crate) fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+pub(crate) fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
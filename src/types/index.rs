@@ -1,9 +1,73 @@
 use std::collections::{BTreeMap, HashMap};
+use std::{fmt, io};
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize)]
+use crate::search::hnsw::Hnsw;
+use crate::search::ltr::RankingModel;
+use crate::search::percolator::PercolatorQuery;
+use crate::search::rules::QueryRule;
+use crate::types::field::FieldValue;
+
+/// Bytes starting every versioned index file.
+pub const INDEX_MAGIC: [u8; 4] = *b"RSIX";
+
+/// The version of the layout of `SavedIndex`, written after `INDEX_MAGIC`.
+///
+/// It must be increased whenever the layout changes, keeping the previous layout around to
+/// migrate files written with it.
+pub const INDEX_FORMAT_VERSION: u32 = 1;
+
+#[derive(Default, Serialize, Deserialize)]
 pub struct SavedIndex {
     pub index_btree_map: BTreeMap<String, HashMap<String, i32>>,
     pub documents_btree_map: BTreeMap<String, String>,
+    pub titles_btree_map: BTreeMap<String, String>,
+    pub synonym_rules: Vec<String>,
+    pub doc_values: BTreeMap<String, BTreeMap<String, FieldValue>>,
+    pub vector_fields: BTreeMap<String, Hnsw>,
+    pub signatures: BTreeMap<String, u64>,
+    pub boosts: BTreeMap<String, f64>,
+    pub query_rules: BTreeMap<String, QueryRule>,
+    pub ranking_models: BTreeMap<String, RankingModel>,
+    pub percolator_queries: BTreeMap<String, PercolatorQuery>,
+    // Search templates as JSON text, because bincode cannot read arbitrary JSON values
+    pub search_templates: BTreeMap<String, String>,
+}
+
+/// The layout of index files before they were versioned, holding only postings and documents.
+#[derive(Serialize, Deserialize)]
+pub struct SavedIndexV0 {
+    pub index_btree_map: BTreeMap<String, HashMap<String, i32>>,
+    pub documents_btree_map: BTreeMap<String, String>,
+}
+
+/// Errors that can occur when the index is read from disk.
+#[derive(Debug)]
+pub enum IndexError {
+    Io(io::Error),
+    UnsupportedVersion(u32),
+    Corrupt(String),
+}
+
+impl fmt::Display for IndexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexError::Io(e) => write!(f, "Could not read the index: {}", e),
+            IndexError::UnsupportedVersion(version) => write!(
+                f,
+                "The index was written with format version {}, but this version reads {}",
+                version, INDEX_FORMAT_VERSION
+            ),
+            IndexError::Corrupt(e) => write!(f, "The index is corrupt: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for IndexError {}
+
+impl From<io::Error> for IndexError {
+    fn from(e: io::Error) -> Self {
+        IndexError::Io(e)
+    }
 }
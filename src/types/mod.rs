@@ -1,3 +1,6 @@
 pub mod app_state;
 pub mod config;
+pub mod document;
+pub mod field;
 pub mod index;
+pub mod response;
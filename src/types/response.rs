@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::search::explain::Explanation;
+use crate::search::facets::Facet;
+use crate::search::hnsw::Metric;
+use crate::search::ltr::RankingModel;
+use crate::search::percolator::PercolatorQuery;
+use crate::search::rules::QueryRule;
+use crate::search::suggest::Suggestion;
+use crate::search::template::SearchTemplate;
+
+/// Envelope returned by the `/search` endpoint.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchResponse {
+    pub took_ms: u64,
+    pub total: usize,
+    pub max_score: Option<f64>,
+    pub hits: Vec<SearchHit>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub facets: BTreeMap<String, FacetResult>,
+    /// Pass as `search_after` to fetch the hits following this page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pit_id: Option<String>,
+    /// The groups of hits, when grouping was requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub groups: Option<Vec<HitGroup>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_groups: Option<usize>,
+    /// A landing page a query rule sends this query to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirect: Option<String>,
+}
+
+/// Hits sharing the same group key, with the number of hits in the group.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HitGroup {
+    pub key: Option<String>,
+    pub count: usize,
+    pub hits: Vec<SearchHit>,
+}
+
+/// A single ranked document inside a `SearchResponse`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub url: String,
+    pub score: f64,
+    pub fields: BTreeMap<String, serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matched_terms: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub highlight: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<Vec<serde_json::Value>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<Explanation>,
+    /// The ranking features of the hit by name, when feature logging was requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub features: Option<BTreeMap<String, f64>>,
+}
+
+/// The buckets of a facet inside a `SearchResponse`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FacetResult {
+    pub buckets: Vec<FacetBucket>,
+    pub other: usize,
+    pub missing: usize,
+}
+
+impl From<Facet> for FacetResult {
+    fn from(facet: Facet) -> Self {
+        FacetResult {
+            buckets: facet
+                .buckets
+                .into_iter()
+                .map(|(key, count)| FacetBucket {
+                    key: key.to_json(),
+                    count,
+                })
+                .collect(),
+            other: facet.other,
+            missing: facet.missing,
+        }
+    }
+}
+
+/// A facet value, or the lower bound of a histogram bucket, with its document count.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FacetBucket {
+    pub key: serde_json::Value,
+    pub count: usize,
+}
+
+/// Envelope returned by the `/search/suggest` endpoint.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SuggestResponse {
+    pub took_ms: u64,
+    pub suggestions: Vec<Suggestion>,
+}
+
+/// Body returned by the `/search/spell` endpoint.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpellResponse {
+    pub query: String,
+    pub suggestion: Option<String>,
+}
+
+/// Body returned by the synonym endpoints.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SynonymsResponse {
+    pub rules: Vec<String>,
+}
+
+/// Body returned after a vector field was defined.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VectorFieldResponse {
+    pub field: String,
+    pub dimension: usize,
+    pub metric: Metric,
+}
+
+/// Body returned after a point in time was opened.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PointInTimeResponse {
+    pub id: String,
+    pub keep_alive: String,
+}
+
+/// Body returned after a point in time was closed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClosePointInTimeResponse {
+    pub succeeded: bool,
+}
+
+/// Body returned after a document was added to the index.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IndexDocumentResponse {
+    pub url: String,
+    pub result: String,
+    /// Indexed documents that are near-duplicates of this one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub duplicates: Vec<String>,
+    /// The IDs of the percolator queries matching the document.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub percolator_matches: Vec<String>,
+}
+
+/// Body returned by the `number_of_documents` endpoint.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NumberOfDocumentsResponse {
+    pub number_of_documents: usize,
+}
+
+/// Response of the explain endpoint.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExplainResponse {
+    pub url: String,
+    pub matched: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<Explanation>,
+}
+
+/// Body returned by the endpoints listing query rules.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QueryRulesResponse {
+    pub rules: BTreeMap<String, QueryRule>,
+}
+
+/// Body returned for a single query rule.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QueryRuleResponse {
+    pub id: String,
+    pub rule: QueryRule,
+}
+
+/// Body returned after a query rule was stored or deleted.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QueryRuleResultResponse {
+    pub id: String,
+    pub result: String,
+}
+
+/// Body returned by the endpoint listing ranking models.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RankingModelsResponse {
+    pub models: BTreeMap<String, RankingModel>,
+}
+
+/// Body returned for a single ranking model.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RankingModelResponse {
+    pub name: String,
+    pub model: RankingModel,
+}
+
+/// Body returned after a ranking model was stored or deleted.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RankingModelResultResponse {
+    pub name: String,
+    pub result: String,
+}
+
+/// Body returned by the endpoint listing percolator queries.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PercolatorQueriesResponse {
+    pub queries: BTreeMap<String, PercolatorQuery>,
+}
+
+/// Body returned for a single percolator query.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PercolatorQueryResponse {
+    pub id: String,
+    pub query: PercolatorQuery,
+}
+
+/// Body returned after a percolator query was stored or deleted.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PercolatorQueryResultResponse {
+    pub id: String,
+    pub result: String,
+}
+
+/// Body returned by the endpoint listing search templates.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchTemplatesResponse {
+    pub templates: BTreeMap<String, SearchTemplate>,
+}
+
+/// Body returned for a single search template.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchTemplateResponse {
+    pub id: String,
+    pub template: SearchTemplate,
+}
+
+/// Body returned after a search template was stored or deleted.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchTemplateResultResponse {
+    pub id: String,
+    pub result: String,
+}
+
+/// Error body shared by all endpoints.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+impl ErrorResponse {
+    pub fn new(error: &str) -> ErrorResponse {
+        ErrorResponse {
+            error: error.to_string(),
+        }
+    }
+}
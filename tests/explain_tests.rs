@@ -0,0 +1,88 @@
+#[cfg(test)]
+mod tests {
+    use rustysearch::search::{
+        engine::{remove_index_from_disk, SearchEngine},
+        explain::Explanation,
+        query::Query,
+        synonyms::SynonymSet,
+    };
+
+    fn find<'a>(explanation: &'a Explanation, prefix: &str) -> Option<&'a Explanation> {
+        if explanation.description.starts_with(prefix) {
+            return Some(explanation);
+        }
+        explanation.details.iter().find_map(|d| find(d, prefix))
+    }
+
+    #[test]
+    fn test_explanation_matches_score() {
+        let mut search_engine = SearchEngine::new(1.2, 0.75);
+        search_engine.bulk_index(vec![
+            (
+                "https://explain.example.com/a",
+                "explaining explainer scores explaining",
+            ),
+            ("https://explain.example.com/b", "explaining ranked scores"),
+            ("https://explain.example.com/c", "explainers everywhere"),
+        ]);
+        search_engine.set_synonyms(SynonymSet::parse("ranked, explainrank").unwrap());
+
+        for query in [
+            "explaining",
+            "explaining scores",
+            "explainer~1",
+            "explain*",
+            "\"explaining ranked\"",
+            "explainrank scores",
+        ] {
+            let query = Query::parse(query).unwrap();
+            let results = search_engine.search_query(&query).unwrap();
+            assert!(!results.is_empty());
+            for url in search_engine.posts() {
+                let explanation = search_engine.explain(&query, &url).unwrap();
+                match results.get(&url) {
+                    Some(score) => assert_eq!(explanation.unwrap().value, *score),
+                    None => assert!(explanation.is_none()),
+                }
+            }
+        }
+
+        remove_index_from_disk();
+    }
+
+    #[test]
+    fn test_explanation_parts() {
+        let mut search_engine = SearchEngine::new(1.2, 0.75);
+        search_engine.bulk_index(vec![
+            ("https://explainparts.example.com/a", "partsterm partsterm"),
+            ("https://explainparts.example.com/b", "partstern"),
+        ]);
+
+        let query = Query::parse("partsterm~1").unwrap();
+        let explanation = search_engine
+            .explain(&query, "https://explainparts.example.com/b")
+            .unwrap()
+            .unwrap();
+        let weight = find(&explanation, "weight of 'partstern'").unwrap();
+        assert_eq!(find(weight, "boost").unwrap().value, 0.9);
+        assert_eq!(find(weight, "n,").unwrap().value, 1.0);
+        assert_eq!(find(weight, "N,").unwrap().value, 2.0);
+        assert_eq!(find(weight, "freq").unwrap().value, 1.0);
+        assert_eq!(find(weight, "k1").unwrap().value, 1.2);
+        assert_eq!(find(weight, "b,").unwrap().value, 0.75);
+        assert_eq!(find(weight, "dl").unwrap().value, 9.0);
+        assert_eq!(find(weight, "avdl").unwrap().value, 14.0);
+        let idf = find(weight, "idf").unwrap().value;
+        let tf = find(weight, "tf").unwrap().value;
+        assert!((weight.value - 0.9 * idf * tf).abs() < 1e-12);
+
+        // Documents that do not match are not explained
+        let query = Query::parse("partsother").unwrap();
+        assert!(search_engine
+            .explain(&query, "https://explainparts.example.com/a")
+            .unwrap()
+            .is_none());
+
+        remove_index_from_disk();
+    }
+}
@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod tests {
+    use rustysearch::{
+        search::{
+            engine::{remove_index_from_disk, SearchEngine},
+            facets::FacetRequest,
+            query::QueryError,
+        },
+        types::{
+            document::Document,
+            field::{parse_date, FieldValue},
+        },
+    };
+
+    #[test]
+    fn test_parse_facets() {
+        let requests = FacetRequest::parse_all("tag, year:10 ,published:month").unwrap();
+
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].field, "tag");
+        assert_eq!(requests[0].option, None);
+        assert_eq!(requests[1].option, Some("10".to_string()));
+        assert_eq!(requests[2].field, "published");
+        assert_eq!(
+            FacetRequest::parse_all("tag:"),
+            Err(QueryError::InvalidFacet("tag:".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_compute_facets() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        let date = |d: &str| FieldValue::Date(parse_date(d).unwrap());
+        let documents = [
+            ("a", "rust", 2019.0, "2019-06-01"),
+            ("b", "rust", 2021.0, "2021-06-15"),
+            ("c", "go", 2021.0, "2021-06-30"),
+            ("d", "zig", 2025.0, "2022-01-01"),
+        ];
+        for (name, tag, year, published) in documents {
+            search_engine.index_document(
+                &Document::new(&format!("https://facet.example.com/{}", name), "facetable")
+                    .with_field("tag", FieldValue::Keyword(tag.to_string()))
+                    .with_field("year", FieldValue::Number(year))
+                    .with_field("published", date(published)),
+            );
+        }
+        search_engine.index("https://facet.example.com/e", "facetable");
+
+        let results = search_engine.search("facetable").unwrap();
+        let requests = FacetRequest::parse_all("tag:2, year:5, published:month").unwrap();
+        let facets = search_engine.facets(results.keys(), &requests).unwrap();
+
+        let tag = &facets["tag"];
+        assert_eq!(
+            tag.buckets,
+            vec![
+                (FieldValue::Keyword("rust".to_string()), 2),
+                (FieldValue::Keyword("go".to_string()), 1)
+            ]
+        );
+        assert_eq!(tag.other, 1);
+        assert_eq!(tag.missing, 1);
+
+        assert_eq!(
+            facets["year"].buckets,
+            vec![
+                (FieldValue::Number(2015.0), 1),
+                (FieldValue::Number(2020.0), 2),
+                (FieldValue::Number(2025.0), 1)
+            ]
+        );
+        assert_eq!(
+            facets["published"].buckets,
+            vec![
+                (date("2019-06-01"), 1),
+                (date("2021-06-01"), 2),
+                (date("2022-01-01"), 1)
+            ]
+        );
+
+        // A bucket width does not fit a date field
+        let requests = FacetRequest::parse_all("published:10").unwrap();
+        assert_eq!(
+            search_engine.facets(results.keys(), &requests),
+            Err(QueryError::InvalidFacet("published:10".to_string()))
+        );
+
+        remove_index_from_disk();
+    }
+}
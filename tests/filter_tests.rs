@@ -0,0 +1,168 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    use rustysearch::{
+        search::{
+            engine::{remove_index_from_disk, SearchEngine},
+            filter::{Filter, FilterCache, FilterOp, MAX_CACHED_FILTERS},
+            query::QueryError,
+        },
+        types::{
+            document::Document,
+            field::{format_date, parse_date, FieldInput, FieldValue},
+        },
+    };
+
+    #[test]
+    fn test_parse_filters() {
+        let filters = Filter::parse_all(
+            "tag = rust; year >= 2020; published between 2020-01-01 and 2020-12-31",
+        )
+        .unwrap();
+
+        assert_eq!(filters.len(), 3);
+        assert_eq!(filters[1].op, FilterOp::Gte);
+        assert_eq!(filters[1].values, vec!["2020".to_string()]);
+        assert_eq!(filters[2].op, FilterOp::Between);
+        assert_eq!(
+            filters[2].to_string(),
+            "published between 2020-01-01 and 2020-12-31"
+        );
+        assert_eq!(
+            Filter::parse("year"),
+            Err(QueryError::InvalidFilter("year".to_string()))
+        );
+
+        // Quoted values may contain separators and operators
+        let filters =
+            Filter::parse_all(r#"title = "a; tag = b"; name between "x and y" and "z""#).unwrap();
+        assert_eq!(filters.len(), 2);
+        assert_eq!(filters[0].field, "title");
+        assert_eq!(filters[0].values, vec!["a; tag = b".to_string()]);
+        assert_eq!(
+            filters[1].values,
+            vec!["x and y".to_string(), "z".to_string()]
+        );
+        assert_eq!(filters[0].to_string(), r#"title = "a; tag = b""#);
+        assert_eq!(Filter::parse(&filters[1].to_string()).unwrap(), filters[1]);
+    }
+
+    #[test]
+    fn test_field_values() {
+        assert_eq!(parse_date("1970-01-02"), Some(86_400_000));
+        assert_eq!(parse_date("2020-02-30"), None);
+        assert_eq!(
+            format_date(parse_date("2024-02-29T12:30:05Z").unwrap()),
+            "2024-02-29T12:30:05Z"
+        );
+        assert_eq!(
+            FieldValue::from(FieldInput::Text("2021-03-04".to_string())),
+            FieldValue::Date(parse_date("2021-03-04").unwrap())
+        );
+        assert_eq!(
+            FieldValue::from(FieldInput::Text("rust".to_string())),
+            FieldValue::Keyword("rust".to_string())
+        );
+
+        // RFC 3339 offsets and fractions of a second
+        assert_eq!(
+            parse_date("2024-01-01T10:00:00+02:00"),
+            parse_date("2024-01-01T08:00:00Z")
+        );
+        assert_eq!(
+            parse_date("2023-12-31T23:30:00-01:00"),
+            parse_date("2024-01-01T00:30:00Z")
+        );
+        assert_eq!(
+            format_date(parse_date("2024-01-01T10:00:00.25+02:00").unwrap()),
+            "2024-01-01T08:00:00.250Z"
+        );
+        assert_eq!(
+            parse_date("2024-01-01T10:00:00.123456Z"),
+            Some(parse_date("2024-01-01T10:00:00Z").unwrap() + 123)
+        );
+        assert_eq!(parse_date("2024-01-01T10:00:00+2"), None);
+        assert_eq!(parse_date("2024-01-01T10:00.5Z"), None);
+    }
+
+    #[test]
+    fn test_filter_cache_is_bounded() {
+        let mut cache = FilterCache::default();
+        let passing = Arc::new(HashSet::from(["https://example.com".to_string()]));
+        for i in 0..MAX_CACHED_FILTERS {
+            cache.insert(format!("year = {}", i), passing.clone());
+        }
+        // The first filter was used recently, so the second one is evicted
+        assert!(cache.get("year = 0").is_some());
+        cache.insert("tag = rust".to_string(), passing.clone());
+        assert_eq!(cache.len(), MAX_CACHED_FILTERS);
+        assert!(cache.contains("year = 0"));
+        assert!(!cache.contains("year = 1"));
+        assert!(cache.contains("tag = rust"));
+    }
+
+    #[test]
+    fn test_filter_documents() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        let date = |d: &str| FieldValue::Date(parse_date(d).unwrap());
+        search_engine.index_document(
+            &Document::new("https://filter.example.com/a", "filterable rust")
+                .with_field("tag", FieldValue::Keyword("rust".to_string()))
+                .with_field("year", FieldValue::Number(2019.0))
+                .with_field("published", date("2019-06-01")),
+        );
+        search_engine.index_document(
+            &Document::new("https://filter.example.com/b", "filterable rust")
+                .with_field("tag", FieldValue::Keyword("rust".to_string()))
+                .with_field("year", FieldValue::Number(2021.0))
+                .with_field("published", date("2021-06-01")),
+        );
+        search_engine.index_document(
+            &Document::new("https://filter.example.com/c", "filterable go")
+                .with_field("year", FieldValue::Number(2022.0)),
+        );
+
+        let filter = |text: &str| {
+            let mut urls: Vec<String> = search_engine
+                .filter(&Filter::parse_all(text).unwrap())
+                .into_iter()
+                .filter(|url| url.starts_with("https://filter.example.com/"))
+                .collect();
+            urls.sort();
+            urls
+        };
+
+        assert_eq!(
+            filter("tag = rust; year >= 2020"),
+            vec!["https://filter.example.com/b"]
+        );
+        assert_eq!(
+            filter("published between 2019-01-01 and 2019-12-31"),
+            vec!["https://filter.example.com/a"]
+        );
+        assert_eq!(filter("tag != rust"), vec!["https://filter.example.com/c"]);
+        // Served from the cache the second time
+        assert_eq!(filter("year < 2022").len(), 2);
+        assert_eq!(filter("year < 2022").len(), 2);
+
+        // Values that cannot be compared with the type of the field are rejected
+        let validate =
+            |text: &str| search_engine.validate_filters(&Filter::parse_all(text).unwrap());
+        assert_eq!(
+            validate("published >= 2020-13-45"),
+            Err(QueryError::InvalidFilterValue {
+                field: "published".to_string(),
+                value: "2020-13-45".to_string(),
+                expected: "a date".to_string(),
+            })
+        );
+        assert!(validate("year >= 20x0").is_err());
+        assert!(validate("year != 20x0").is_err());
+        assert!(validate("year between 2019 and later").is_err());
+        assert!(validate("tag >= rust; year between 2019 and 2021; unknown > x").is_ok());
+
+        remove_index_from_disk();
+    }
+}
@@ -0,0 +1,135 @@
+#[cfg(test)]
+mod tests {
+    use rustysearch::{
+        search::{
+            engine::{remove_index_from_disk, SearchEngine},
+            explain::Explanation,
+            function_score::{BoostMode, DecayFunction, FunctionScore, Modifier, ScoreFunction},
+        },
+        types::{
+            document::Document,
+            field::{parse_date, parse_duration, FieldValue},
+        },
+    };
+
+    const DAY: i64 = 24 * 60 * 60 * 1000;
+
+    fn decay(function: DecayFunction) -> FunctionScore {
+        FunctionScore {
+            functions: vec![ScoreFunction::Decay {
+                field: "published".to_string(),
+                function,
+                origin: Some("2024-01-31".to_string()),
+                scale: "10d".to_string(),
+                offset: Some("1d".to_string()),
+                decay: 0.5,
+            }],
+            boost_mode: BoostMode::Multiply,
+        }
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("7d"), Some(7 * DAY));
+        assert_eq!(parse_duration("2w"), Some(14 * DAY));
+        assert_eq!(parse_duration("250ms"), Some(250));
+        assert_eq!(parse_duration("7 days"), None);
+        assert_eq!(parse_duration("d"), None);
+    }
+
+    #[test]
+    fn test_decay_functions() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        let date = |d: &str| FieldValue::Date(parse_date(d).unwrap());
+        search_engine.index_document(
+            &Document::new("https://decay.example.com/scale", "decaying")
+                .with_field("published", date("2024-01-20")),
+        );
+        search_engine.index_document(
+            &Document::new("https://decay.example.com/offset", "decaying")
+                .with_field("published", date("2024-01-30")),
+        );
+        search_engine.index_document(&Document::new("https://decay.example.com/none", "decaying"));
+
+        // At `scale` beyond the offset every function has decayed to `decay`
+        for function in [
+            DecayFunction::Gauss,
+            DecayFunction::Exp,
+            DecayFunction::Linear,
+        ] {
+            let function_score = decay(function);
+            function_score.validate().unwrap();
+            let score = |url: &str| function_score.score(&search_engine, url, 2.0, 0);
+            assert!((score("https://decay.example.com/scale") - 1.0).abs() < 1e-9);
+            assert_eq!(score("https://decay.example.com/offset"), 2.0);
+            assert_eq!(score("https://decay.example.com/none"), 2.0);
+        }
+
+        remove_index_from_disk();
+    }
+
+    #[test]
+    fn test_field_value_factor_and_document_boost() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.index_document(
+            &Document::new("https://popular.example.com/a", "popular")
+                .with_field("likes", FieldValue::Number(99.0))
+                .with_boost(3.0),
+        );
+        search_engine.index_document(&Document::new("https://popular.example.com/b", "popular"));
+
+        let mut function_score = FunctionScore {
+            functions: vec![
+                ScoreFunction::FieldValueFactor {
+                    field: "likes".to_string(),
+                    factor: 1.0,
+                    modifier: Modifier::Log1p,
+                    missing: None,
+                },
+                ScoreFunction::DocumentBoost,
+            ],
+            boost_mode: BoostMode::Sum,
+        };
+        let a = "https://popular.example.com/a";
+        let b = "https://popular.example.com/b";
+        assert_eq!(function_score.score(&search_engine, a, 1.0, 0), 7.0);
+        assert_eq!(function_score.score(&search_engine, b, 1.0, 0), 2.0);
+
+        function_score.boost_mode = BoostMode::Replace;
+        assert_eq!(function_score.score(&search_engine, a, 1.0, 0), 6.0);
+
+        // The explanation wraps the text score and agrees with the final score
+        function_score.boost_mode = BoostMode::Multiply;
+        let explanation =
+            function_score.explain(&search_engine, a, Explanation::new(0.5, "text"), 0);
+        assert_eq!(
+            explanation.value,
+            function_score.score(&search_engine, a, 0.5, 0)
+        );
+        assert_eq!(explanation.details[1].details.len(), 2);
+
+        remove_index_from_disk();
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut function_score = decay(DecayFunction::Gauss);
+        if let ScoreFunction::Decay { decay, .. } = &mut function_score.functions[0] {
+            *decay = 1.0;
+        }
+        assert!(function_score.validate().is_err());
+
+        let function_score: FunctionScore = serde_json::from_str(
+            r#"{"functions": [{"decay": {"field": "published", "function": "exp", "scale": "soon"}}]}"#,
+        )
+        .unwrap();
+        assert!(function_score.validate().is_err());
+
+        let function_score: FunctionScore = serde_json::from_str(
+            r#"{"functions": ["document_boost", {"field_value_factor": {"field": "likes"}}], "boost_mode": "sum"}"#,
+        )
+        .unwrap();
+        assert!(function_score.validate().is_ok());
+        assert_eq!(function_score.boost_mode, BoostMode::Sum);
+    }
+}
@@ -0,0 +1,69 @@
+#[cfg(test)]
+mod tests {
+    use rustysearch::search::fusion::{fuse, Fusion};
+
+    fn hits(hits: &[(&str, f64)]) -> Vec<(String, f64)> {
+        hits.iter()
+            .map(|(url, score)| (url.to_string(), *score))
+            .collect()
+    }
+
+    #[test]
+    fn test_reciprocal_rank_fusion() {
+        let lexical = hits(&[("a", 12.0), ("b", 3.0)]);
+        let vector = hits(&[("b", 0.9), ("c", 0.8)]);
+
+        let fused = fuse(&lexical, &vector, &Fusion::default());
+        let urls: Vec<&str> = fused.iter().map(|(url, _, _)| url.as_str()).collect();
+        assert_eq!(urls, vec!["b", "a", "c"]);
+        assert!((fused[0].1 - (1.0 / 62.0 + 1.0 / 61.0)).abs() < 1e-12);
+        assert!((fused[1].1 - 1.0 / 61.0).abs() < 1e-12);
+
+        // Every retriever that found the hit reports its contribution
+        let explanation = &fused[0].2;
+        assert_eq!(explanation.details.len(), 2);
+        assert!(explanation.details[0]
+            .description
+            .starts_with("lexical rank 2"));
+        assert!(explanation.details[1]
+            .description
+            .starts_with("vector rank 1"));
+    }
+
+    #[test]
+    fn test_linear_fusion() {
+        let lexical = hits(&[("a", 10.0), ("b", 5.0), ("c", 0.0)]);
+        let vector = hits(&[("c", 0.7), ("a", 0.2)]);
+        let fusion = Fusion::Linear {
+            lexical_weight: 0.3,
+            vector_weight: 0.7,
+        };
+
+        let fused = fuse(&lexical, &vector, &fusion);
+        let scores: Vec<(&str, f64)> = fused
+            .iter()
+            .map(|(url, score, _)| (url.as_str(), *score))
+            .collect();
+        assert_eq!(scores[0].0, "c");
+        assert!((scores[0].1 - 0.7).abs() < 1e-12);
+        assert_eq!(scores[1].0, "a");
+        assert!((scores[1].1 - 0.3).abs() < 1e-12);
+        assert_eq!(scores[2].0, "b");
+        assert!((scores[2].1 - 0.15).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_fusion_from_json() {
+        let fusion: Fusion = serde_json::from_str(r#"{"method": "rrf"}"#).unwrap();
+        assert_eq!(fusion, Fusion::default());
+        let fusion: Fusion =
+            serde_json::from_str(r#"{"method": "linear", "vector_weight": 0.8}"#).unwrap();
+        assert_eq!(
+            fusion,
+            Fusion::Linear {
+                lexical_weight: 0.5,
+                vector_weight: 0.8
+            }
+        );
+    }
+}
@@ -0,0 +1,79 @@
+#[cfg(test)]
+mod tests {
+    use rustysearch::{
+        search::{
+            engine::{remove_index_from_disk, SearchEngine},
+            group::{domain, group, GroupBy},
+        },
+        types::{document::Document, field::FieldValue},
+    };
+
+    #[test]
+    fn test_domain() {
+        assert_eq!(
+            domain("https://www.Example.com:8080/path?q=1"),
+            Some("example.com".to_string())
+        );
+        assert_eq!(
+            domain("http://user@docs.rs#top"),
+            Some("docs.rs".to_string())
+        );
+        assert_eq!(domain("example.org/page"), Some("example.org".to_string()));
+        assert_eq!(domain("https:///path"), None);
+    }
+
+    #[test]
+    fn test_group() {
+        let ranked: Vec<(String, f64)> = [
+            ("https://a.com/1", 5.0),
+            ("https://b.com/1", 4.0),
+            ("https://a.com/2", 3.0),
+            ("https://a.com/3", 2.0),
+            ("https://c.com/1", 1.0),
+        ]
+        .iter()
+        .map(|(url, score)| (url.to_string(), *score))
+        .collect();
+
+        let groups = group(ranked, domain, 2);
+        let keys: Vec<&str> = groups.iter().map(|g| g.key.as_deref().unwrap()).collect();
+        assert_eq!(keys, vec!["a.com", "b.com", "c.com"]);
+        assert_eq!(groups[0].count, 3);
+        assert_eq!(
+            groups[0].hits,
+            vec![
+                ("https://a.com/1".to_string(), 5.0),
+                ("https://a.com/2".to_string(), 3.0)
+            ]
+        );
+        assert_eq!(groups[1].count, 1);
+    }
+
+    #[test]
+    fn test_group_key() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.index_document(
+            &Document::new("https://www.groupkey.example.com/a", "groupkey")
+                .with_field("site", FieldValue::Keyword("blog".to_string()))
+                .with_field("year", FieldValue::Number(2024.0)),
+        );
+
+        let url = "https://www.groupkey.example.com/a";
+        assert_eq!(GroupBy::parse(" "), None);
+        let by_domain = GroupBy::parse("_domain").unwrap();
+        assert_eq!(
+            search_engine.group_key(url, &by_domain),
+            Some("groupkey.example.com".to_string())
+        );
+        let by_site = GroupBy::parse("site").unwrap();
+        assert_eq!(
+            search_engine.group_key(url, &by_site),
+            Some("blog".to_string())
+        );
+        // Only keyword fields are grouped by
+        let by_year = GroupBy::Field("year".to_string());
+        assert_eq!(search_engine.group_key(url, &by_year), None);
+
+        remove_index_from_disk();
+    }
+}
@@ -20,4 +20,4 @@ mod tests {
         let resp = test::call_service(&app, req).await;
         assert!(resp.status().is_client_error());
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use rustysearch::search::{
+        analyzer::{analyze, tokenize},
+        highlight::{highlight, Encoder, HighlightOptions},
+    };
+
+    #[test]
+    fn test_tokenize_matches_analyze() {
+        let text = "Rust ist eine Multiparadigmen-Systemprogrammiersprache, (die) von - Mozilla.";
+        let tokens = tokenize(text);
+        let terms: Vec<String> = tokens.iter().map(|t| t.term.clone()).collect();
+
+        assert_eq!(terms, analyze(text));
+        assert_eq!(&text[tokens[4].start..tokens[4].end], "die");
+        assert_eq!(&text[tokens[6].start..tokens[6].end], "Mozilla");
+    }
+
+    #[test]
+    fn test_highlight_prefers_dense_fragments() {
+        let content = "Rust is fast. Some filler words follow here and there. \
+                       Rust memory safety makes Rust popular.";
+        let terms: HashSet<String> = ["rust".to_string()].into_iter().collect();
+        let options = HighlightOptions {
+            pre_tag: String::from("["),
+            post_tag: String::from("]"),
+            fragment_size: 40,
+            number_of_fragments: 1,
+            ..HighlightOptions::default()
+        };
+
+        let fragments = highlight(content, &terms, &options);
+
+        assert_eq!(fragments.len(), 1);
+        assert_eq!(fragments[0].matches("[Rust]").count(), 2);
+        assert!(fragments[0].len() <= 40 + 2 * 2);
+    }
+
+    #[test]
+    fn test_highlight_escapes_html() {
+        let content = "Rust <script>alert(1)</script> & <img onerror=x> rust";
+        let terms: HashSet<String> = ["rust".to_string()].into_iter().collect();
+
+        let fragments = highlight(content, &terms, &HighlightOptions::default());
+        assert_eq!(
+            fragments[0],
+            "<em>Rust</em> &lt;script&gt;alert(1)&lt;/script&gt; &amp; &lt;img onerror=x&gt; <em>rust</em>"
+        );
+
+        let options = HighlightOptions {
+            encoder: Encoder::None,
+            ..HighlightOptions::default()
+        };
+        assert_eq!(
+            highlight(content, &terms, &options)[0]
+                .matches("<script>")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_highlight_without_matches() {
+        let terms: HashSet<String> = ["missing".to_string()].into_iter().collect();
+        let fragments = highlight("Nothing to see", &terms, &HighlightOptions::default());
+
+        assert!(fragments.is_empty());
+    }
+}
@@ -0,0 +1,191 @@
+#[cfg(test)]
+mod tests {
+    use rustysearch::{
+        search::{
+            engine::{remove_index_from_disk, SearchEngine},
+            filter::Filter,
+            hnsw::{Hnsw, Metric, VectorError},
+        },
+        types::{document::Document, field::FieldValue},
+    };
+
+    // Deterministic pseudo-random vectors
+    fn vectors(count: usize, dimension: usize) -> Vec<Vec<f32>> {
+        let mut state: u64 = 42;
+        (0..count)
+            .map(|_| {
+                (0..dimension)
+                    .map(|_| {
+                        state = state
+                            .wrapping_mul(6364136223846793005)
+                            .wrapping_add(1442695040888963407);
+                        (state >> 40) as f32 / (1u64 << 24) as f32 - 0.5
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn l2(a: &[f32], b: &[f32]) -> f32 {
+        a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+    }
+
+    #[test]
+    fn test_hnsw_recall() {
+        let data = vectors(1000, 16);
+        let mut graph = Hnsw::new(16, Metric::L2).unwrap();
+        for (i, vector) in data.iter().enumerate() {
+            graph.insert(&i.to_string(), vector);
+        }
+        assert_eq!(graph.len(), 1000);
+
+        let mut found = 0;
+        for query in vectors(1020, 16).iter().skip(1000) {
+            let mut exact: Vec<(usize, f32)> = data
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (i, l2(query, v)))
+                .collect();
+            exact.sort_by(|a, b| a.1.total_cmp(&b.1));
+            let approximate: Vec<String> = graph
+                .search(query, 10, 100, |_| true)
+                .into_iter()
+                .map(|(url, _)| url)
+                .collect();
+            found += exact
+                .iter()
+                .take(10)
+                .filter(|(i, _)| approximate.contains(&i.to_string()))
+                .count();
+        }
+        // At least 90% of the true nearest neighbors are found
+        assert!(found >= 180, "recall too low: {} of 200", found);
+    }
+
+    #[test]
+    fn test_hnsw_updates() {
+        let data = vectors(200, 8);
+        let mut graph = Hnsw::new(8, Metric::Cosine).unwrap();
+        for (i, vector) in data.iter().enumerate() {
+            graph.insert(&i.to_string(), vector);
+        }
+
+        // Reindexing with unchanged vectors keeps the nodes
+        for (i, vector) in data.iter().enumerate() {
+            graph.insert(&i.to_string(), vector);
+        }
+        assert_eq!(graph.deleted(), 0);
+
+        // Many updates rebuild the graph instead of piling up removed nodes
+        let updated = vectors(1200, 8);
+        for round in 0..5 {
+            for i in 0..200 {
+                graph.insert(&i.to_string(), &updated[200 + round * 200 + i]);
+            }
+            assert!(
+                graph.deleted() <= 200 / 3,
+                "{} removed nodes",
+                graph.deleted()
+            );
+        }
+        for i in 100..200 {
+            graph.remove(&i.to_string());
+        }
+        assert_eq!(graph.len(), 100);
+        assert!(graph.deleted() <= 100 / 3);
+
+        // The rebuilt graph still finds the current vectors
+        for i in 0..100 {
+            let hits = graph.search(&updated[1000 + i], 1, 50, |_| true);
+            assert_eq!(hits[0].0, i.to_string());
+        }
+    }
+
+    #[test]
+    fn test_hnsw_metrics() {
+        let mut cosine = Hnsw::new(2, Metric::Cosine).unwrap();
+        let mut dot = Hnsw::new(2, Metric::DotProduct).unwrap();
+        for (url, vector) in [
+            ("small", [1.0, 0.0]),
+            ("large", [10.0, 1.0]),
+            ("opposite", [-1.0, 0.0]),
+        ] {
+            cosine.insert(url, &vector);
+            dot.insert(url, &vector);
+        }
+
+        let cosine_hits = cosine.search(&[1.0, 0.0], 3, 10, |_| true);
+        assert_eq!(cosine_hits[0], ("small".to_string(), 1.0));
+        assert_eq!(cosine_hits[2], ("opposite".to_string(), 0.0));
+        assert_eq!(
+            dot.search(&[1.0, 0.0], 1, 10, |_| true)[0],
+            ("large".to_string(), 10.0)
+        );
+
+        // Replaced and removed vectors are never returned
+        cosine.insert("small", &[0.0, 1.0]);
+        cosine.remove("opposite");
+        let urls: Vec<String> = cosine
+            .search(&[1.0, 0.0], 3, 10, |_| true)
+            .into_iter()
+            .map(|(url, _)| url)
+            .collect();
+        assert_eq!(urls, vec!["large".to_string(), "small".to_string()]);
+        assert_eq!(
+            Hnsw::new(0, Metric::L2).err(),
+            Some(VectorError::InvalidDimension(0))
+        );
+    }
+
+    #[test]
+    fn test_knn_with_filters() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine
+            .create_vector_field("knn_embedding", 2, Metric::L2)
+            .unwrap();
+        for (i, vector) in vectors(200, 2).into_iter().enumerate() {
+            let tag = if i % 10 == 0 { "rare" } else { "common" };
+            search_engine.index_document(
+                &Document::new(&format!("https://knn.example.com/{}", i), "vectorized")
+                    .with_field("knn_tag", FieldValue::Keyword(tag.to_string()))
+                    .with_vector("knn_embedding", vector),
+            );
+        }
+
+        let filters = Filter::parse_all("knn_tag = rare").unwrap();
+        let hits = search_engine
+            .knn("knn_embedding", &[0.0, 0.0], 5, 10, &filters)
+            .unwrap();
+        assert_eq!(hits.len(), 5);
+        for (url, _) in &hits {
+            assert_eq!(
+                search_engine.field(url, "knn_tag"),
+                Some(&FieldValue::Keyword("rare".to_string()))
+            );
+        }
+
+        assert_eq!(
+            search_engine.knn("knn_embedding", &[0.0], 5, 10, &[]),
+            Err(VectorError::DimensionMismatch {
+                field: "knn_embedding".to_string(),
+                expected: 2,
+                actual: 1
+            })
+        );
+        assert_eq!(
+            search_engine.create_vector_field("knn_embedding", 3, Metric::L2),
+            Err(VectorError::FieldExists("knn_embedding".to_string()))
+        );
+
+        // The graph survives the round trip through the index file format
+        let graph = &search_engine.vector_fields()["knn_embedding"];
+        let reloaded: Hnsw = bincode::deserialize(&bincode::serialize(graph).unwrap()).unwrap();
+        let accept = |url: &str| search_engine.field(url, "knn_tag").is_some();
+        assert_eq!(
+            reloaded.search(&[0.0, 0.0], 5, 10, accept),
+            graph.search(&[0.0, 0.0], 5, 10, accept)
+        );
+
+        remove_index_from_disk();
+    }
+}
@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+    use std::fs;
+
+    use rustysearch::{
+        search::engine::{remove_index_from_disk, SearchEngine},
+        types::index::{IndexError, SavedIndexV0, INDEX_FORMAT_VERSION, INDEX_MAGIC},
+    };
+
+    // The only test of this file, so no other test writes the index while it runs
+    #[test]
+    fn test_index_format() {
+        // Files written before the format was versioned are migrated
+        let legacy = SavedIndexV0 {
+            index_btree_map: BTreeMap::from([(
+                "indexformat".to_string(),
+                HashMap::from([("https://indexformat.example.com".to_string(), 1)]),
+            )]),
+            documents_btree_map: BTreeMap::from([(
+                "https://indexformat.example.com".to_string(),
+                "indexformat legacy document with enough words".to_string(),
+            )]),
+        };
+        fs::write("/tmp/search.db", bincode::serialize(&legacy).unwrap()).unwrap();
+        let mut search_engine = SearchEngine::open(1.5, 0.75).unwrap();
+        assert_eq!(search_engine.number_of_documents(), 1);
+        assert_eq!(search_engine.search("indexformat").unwrap().len(), 1);
+
+        // The next write uses the current format, which reads back the same
+        search_engine.index(
+            "https://indexformat.example.com/copy",
+            "indexformat legacy document with enough words",
+        );
+        let bytes = fs::read("/tmp/search.db").unwrap();
+        assert_eq!(bytes[..4], INDEX_MAGIC);
+        assert_eq!(bytes[4..8], INDEX_FORMAT_VERSION.to_le_bytes());
+        let search_engine = SearchEngine::open(1.5, 0.75).unwrap();
+        assert_eq!(search_engine.number_of_documents(), 2);
+        // Signatures of migrated documents are computed from their content
+        assert_eq!(
+            search_engine.near_duplicates("https://indexformat.example.com/copy"),
+            vec!["https://indexformat.example.com".to_string()]
+        );
+
+        // Unreadable files are reported and left alone instead of being replaced
+        fs::write("/tmp/search.db", b"not an index").unwrap();
+        assert!(matches!(
+            SearchEngine::open(1.5, 0.75),
+            Err(IndexError::Corrupt(_))
+        ));
+        assert_eq!(fs::read("/tmp/search.db").unwrap(), b"not an index");
+
+        let mut newer = INDEX_MAGIC.to_vec();
+        newer.extend_from_slice(&(INDEX_FORMAT_VERSION + 1).to_le_bytes());
+        fs::write("/tmp/search.db", &newer).unwrap();
+        assert!(matches!(
+            SearchEngine::open(1.5, 0.75),
+            Err(IndexError::UnsupportedVersion(version)) if version == INDEX_FORMAT_VERSION + 1
+        ));
+
+        remove_index_from_disk();
+        assert_eq!(
+            SearchEngine::open(1.5, 0.75).unwrap().number_of_documents(),
+            0
+        );
+    }
+}
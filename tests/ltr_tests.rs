@@ -0,0 +1,213 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use rustysearch::{
+        search::{
+            engine::{remove_index_from_disk, SearchEngine},
+            ltr::{Branch, Feature, FeatureExtractor, ModelError, RankingModel, TreeNode},
+            query::Query,
+            rescore::{Rescore, Rescorer, ScoreMode},
+        },
+        types::{
+            document::Document,
+            field::{parse_date, FieldValue},
+        },
+    };
+
+    fn leaf(value: f64) -> Box<TreeNode> {
+        Box::new(TreeNode::Leaf(value))
+    }
+
+    #[test]
+    fn test_parse_features() {
+        let features = Feature::parse_all("bm25_content, proximity,freshness:published").unwrap();
+        assert_eq!(
+            features,
+            vec![
+                Feature::Bm25Content,
+                Feature::Proximity,
+                Feature::Freshness("published".to_string())
+            ]
+        );
+        assert_eq!(features[2].to_string(), "freshness:published");
+        assert!(Feature::parse("pagerank").is_err());
+        assert!(Feature::parse("popularity:").is_err());
+    }
+
+    #[test]
+    fn test_extract_features() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.index_document(
+            &Document::new(
+                "https://ltr.example.com/guide",
+                "ltrguide covers ltrranking in depth",
+            )
+            .with_title("Ltrranking guide")
+            .with_field(
+                "published",
+                FieldValue::Date(parse_date("2024-01-01").unwrap()),
+            )
+            .with_field("views", FieldValue::Number(120.0)),
+        );
+        search_engine.index("https://ltr.example.com/other", "ltrranking elsewhere");
+        let query = Query::parse("ltrguide ltrranking").unwrap();
+        let now = parse_date("2024-01-11").unwrap();
+        let extractor = FeatureExtractor::new(&search_engine, &query, now);
+
+        let features = Feature::parse_all(
+            "score,bm25_content,bm25_title,proximity,document_length,freshness:published,popularity:views,popularity:likes",
+        )
+        .unwrap();
+        let values = extractor.extract(&features, "https://ltr.example.com/guide", 3.0);
+        let content = search_engine.search_query(&query).unwrap()["https://ltr.example.com/guide"];
+        assert_eq!(values["score"], 3.0);
+        assert!((values["bm25_content"] - content).abs() < 1e-12);
+        assert!(values["bm25_title"] > 0.0);
+        assert_eq!(values["proximity"], 2.0 / 3.0);
+        assert_eq!(values["document_length"], 5.0);
+        assert_eq!(values["freshness:published"], 10.0);
+        assert_eq!(values["popularity:views"], 120.0);
+        // Features of missing fields are left out
+        assert!(!values.contains_key("popularity:likes"));
+
+        let values = extractor.extract(&features, "https://ltr.example.com/other", 1.0);
+        assert_eq!(values["bm25_title"], 0.0);
+        assert_eq!(values["proximity"], 0.0);
+
+        remove_index_from_disk();
+    }
+
+    #[test]
+    fn test_model_scores() {
+        let features = BTreeMap::from([
+            ("bm25_content".to_string(), 2.0),
+            ("proximity".to_string(), 0.5),
+        ]);
+
+        let linear = RankingModel::Linear {
+            bias: 1.0,
+            weights: BTreeMap::from([
+                ("bm25_content".to_string(), 0.5),
+                ("proximity".to_string(), 4.0),
+                ("popularity:views".to_string(), 10.0),
+            ]),
+        };
+        assert!(linear.validate().is_ok());
+        assert_eq!(linear.score(&features), 1.0 + 1.0 + 2.0);
+        assert_eq!(linear.features().len(), 3);
+
+        let trees = RankingModel::Trees {
+            base_score: 0.5,
+            trees: vec![
+                TreeNode::Split {
+                    feature: "bm25_content".to_string(),
+                    threshold: 1.0,
+                    missing: Branch::Left,
+                    left: leaf(-1.0),
+                    right: Box::new(TreeNode::Split {
+                        feature: "proximity".to_string(),
+                        threshold: 0.5,
+                        missing: Branch::Left,
+                        left: leaf(1.0),
+                        right: leaf(2.0),
+                    }),
+                },
+                TreeNode::Split {
+                    feature: "freshness:published".to_string(),
+                    threshold: 30.0,
+                    missing: Branch::Right,
+                    left: leaf(0.25),
+                    right: leaf(-0.25),
+                },
+            ],
+        };
+        assert!(trees.validate().is_ok());
+        // Values equal to the threshold go right, missing features follow the split
+        assert_eq!(trees.score(&features), 0.5 + 2.0 - 0.25);
+
+        let json = serde_json::to_string(&trees).unwrap();
+        assert_eq!(serde_json::from_str::<RankingModel>(&json).unwrap(), trees);
+        let bytes = bincode::serialize(&trees).unwrap();
+        assert_eq!(bincode::deserialize::<RankingModel>(&bytes).unwrap(), trees);
+    }
+
+    #[test]
+    fn test_validate_model() {
+        let model: RankingModel = serde_json::from_value(serde_json::json!({
+            "linear": {"weights": {"pagerank": 1.0}}
+        }))
+        .unwrap();
+        assert_eq!(
+            model.validate(),
+            Err(ModelError::UnknownFeature("pagerank".to_string()))
+        );
+
+        let model: RankingModel = serde_json::from_value(serde_json::json!({
+            "trees": {"trees": []}
+        }))
+        .unwrap();
+        assert_eq!(model.validate(), Err(ModelError::Empty));
+
+        let model: RankingModel = serde_json::from_value(serde_json::json!({
+            "trees": {"trees": [{"split": {
+                "feature": "score",
+                "threshold": 1.0,
+                "left": {"leaf": 1.0},
+                "right": {"leaf": 2.0}
+            }}]}
+        }))
+        .unwrap();
+        assert!(model.validate().is_ok());
+    }
+
+    #[test]
+    fn test_rescore_with_model() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.index_document(
+            &Document::new("https://ltrmodel.example.com/popular", "ltrmodel")
+                .with_field("views", FieldValue::Number(1000.0)),
+        );
+        search_engine.index_document(
+            &Document::new("https://ltrmodel.example.com/obscure", "ltrmodel ltrmodel")
+                .with_field("views", FieldValue::Number(3.0)),
+        );
+        let model = RankingModel::Linear {
+            bias: 0.0,
+            weights: BTreeMap::from([("popularity:views".to_string(), 0.01)]),
+        };
+        assert!(search_engine
+            .put_ranking_model("popularity", model)
+            .unwrap());
+
+        let query = Query::parse("ltrmodel").unwrap();
+        let ranked = vec![
+            ("https://ltrmodel.example.com/obscure".to_string(), 1.0),
+            ("https://ltrmodel.example.com/popular".to_string(), 0.9),
+        ];
+        let rescore = Rescore {
+            window_size: 10,
+            query_weight: 0.0,
+            rescore_query_weight: 1.0,
+            score_mode: ScoreMode::Total,
+            scorers: vec![Rescorer::Model {
+                name: "popularity".to_string(),
+            }],
+        };
+        assert!(rescore.check_models(&search_engine).is_ok());
+        let rescored = rescore.rescore(&search_engine, &query, ranked, 0);
+        assert_eq!(
+            rescored[0],
+            ("https://ltrmodel.example.com/popular".to_string(), 10.0)
+        );
+        assert_eq!(
+            rescored[1],
+            ("https://ltrmodel.example.com/obscure".to_string(), 0.03)
+        );
+
+        assert!(search_engine.delete_ranking_model("popularity"));
+        assert!(rescore.check_models(&search_engine).is_err());
+
+        remove_index_from_disk();
+    }
+}
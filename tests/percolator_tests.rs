@@ -0,0 +1,134 @@
+#[cfg(test)]
+mod tests {
+    use rustysearch::{
+        search::{
+            engine::{remove_index_from_disk, SearchEngine},
+            percolator::PercolatorQuery,
+            synonyms::SynonymSet,
+        },
+        types::{document::Document, field::FieldValue},
+    };
+
+    fn query(query: &str, filter: Option<&str>) -> PercolatorQuery {
+        PercolatorQuery {
+            query: query.to_string(),
+            filter: filter.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_percolate_terms_and_phrases() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        assert!(search_engine
+            .put_percolator_query("terms", query("percolone percoltwo", None))
+            .unwrap());
+        assert!(search_engine
+            .put_percolator_query("phrase", query("\"percolthree percolfour\"", None))
+            .unwrap());
+
+        search_engine.index("https://percol.example.com/a", "percoltwo and more");
+        assert_eq!(
+            search_engine.percolate("https://percol.example.com/a"),
+            vec!["terms".to_string()]
+        );
+
+        // All terms of a phrase must occur in order
+        search_engine.index(
+            "https://percol.example.com/b",
+            "percolfour before percolthree",
+        );
+        assert!(search_engine
+            .percolate("https://percol.example.com/b")
+            .is_empty());
+        search_engine.index("https://percol.example.com/c", "percolthree percolfour");
+        assert_eq!(
+            search_engine.percolate("https://percol.example.com/c"),
+            vec!["phrase".to_string()]
+        );
+
+        assert!(search_engine
+            .percolate("https://percol.example.com/missing")
+            .is_empty());
+
+        remove_index_from_disk();
+    }
+
+    #[test]
+    fn test_percolate_expanded_queries_and_filters() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine
+            .put_percolator_query("prefix", query("percexp*", None))
+            .unwrap();
+        search_engine
+            .put_percolator_query("fuzzy", query("percfuzzy~1", None))
+            .unwrap();
+        search_engine
+            .put_percolator_query("english", query("percfilter", Some("lang = en")))
+            .unwrap();
+
+        search_engine.index("https://percexp.example.com/a", "percexpanded percfuzzi");
+        assert_eq!(
+            search_engine.percolate("https://percexp.example.com/a"),
+            vec!["fuzzy".to_string(), "prefix".to_string()]
+        );
+
+        search_engine.index_document(
+            &Document::new("https://percexp.example.com/de", "percfilter")
+                .with_field("lang", FieldValue::Keyword("de".to_string())),
+        );
+        assert!(search_engine
+            .percolate("https://percexp.example.com/de")
+            .is_empty());
+        search_engine.index_document(
+            &Document::new("https://percexp.example.com/en", "percfilter")
+                .with_field("lang", FieldValue::Keyword("en".to_string())),
+        );
+        assert_eq!(
+            search_engine.percolate("https://percexp.example.com/en"),
+            vec!["english".to_string()]
+        );
+
+        // Malformed queries are rejected when they are stored
+        assert!(search_engine
+            .put_percolator_query("regex", query("/perc[/", None))
+            .is_err());
+        assert!(search_engine
+            .put_percolator_query("filter", query("percfilter", Some("lang")))
+            .is_err());
+        assert!(!search_engine.percolator_queries().contains_key("regex"));
+
+        remove_index_from_disk();
+    }
+
+    #[test]
+    fn test_percolate_with_synonyms_and_updates() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine
+            .put_percolator_query("cars", query("percauto", None))
+            .unwrap();
+        search_engine.index("https://percsyn.example.com/a", "percvehicle for sale");
+        assert!(search_engine
+            .percolate("https://percsyn.example.com/a")
+            .is_empty());
+
+        // The stored queries are reindexed when the synonyms change
+        search_engine.set_synonyms(SynonymSet::parse("percauto, percvehicle").unwrap());
+        assert_eq!(
+            search_engine.percolate("https://percsyn.example.com/a"),
+            vec!["cars".to_string()]
+        );
+
+        assert!(!search_engine
+            .put_percolator_query("cars", query("percboat", None))
+            .unwrap());
+        assert!(search_engine
+            .percolate("https://percsyn.example.com/a")
+            .is_empty());
+        assert!(search_engine.delete_percolator_query("cars"));
+        assert!(!search_engine.delete_percolator_query("cars"));
+        assert!(search_engine.percolator_queries().is_empty());
+
+        search_engine.set_synonyms(SynonymSet::default());
+        remove_index_from_disk();
+    }
+}
@@ -0,0 +1,61 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rustysearch::search::{
+        engine::{remove_index_from_disk, SearchEngine},
+        pit::{parse_keep_alive, PointInTimeStore},
+        query::QueryError,
+    };
+
+    #[test]
+    fn test_parse_keep_alive() {
+        assert_eq!(parse_keep_alive("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_keep_alive("5m"), Ok(Duration::from_secs(300)));
+        assert_eq!(parse_keep_alive("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(
+            parse_keep_alive("0s"),
+            Err(QueryError::InvalidKeepAlive("0s".to_string()))
+        );
+        assert_eq!(
+            parse_keep_alive("1d"),
+            Ok(Duration::from_secs(24 * 60 * 60))
+        );
+        assert!(parse_keep_alive("2d").is_err());
+        assert!(parse_keep_alive("1w").is_err());
+        assert!(parse_keep_alive("5 minutes").is_err());
+    }
+
+    #[test]
+    fn test_snapshot_is_isolated_from_writes() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.index("https://pit.example.com/a", "snapshotted");
+        let snapshot = search_engine.snapshot();
+
+        search_engine.index("https://pit.example.com/b", "snapshotted");
+
+        assert_eq!(search_engine.search("snapshotted").unwrap().len(), 2);
+        assert_eq!(snapshot.search("snapshotted").unwrap().len(), 1);
+
+        remove_index_from_disk();
+    }
+
+    #[test]
+    fn test_point_in_time_store() {
+        let store = PointInTimeStore::default();
+        let snapshot = SearchEngine::default().snapshot();
+
+        let id = store
+            .open(snapshot.clone(), Duration::from_secs(60))
+            .unwrap();
+        assert!(store.get(&id, None).is_some());
+        assert!(store.get("unknown", None).is_none());
+        assert!(store.close(&id));
+        assert!(!store.close(&id));
+
+        // Expired points in time are gone
+        let id = store.open(snapshot, Duration::from_millis(1)).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(store.get(&id, None).is_none());
+    }
+}
@@ -0,0 +1,133 @@
+#[cfg(test)]
+mod tests {
+    use rustysearch::search::{
+        engine::{remove_index_from_disk, SearchEngine},
+        query::{Clause, Fuzziness, Query, QueryError},
+    };
+
+    #[test]
+    fn test_parse_fuzzy_clauses() {
+        let query = Query::parse("Rsut~1 speicher~ Mozilla").unwrap();
+
+        assert_eq!(
+            query.clauses,
+            vec![
+                Clause::Fuzzy {
+                    term: "rsut".to_string(),
+                    fuzziness: Fuzziness::Distance(1)
+                },
+                Clause::Fuzzy {
+                    term: "speicher".to_string(),
+                    fuzziness: Fuzziness::Auto
+                },
+                Clause::Term("mozilla".to_string()),
+            ]
+        );
+        assert_eq!(
+            Query::parse("rust~3"),
+            Err(QueryError::InvalidFuzziness("3".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_auto_fuzziness() {
+        assert_eq!(Fuzziness::Auto.max_distance("rs"), 0);
+        assert_eq!(Fuzziness::Auto.max_distance("rsut"), 1);
+        assert_eq!(Fuzziness::Auto.max_distance("speicherverwaltng"), 2);
+    }
+
+    #[test]
+    fn test_fuzzy_search() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.bulk_index(vec![
+            (
+                "https://fuzzy.example.com/rust",
+                "Rustacean Speicherverwaltung",
+            ),
+            ("https://fuzzy.example.com/other", "Rustacaen"),
+        ]);
+
+        let exact = search_engine.search("Speicherverwaltng").unwrap();
+        assert!(!exact.contains_key("https://fuzzy.example.com/rust"));
+
+        let fuzzy = search_engine.search("Speicherverwaltng~").unwrap();
+        assert!(fuzzy.contains_key("https://fuzzy.example.com/rust"));
+
+        // The transposed term is expanded with a slightly lower boost than the exact one
+        let results = search_engine.search("rustacean~1").unwrap();
+        assert!(results.contains_key("https://fuzzy.example.com/other"));
+        let query = Query::parse("rustacean~1").unwrap();
+        let expanded = search_engine.expand_query(&query).unwrap();
+        assert!(expanded.contains(&("rustacean".to_string(), 1.0)));
+        assert!(expanded.contains(&("rustacaen".to_string(), 0.9)));
+
+        remove_index_from_disk();
+    }
+
+    #[test]
+    fn test_parse_multi_term_clauses() {
+        let query = Query::parse("Rust* R?st* /rust(y|ic)/").unwrap();
+
+        assert_eq!(
+            query.clauses,
+            vec![
+                Clause::Prefix("rust".to_string()),
+                Clause::Wildcard("r?st*".to_string()),
+                Clause::Regex("rust(y|ic)".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_phrases() {
+        let query = Query::parse("Mozilla \"Rust Programming\" \"Research\"").unwrap();
+
+        assert_eq!(
+            query.clauses,
+            vec![
+                Clause::Term("mozilla".to_string()),
+                Clause::Phrase(vec!["rust".to_string(), "programming".to_string()]),
+                Clause::Term("research".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multi_term_search() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.bulk_index(vec![
+            (
+                "https://multiterm.example.com/a",
+                "zyxprogramm zyxprogrammer",
+            ),
+            ("https://multiterm.example.com/b", "zyxprogrammierung"),
+            ("https://multiterm.example.com/c", "zyxprozess"),
+        ]);
+
+        let prefix = search_engine.search("zyxprogr*").unwrap();
+        assert_eq!(prefix.len(), 2);
+
+        let wildcard = search_engine.search("zyxpro?ess").unwrap();
+        assert_eq!(wildcard.len(), 1);
+        assert!(wildcard.contains_key("https://multiterm.example.com/c"));
+
+        let regex = search_engine.search("/zyxprogramm(er)?/").unwrap();
+        assert_eq!(regex.len(), 1);
+        assert!(regex.contains_key("https://multiterm.example.com/a"));
+
+        let limited = Query::parse("zyxpro*").unwrap().with_max_expansions(3);
+        assert_eq!(
+            search_engine.search_query(&limited),
+            Err(QueryError::TooManyExpansions {
+                pattern: "zyxpro*".to_string(),
+                max_expansions: 3
+            })
+        );
+        assert_eq!(
+            search_engine.search("/zyx(/"),
+            Err(QueryError::InvalidRegex("zyx(".to_string()))
+        );
+
+        remove_index_from_disk();
+    }
+}
@@ -0,0 +1,147 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use rustysearch::{
+        search::{
+            analyzer::analyze,
+            engine::{remove_index_from_disk, SearchEngine},
+            explain::Explanation,
+            query::Query,
+            rescore::{min_span, Rescore, Rescorer, ScoreMode},
+        },
+        types::document::Document,
+    };
+
+    fn rescore(window_size: usize, scorers: Vec<Rescorer>) -> Rescore {
+        Rescore {
+            window_size,
+            query_weight: 1.0,
+            rescore_query_weight: 1.0,
+            score_mode: ScoreMode::Total,
+            scorers,
+        }
+    }
+
+    #[test]
+    fn test_min_span() {
+        let tokens = analyze("a x b a y y b c");
+        let a = "a".to_string();
+        let b = "b".to_string();
+        let c = "c".to_string();
+        assert_eq!(min_span(&tokens, &HashSet::from([&a, &b])), Some(2));
+        assert_eq!(min_span(&tokens, &HashSet::from([&a, &c])), Some(5));
+        let missing = "z".to_string();
+        assert_eq!(min_span(&tokens, &HashSet::from([&a, &missing])), None);
+    }
+
+    #[test]
+    fn test_proximity_rescore() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.bulk_index(vec![
+            (
+                "https://rescore.example.com/apart",
+                "rescoreborrow rescoreborrow with a long gap before rescorechecker",
+            ),
+            (
+                "https://rescore.example.com/close",
+                "the rescoreborrow rescorechecker and more filler words here",
+            ),
+            ("https://rescore.example.com/single", "rescoreborrow"),
+        ]);
+        let query = Query::parse("rescoreborrow rescorechecker").unwrap();
+        let ranked = vec![
+            ("https://rescore.example.com/apart".to_string(), 2.0),
+            ("https://rescore.example.com/close".to_string(), 1.5),
+            ("https://rescore.example.com/single".to_string(), 1.0),
+        ];
+
+        let proximity = rescore(2, vec![Rescorer::Proximity { boost: 1.0 }]);
+        let rescored = proximity.rescore(&search_engine, &query, ranked.clone(), 0);
+        assert_eq!(
+            rescored[0],
+            ("https://rescore.example.com/close".to_string(), 2.5)
+        );
+        assert_eq!(rescored[1].0, "https://rescore.example.com/apart");
+        assert!((rescored[1].1 - (2.0 + 2.0 / 7.0)).abs() < 1e-12);
+        // Hits outside of the window keep their score and position
+        assert_eq!(rescored[2], ranked[2]);
+
+        let explanation = search_engine
+            .explain(&query, "https://rescore.example.com/apart")
+            .unwrap()
+            .unwrap();
+        let original = explanation.value;
+        let explanation = proximity.explain(
+            &search_engine,
+            &query,
+            "https://rescore.example.com/apart",
+            explanation,
+            0,
+        );
+        let rescored = proximity.rescore(
+            &search_engine,
+            &query,
+            vec![("https://rescore.example.com/apart".to_string(), original)],
+            0,
+        );
+        assert_eq!(explanation.value, rescored[0].1);
+
+        remove_index_from_disk();
+    }
+
+    #[test]
+    fn test_field_match_and_score_modes() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.index_document(
+            &Document::new("https://rescorefield.example.com", "fieldrescore text")
+                .with_title("Fieldrescore guide"),
+        );
+        let query = Query::parse("fieldrescore guide").unwrap();
+        let ranked = vec![("https://rescorefield.example.com".to_string(), 2.0)];
+
+        let mut field_match = rescore(
+            10,
+            vec![Rescorer::FieldMatch {
+                field: "title".to_string(),
+                boost: 3.0,
+            }],
+        );
+        // Both query terms occur in the title
+        let score = |r: &Rescore| r.rescore(&search_engine, &query, ranked.clone(), 0)[0].1;
+        assert_eq!(score(&field_match), 8.0);
+        field_match.score_mode = ScoreMode::Multiply;
+        field_match.query_weight = 0.5;
+        assert_eq!(score(&field_match), 6.0);
+        field_match.score_mode = ScoreMode::Max;
+        assert_eq!(score(&field_match), 6.0);
+        field_match.score_mode = ScoreMode::Min;
+        assert_eq!(score(&field_match), 1.0);
+
+        // Explanations name the score mode like requests do
+        let explanation = field_match.explain(
+            &search_engine,
+            &query,
+            "https://rescorefield.example.com",
+            Explanation::new(2.0, "original"),
+            0,
+        );
+        assert!(explanation
+            .description
+            .starts_with("rescored with score_mode min,"));
+        for mode in [
+            ScoreMode::Total,
+            ScoreMode::Multiply,
+            ScoreMode::Avg,
+            ScoreMode::Max,
+            ScoreMode::Min,
+        ] {
+            assert_eq!(serde_json::to_value(mode).unwrap(), mode.to_string());
+        }
+
+        field_match.window_size = 100_000;
+        assert!(field_match.validate().is_err());
+
+        remove_index_from_disk();
+    }
+}
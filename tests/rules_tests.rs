@@ -0,0 +1,173 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rustysearch::search::{
+        analyzer::analyze,
+        engine::{remove_index_from_disk, SearchEngine},
+        explain::Explanation,
+        rules::{MatchKind, QueryRule, RuleAction, RuleError, RuleOutcome},
+    };
+
+    fn rule(kind: MatchKind, query: &str, actions: Vec<RuleAction>) -> QueryRule {
+        QueryRule {
+            kind,
+            query: query.to_string(),
+            actions,
+        }
+    }
+
+    fn urls(urls: &[&str]) -> Vec<String> {
+        urls.iter().map(|url| url.to_string()).collect()
+    }
+
+    #[test]
+    fn test_matches() {
+        let redirect = vec![RuleAction::Redirect {
+            url: "/landing".to_string(),
+        }];
+        let exact = rule(MatchKind::Exact, "Rust Book", redirect.clone());
+        let prefix = rule(MatchKind::Prefix, "rust", redirect.clone());
+        let contains = rule(MatchKind::Contains, "rust book", redirect);
+
+        assert!(exact.matches(&analyze("rust book")));
+        assert!(!exact.matches(&analyze("rust book online")));
+        assert!(prefix.matches(&analyze("rust async")));
+        assert!(!prefix.matches(&analyze("trust rust")));
+        assert!(contains.matches(&analyze("the rust book online")));
+        assert!(!contains.matches(&analyze("book rust")));
+    }
+
+    #[test]
+    fn test_outcome() {
+        let rules = [
+            rule(
+                MatchKind::Contains,
+                "rust",
+                vec![
+                    RuleAction::Pin {
+                        urls: urls(&[
+                            "https://rules.example.com/pinned",
+                            "https://rules.example.com/hidden",
+                        ]),
+                    },
+                    RuleAction::Boost {
+                        urls: urls(&["https://rules.example.com/boosted"]),
+                        factor: 2.0,
+                    },
+                ],
+            ),
+            rule(
+                MatchKind::Exact,
+                "rust",
+                vec![
+                    RuleAction::Hide {
+                        urls: urls(&["https://rules.example.com/hidden"]),
+                    },
+                    RuleAction::Redirect {
+                        url: "https://rules.example.com/landing".to_string(),
+                    },
+                ],
+            ),
+        ];
+        let outcome = RuleOutcome::evaluate(rules.iter(), "Rust");
+        assert_eq!(outcome.pinned, urls(&["https://rules.example.com/pinned"]));
+        assert_eq!(
+            outcome.redirect.as_deref(),
+            Some("https://rules.example.com/landing")
+        );
+
+        let mut results: HashMap<String, f64> = [
+            ("https://rules.example.com/hidden", 3.0),
+            ("https://rules.example.com/boosted", 1.5),
+            ("https://rules.example.com/other", 2.0),
+        ]
+        .iter()
+        .map(|(url, score)| (url.to_string(), *score))
+        .collect();
+        outcome.apply(&mut results, |_| true);
+        assert_eq!(results.len(), 3);
+        assert_eq!(results["https://rules.example.com/boosted"], 3.0);
+        assert_eq!(results["https://rules.example.com/pinned"], 0.0);
+
+        let ranked = vec![
+            ("https://rules.example.com/boosted".to_string(), 3.0),
+            ("https://rules.example.com/other".to_string(), 2.0),
+            ("https://rules.example.com/pinned".to_string(), 0.0),
+        ];
+        let pinned = outcome.pin(ranked.clone(), true);
+        assert_eq!(pinned[0].0, "https://rules.example.com/pinned");
+        assert_eq!(pinned[1].0, "https://rules.example.com/boosted");
+        assert_eq!(outcome.pin(ranked, false).len(), 2);
+
+        let explanation = outcome
+            .explain(
+                "https://rules.example.com/boosted",
+                Some(Explanation::new(1.5, "text score")),
+            )
+            .unwrap();
+        assert_eq!(explanation.value, 3.0);
+        assert_eq!(explanation.details[0].description, "text score");
+        assert_eq!(
+            outcome
+                .explain("https://rules.example.com/pinned", None)
+                .unwrap()
+                .value,
+            0.0
+        );
+        assert!(outcome
+            .explain("https://rules.example.com/other", None)
+            .is_none());
+
+        // Rules for other queries do nothing
+        assert_eq!(
+            RuleOutcome::evaluate(rules.iter(), "python"),
+            RuleOutcome::default()
+        );
+    }
+
+    #[test]
+    fn test_store_rules() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        let pin = rule(
+            MatchKind::Exact,
+            "rulestore",
+            vec![RuleAction::Pin {
+                urls: urls(&["https://rulestore.example.com"]),
+            }],
+        );
+
+        assert_eq!(search_engine.put_query_rule("pin", pin.clone()), Ok(true));
+        assert_eq!(search_engine.put_query_rule("pin", pin.clone()), Ok(false));
+        assert_eq!(search_engine.query_rules()["pin"], pin);
+        assert_eq!(
+            search_engine.put_query_rule("empty", rule(MatchKind::Exact, " ", pin.actions.clone())),
+            Err(RuleError::EmptyQuery)
+        );
+        assert_eq!(
+            search_engine.put_query_rule(
+                "boost",
+                rule(
+                    MatchKind::Exact,
+                    "rulestore",
+                    vec![RuleAction::Boost {
+                        urls: urls(&["https://rulestore.example.com"]),
+                        factor: 0.0
+                    }]
+                )
+            ),
+            Err(RuleError::InvalidBoost(0.0))
+        );
+
+        // Rules are stored with the index
+        let bytes = bincode::serialize(&search_engine.query_rules()).unwrap();
+        let restored: std::collections::BTreeMap<String, QueryRule> =
+            bincode::deserialize(&bytes).unwrap();
+        assert_eq!(&restored, search_engine.query_rules());
+
+        assert!(search_engine.delete_query_rule("pin"));
+        assert!(!search_engine.delete_query_rule("pin"));
+
+        remove_index_from_disk();
+    }
+}
@@ -0,0 +1,112 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use rustysearch::{
+        search::{
+            engine::{remove_index_from_disk, SearchEngine},
+            explain::Explanation,
+            script::{Script, ScriptError, ScriptScore, MAX_SCRIPT_LENGTH},
+        },
+        types::{
+            document::Document,
+            field::{parse_date, FieldValue},
+        },
+    };
+
+    #[test]
+    fn test_evaluate_script() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.index_document(
+            &Document::new("https://script.example.com", "scripted content")
+                .with_field("views", FieldValue::Number(99.0))
+                .with_field(
+                    "published",
+                    FieldValue::Date(parse_date("2024-03-01").unwrap()),
+                )
+                .with_boost(2.0),
+        );
+        let url = "https://script.example.com";
+        let now = parse_date("2024-03-04").unwrap();
+        let evaluate = |source: &str, score: f64| {
+            Script::compile(source)
+                .unwrap()
+                .evaluate(&search_engine, url, score, now)
+        };
+
+        assert_eq!(evaluate("1 + 2 * 3 - -4 / 2", 0.0), 9.0);
+        assert_eq!(evaluate("(1 + 2) * 3 % 4", 0.0), 1.0);
+        assert_eq!(evaluate("_score * log10(1 + doc['views'])", 3.0), 6.0);
+        assert_eq!(evaluate("recency('published')", 0.0), 0.25);
+        assert_eq!(evaluate("_boost * max(_score, 1) + pow(2, 3)", 0.5), 10.0);
+        assert_eq!(evaluate("doc[\"likes\"] + recency('likes')", 0.0), 0.0);
+        // Results that are not finite do not score at all
+        assert_eq!(evaluate("_score / 0", 1.0), 0.0);
+        assert_eq!(evaluate("sqrt(-1)", 1.0), 0.0);
+
+        let script = Script::compile("_score * 2").unwrap();
+        let explanation = script.explain(&search_engine, url, Explanation::new(1.5, "text"), now);
+        assert_eq!(explanation.value, 3.0);
+        assert_eq!(explanation.details[0].description, "text");
+
+        remove_index_from_disk();
+    }
+
+    #[test]
+    fn test_compile_errors() {
+        let error = |source: &str| Script::compile(source).unwrap_err();
+        assert_eq!(
+            error("system('rm')"),
+            ScriptError::UnknownFunction("system".to_string())
+        );
+        assert_eq!(
+            error("max(1)"),
+            ScriptError::WrongArity {
+                function: "max".to_string(),
+                expected: 2
+            }
+        );
+        assert!(matches!(
+            error("1 + * 2"),
+            ScriptError::Syntax { position: 4, .. }
+        ));
+        assert!(matches!(error("doc['views"), ScriptError::Syntax { .. }));
+        assert!(matches!(error("1 2"), ScriptError::Syntax { .. }));
+        assert!(matches!(error("1 + 2;"), ScriptError::Syntax { .. }));
+        assert_eq!(
+            error(&"1".repeat(MAX_SCRIPT_LENGTH + 1)),
+            ScriptError::TooLong
+        );
+        assert_eq!(
+            error(&format!("{}1{}", "(".repeat(40), ")".repeat(40))),
+            ScriptError::TooComplex
+        );
+        assert_eq!(error(&vec!["1"; 300].join("+")), ScriptError::TooComplex);
+
+        let script_score = ScriptScore {
+            source: "_score".to_string(),
+            timeout: Some("1h".to_string()),
+        };
+        assert!(matches!(
+            script_score.compile(),
+            Err(ScriptError::InvalidTimeout(_))
+        ));
+    }
+
+    #[test]
+    fn test_script_timeout() {
+        let search_engine = SearchEngine::new(1.5, 0.75);
+        let mut results = HashMap::from([("https://timeout.example.com".to_string(), 1.0)]);
+
+        let script = Script::compile("_score + 1").unwrap();
+        assert!(script.score_all(&search_engine, &mut results, 0).is_ok());
+        assert_eq!(results["https://timeout.example.com"], 2.0);
+
+        let script = script.with_timeout(Duration::ZERO);
+        assert_eq!(
+            script.score_all(&search_engine, &mut results, 0),
+            Err(ScriptError::Timeout(Duration::ZERO))
+        );
+    }
+}
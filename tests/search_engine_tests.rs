@@ -7,7 +7,7 @@ mod tests {
         let mut search_engine = SearchEngine::new(1.5, 0.75);
 
         search_engine.index("https://www.rust-lang.org/", "Rust Programming Language");
-        let result = search_engine.search("Rust");
+        let result = search_engine.search("Rust").unwrap();
 
         assert_eq!(result.len(), 1);
         assert_eq!(search_engine.posts().len(), 1);
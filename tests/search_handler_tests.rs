@@ -1,21 +1,32 @@
 #[cfg(test)]
 mod tests {
-    use std::sync::Mutex;
+    use std::sync::mpsc;
+    use std::time::Duration;
 
     use actix_web::{test, web, App};
     use rustysearch::{
         handlers::search,
-        search::engine::{remove_index_from_disk, SearchEngine},
-        types::app_state::AppStateWithSearchEngine,
+        search::{
+            engine::{remove_index_from_disk, SearchEngine},
+            hnsw::Metric,
+        },
+        types::{
+            app_state::AppStateWithSearchEngine,
+            document::Document,
+            field::FieldValue,
+            response::{
+                ErrorResponse, ExplainResponse, IndexDocumentResponse, PercolatorQueriesResponse,
+                PointInTimeResponse, QueryRuleResponse, QueryRulesResponse, RankingModelResponse,
+                SearchResponse, SearchTemplatesResponse, SpellResponse, SuggestResponse,
+            },
+        },
     };
 
     #[actix_web::test]
     async fn test_add_document_to_index() {
         let search_engine = SearchEngine::new(1.5, 0.75);
 
-        let app_state = web::Data::new(AppStateWithSearchEngine {
-            search_engine: Mutex::new(search_engine.clone()),
-        });
+        let app_state = web::Data::new(AppStateWithSearchEngine::new(search_engine.clone()));
 
         let app = test::init_service(App::new().app_data(app_state.clone()).route(
             "/search/index/document",
@@ -24,18 +35,81 @@ mod tests {
         .await;
 
         let data = search::AddDocumentRequest {
-            url: "https://example.com".to_string(),
-            content: "This is an example document".to_string(),
+            url: "https://indexhandler.example.com".to_string(),
+            content: "Indexhandler original original document".to_string(),
+            title: Some("Indexhandler original".to_string()),
+            ..Default::default()
         };
 
         let req = test::TestRequest::post()
             .uri("/search/index/document")
-            .set_json(data)
+            .set_json(&data)
             .to_request();
 
         let resp = test::call_service(&app, req).await;
         assert!(resp.status() == 201);
 
+        // Indexing the URL again replaces the document, including its old words and title
+        let data = search::AddDocumentRequest {
+            content: "Indexhandler replacement".to_string(),
+            title: None,
+            ..data
+        };
+        let req = test::TestRequest::post()
+            .uri("/search/index/document")
+            .set_json(&data)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 200);
+        let body: IndexDocumentResponse = test::read_body_json(resp).await;
+        assert_eq!(body.result, "updated");
+
+        let search_engine = app_state.search_engine.lock().unwrap();
+        assert!(search_engine.search("original").unwrap().is_empty());
+        assert_eq!(search_engine.search("replacement").unwrap().len(), 1);
+        assert_eq!(search_engine.search("indexhandler").unwrap().len(), 1);
+        assert!(search_engine
+            .title("https://indexhandler.example.com")
+            .is_none());
+        drop(search_engine);
+
+        remove_index_from_disk();
+    }
+
+    #[actix_web::test]
+    async fn test_malformed_requests() {
+        let search_engine = SearchEngine::new(1.5, 0.75);
+
+        let app_state = web::Data::new(AppStateWithSearchEngine::new(search_engine.clone()));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .app_data(search::query_config())
+                .app_data(search::json_config())
+                .route("/search", web::get().to(search::search))
+                .route("/search", web::post().to(search::search_with_body)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/search?query=malformed&size=many")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 400);
+        let body: ErrorResponse = test::read_body_json(resp).await;
+        assert!(body.error.starts_with("Invalid query parameters"));
+
+        let req = test::TestRequest::post()
+            .uri("/search")
+            .insert_header(("content-type", "application/json"))
+            .set_payload("{\"query\": ")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 400);
+        let body: ErrorResponse = test::read_body_json(resp).await;
+        assert!(body.error.starts_with("Invalid request body"));
+
         remove_index_from_disk();
     }
 
@@ -44,9 +118,7 @@ mod tests {
         let mut search_engine = SearchEngine::new(1.5, 0.75);
         search_engine.index("https://example.com", "This is an example document");
 
-        let app_state = web::Data::new(AppStateWithSearchEngine {
-            search_engine: Mutex::new(search_engine.clone()),
-        });
+        let app_state = web::Data::new(AppStateWithSearchEngine::new(search_engine.clone()));
 
         let app = test::init_service(App::new().app_data(app_state.clone()).route(
             "/search/index/number_of_documents",
@@ -69,9 +141,7 @@ mod tests {
         let mut search_engine = SearchEngine::new(1.5, 0.75);
         search_engine.index("https://example.com", "This is an example document");
 
-        let app_state = web::Data::new(AppStateWithSearchEngine {
-            search_engine: Mutex::new(search_engine.clone()),
-        });
+        let app_state = web::Data::new(AppStateWithSearchEngine::new(search_engine.clone()));
 
         let app = test::init_service(
             App::new()
@@ -91,13 +161,11 @@ mod tests {
     }
 
     #[actix_web::test]
-    async fn test_search_without_query() {
+    async fn test_search_response_envelope() {
         let mut search_engine = SearchEngine::new(1.5, 0.75);
-        search_engine.index("https://example.com", "This is an example document");
+        search_engine.index("https://envelope.example.com", "Envelope envelope document");
 
-        let app_state = web::Data::new(AppStateWithSearchEngine {
-            search_engine: Mutex::new(search_engine.clone()),
-        });
+        let app_state = web::Data::new(AppStateWithSearchEngine::new(search_engine.clone()));
 
         let app = test::init_service(
             App::new()
@@ -106,8 +174,96 @@ mod tests {
         )
         .await;
 
-        let req = test::TestRequest::get().uri("/search?query=").to_request();
+        let req = test::TestRequest::get()
+            .uri("/search?query=envelope&matched_terms=true")
+            .to_request();
+
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.total, 1);
+        assert_eq!(body.hits[0].url, "https://envelope.example.com");
+        assert_eq!(body.max_score, Some(body.hits[0].score));
+        assert_eq!(
+            body.hits[0].fields.get("content").unwrap(),
+            "Envelope envelope document"
+        );
+        assert_eq!(
+            body.hits[0].matched_terms,
+            Some(vec!["envelope".to_string()])
+        );
+
+        remove_index_from_disk();
+    }
+
+    #[actix_web::test]
+    async fn test_search_with_filter() {
+        let search_engine = SearchEngine::new(1.5, 0.75);
+
+        let app_state = web::Data::new(AppStateWithSearchEngine::new(search_engine.clone()));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .route("/search", web::get().to(search::search))
+                .route(
+                    "/search/index/document",
+                    web::post().to(search::add_document_to_index),
+                ),
+        )
+        .await;
+
+        for (url, year) in [
+            ("https://filtered.example.com/old", 2019),
+            ("https://filtered.example.com/new", 2021),
+        ] {
+            let req = test::TestRequest::post()
+                .uri("/search/index/document")
+                .set_json(serde_json::json!({
+                    "url": url,
+                    "content": "Filtered document",
+                    "fields": {"year": year, "published": "2021-01-01"}
+                }))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert!(resp.status() == 201);
+        }
+
+        let req = test::TestRequest::get()
+            .uri("/search?query=filtered")
+            .to_request();
+        let unfiltered: SearchResponse = test::call_and_read_body_json(&app, req).await;
+
+        let req = test::TestRequest::get()
+            .uri("/search?query=filtered&filter=year%20%3E%3D%202020")
+            .to_request();
+        let filtered: SearchResponse = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(unfiltered.total, 2);
+        assert_eq!(filtered.total, 1);
+        assert_eq!(filtered.hits[0].url, "https://filtered.example.com/new");
+        assert_eq!(filtered.hits[0].score, unfiltered.hits[0].score);
+        assert_eq!(filtered.hits[0].fields["year"], 2021.0);
+        assert_eq!(filtered.hits[0].fields["published"], "2021-01-01");
+
+        // Facets count every match, not only the returned page
+        let req = test::TestRequest::get()
+            .uri("/search?query=filtered&size=1&facets=year:10,published:year")
+            .to_request();
+        let faceted: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(faceted.hits.len(), 1);
+        assert_eq!(faceted.facets["year"].buckets.len(), 2);
+        assert_eq!(faceted.facets["published"].buckets[0].key, "2021-01-01");
+        assert_eq!(faceted.facets["published"].buckets[0].count, 2);
+
+        let req = test::TestRequest::get()
+            .uri("/search?query=filtered&sort=year:desc,score")
+            .to_request();
+        let sorted: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(sorted.hits[0].url, "https://filtered.example.com/new");
+        assert_eq!(sorted.hits[0].sort.as_ref().unwrap()[0], 2021.0);
 
+        let req = test::TestRequest::get()
+            .uri("/search?query=filtered&filter=year")
+            .to_request();
         let resp = test::call_service(&app, req).await;
         assert!(resp.status() == 400);
 
@@ -115,26 +271,1149 @@ mod tests {
     }
 
     #[actix_web::test]
-    async fn test_debug_index() {
+    async fn test_search_after_cursor() {
         let search_engine = SearchEngine::new(1.5, 0.75);
 
-        let app_state = web::Data::new(AppStateWithSearchEngine {
-            search_engine: Mutex::new(search_engine.clone()),
-        });
+        let app_state = web::Data::new(AppStateWithSearchEngine::new(search_engine.clone()));
 
         let app = test::init_service(
             App::new()
                 .app_data(app_state.clone())
-                .route("/search/debug", web::get().to(search::debug_index)),
+                .route("/search", web::get().to(search::search))
+                .route(
+                    "/search/index/document",
+                    web::post().to(search::add_document_to_index),
+                ),
         )
         .await;
 
-        let req = test::TestRequest::get().uri("/search/debug").to_request();
+        for rank in [5, 1, 3, 4, 2] {
+            let req = test::TestRequest::post()
+                .uri("/search/index/document")
+                .set_json(serde_json::json!({
+                    "url": format!("https://cursor.example.com/{}", rank),
+                    "content": "Paginated document",
+                    "fields": {"rank": rank}
+                }))
+                .to_request();
+            test::call_service(&app, req).await;
+        }
+
+        let mut urls = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let uri = match &cursor {
+                Some(cursor) => format!(
+                    "/search?query=paginated&sort=rank&size=2&search_after={}",
+                    cursor
+                ),
+                None => "/search?query=paginated&sort=rank&size=2".to_string(),
+            };
+            let req = test::TestRequest::get().uri(&uri).to_request();
+            let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+            urls.extend(body.hits.into_iter().map(|hit| hit.url));
+
+            // Documents indexed before the cursor do not shift the following pages
+            if cursor.is_none() {
+                let req = test::TestRequest::post()
+                    .uri("/search/index/document")
+                    .set_json(serde_json::json!({
+                        "url": "https://cursor.example.com/0",
+                        "content": "Paginated document",
+                        "fields": {"rank": 0}
+                    }))
+                    .to_request();
+                test::call_service(&app, req).await;
+            }
+
+            match body.next_cursor {
+                Some(next_cursor) => cursor = Some(next_cursor),
+                None => break,
+            }
+        }
+        let expected: Vec<String> = (1..=5)
+            .map(|rank| format!("https://cursor.example.com/{}", rank))
+            .collect();
+        assert_eq!(urls, expected);
+
+        let req = test::TestRequest::get()
+            .uri("/search?query=paginated&search_after=zz")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 400);
+
+        remove_index_from_disk();
+    }
+
+    #[actix_web::test]
+    async fn test_search_point_in_time() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.index("https://pit.example.com/a", "Frozen document");
+
+        let app_state = web::Data::new(AppStateWithSearchEngine::new(search_engine.clone()));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .route("/search", web::get().to(search::search))
+                .route("/search/_pit", web::post().to(search::open_point_in_time))
+                .route(
+                    "/search/_pit",
+                    web::delete().to(search::close_point_in_time),
+                )
+                .route(
+                    "/search/index/document",
+                    web::post().to(search::add_document_to_index),
+                ),
+        )
+        .await;
 
+        let req = test::TestRequest::post()
+            .uri("/search/_pit?keep_alive=1m")
+            .to_request();
+        let pit: PointInTimeResponse = test::call_and_read_body_json(&app, req).await;
+
+        let req = test::TestRequest::post()
+            .uri("/search/index/document")
+            .set_json(serde_json::json!({"url": "https://pit.example.com/b", "content": "Frozen document"}))
+            .to_request();
+        test::call_service(&app, req).await;
+
+        // The point in time does not see the document indexed after it was opened
+        let req = test::TestRequest::get()
+            .uri(&format!("/search?query=frozen&pit={}", pit.id))
+            .to_request();
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.total, 1);
+        assert_eq!(body.pit_id, Some(pit.id.clone()));
+
+        let req = test::TestRequest::get()
+            .uri("/search?query=frozen")
+            .to_request();
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.total, 2);
+
+        let req = test::TestRequest::delete()
+            .uri("/search/_pit")
+            .set_json(serde_json::json!({"id": pit.id}))
+            .to_request();
         let resp = test::call_service(&app, req).await;
         assert!(resp.status() == 200);
 
+        let req = test::TestRequest::get()
+            .uri(&format!("/search?query=frozen&pit={}", pit.id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 404);
+
         remove_index_from_disk();
     }
-}
 
+    #[actix_web::test]
+    async fn test_knn_search() {
+        let search_engine = SearchEngine::new(1.5, 0.75);
+
+        let app_state = web::Data::new(AppStateWithSearchEngine::new(search_engine.clone()));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .route(
+                    "/search/vectors/{field}",
+                    web::put().to(search::create_vector_field),
+                )
+                .route("/search/knn", web::post().to(search::knn))
+                .route(
+                    "/search/index/document",
+                    web::post().to(search::add_document_to_index),
+                ),
+        )
+        .await;
+
+        let req = test::TestRequest::put()
+            .uri("/search/vectors/handler_embedding")
+            .set_json(serde_json::json!({"dimension": 2, "metric": "cosine"}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 200);
+
+        for (url, vector) in [
+            ("https://knn.example.com/east", [1.0, 0.1]),
+            ("https://knn.example.com/north", [0.1, 1.0]),
+        ] {
+            let req = test::TestRequest::post()
+                .uri("/search/index/document")
+                .set_json(serde_json::json!({"url": url, "content": "Embedded", "vectors": {"handler_embedding": vector}}))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert!(resp.status() == 201);
+        }
+
+        let req = test::TestRequest::post()
+            .uri("/search/knn")
+            .set_json(
+                serde_json::json!({"field": "handler_embedding", "vector": [0.0, 1.0], "k": 1}),
+            )
+            .to_request();
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.hits.len(), 1);
+        assert_eq!(body.hits[0].url, "https://knn.example.com/north");
+        assert_eq!(body.hits[0].fields["content"], "Embedded");
+
+        // Vectors must match the dimension of the field
+        let req = test::TestRequest::post()
+            .uri("/search/index/document")
+            .set_json(serde_json::json!({"url": "https://knn.example.com/bad", "content": "Embedded", "vectors": {"handler_embedding": [1.0]}}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 400);
+
+        remove_index_from_disk();
+    }
+
+    #[actix_web::test]
+    async fn test_hybrid_search() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine
+            .create_vector_field("hybrid_embedding", 2, Metric::Cosine)
+            .unwrap();
+        search_engine.index_document(
+            &Document::new("https://hybrid.example.com/both", "Fused fused")
+                .with_vector("hybrid_embedding", vec![1.0, 0.0]),
+        );
+        search_engine.index_document(
+            &Document::new("https://hybrid.example.com/lexical", "Fused")
+                .with_vector("hybrid_embedding", vec![0.0, 1.0]),
+        );
+        search_engine.index_document(
+            &Document::new("https://hybrid.example.com/vector", "Unrelated")
+                .with_vector("hybrid_embedding", vec![0.9, 0.1]),
+        );
+
+        let app_state = web::Data::new(AppStateWithSearchEngine::new(search_engine.clone()));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .route("/search/hybrid", web::post().to(search::hybrid_search)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/search/hybrid")
+            .set_json(serde_json::json!({"query": "fused", "knn": {"field": "hybrid_embedding", "vector": [1.0, 0.0]}, "explain": true}))
+            .to_request();
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.total, 3);
+        assert_eq!(body.hits[0].url, "https://hybrid.example.com/both");
+        let explanation = body.hits[0].explanation.as_ref().unwrap();
+        assert_eq!(explanation.details.len(), 2);
+        assert!((explanation.value - body.hits[0].score).abs() < 1e-9);
+
+        // Explanations are only returned on request
+        let req = test::TestRequest::post()
+            .uri("/search/hybrid")
+            .set_json(serde_json::json!({"query": "fused", "knn": {"field": "hybrid_embedding", "vector": [1.0, 0.0]}, "fusion": {"method": "linear", "lexical_weight": 0.0, "vector_weight": 1.0}}))
+            .to_request();
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(body.hits[0].explanation.is_none());
+        assert_eq!(body.hits[0].score, 1.0);
+
+        let req = test::TestRequest::post()
+            .uri("/search/hybrid")
+            .set_json(serde_json::json!({"query": "fused", "knn": {"field": "unknown", "vector": [1.0, 0.0]}}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 400);
+
+        // Rank constants that would give infinite or negative scores are rejected
+        for fusion in [
+            serde_json::json!({"method": "rrf", "rank_constant": -1.0}),
+            serde_json::json!({"method": "rrf", "rank_constant": 0.0}),
+            serde_json::json!({"method": "linear", "lexical_weight": -0.5}),
+        ] {
+            let req = test::TestRequest::post()
+                .uri("/search/hybrid")
+                .set_json(serde_json::json!({"query": "fused", "knn": {"field": "hybrid_embedding", "vector": [1.0, 0.0]}, "fusion": fusion}))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert!(resp.status() == 400);
+            let body: ErrorResponse = test::read_body_json(resp).await;
+            assert!(body.error.starts_with("Invalid fusion"));
+        }
+
+        remove_index_from_disk();
+    }
+
+    #[actix_web::test]
+    async fn test_explain() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.index(
+            "https://explainhandler.example.com/a",
+            "Explainable explainable ranking",
+        );
+        search_engine.index("https://explainhandler.example.com/b", "Explainable");
+
+        let app_state = web::Data::new(AppStateWithSearchEngine::new(search_engine.clone()));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .route("/search/explain", web::get().to(search::explain))
+                .route("/search", web::get().to(search::search)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/search?query=explainable&explain=true")
+            .to_request();
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.hits.len(), 2);
+        for hit in &body.hits {
+            assert_eq!(hit.explanation.as_ref().unwrap().value, hit.score);
+        }
+
+        let req = test::TestRequest::get()
+            .uri("/search/explain?query=explainable&url=https://explainhandler.example.com/a")
+            .to_request();
+        let body: ExplainResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(body.matched);
+        let explanation = body.explanation.unwrap();
+        assert!(explanation.details[0]
+            .description
+            .starts_with("weight of 'explainable'"));
+
+        let req = test::TestRequest::get()
+            .uri("/search/explain?query=ranking&url=https://explainhandler.example.com/b")
+            .to_request();
+        let body: ExplainResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(!body.matched);
+        assert!(body.explanation.is_none());
+
+        let req = test::TestRequest::get()
+            .uri("/search/explain?query=ranking&url=https://explainhandler.example.com/missing")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 404);
+
+        remove_index_from_disk();
+    }
+
+    #[actix_web::test]
+    async fn test_similar() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.index(
+            "https://similarhandler.example.com/source",
+            "Relatedterm otherterm",
+        );
+        search_engine.index("https://similarhandler.example.com/related", "Relatedterm");
+        search_engine.index("https://similarhandler.example.com/other", "Otherterm");
+
+        let app_state = web::Data::new(AppStateWithSearchEngine::new(search_engine.clone()));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .route("/search/similar", web::get().to(search::similar)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/search/similar?url=https://similarhandler.example.com/source&size=1")
+            .to_request();
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.total, 2);
+        assert_eq!(body.hits.len(), 1);
+        assert!(body.hits[0].url != "https://similarhandler.example.com/source");
+
+        let req = test::TestRequest::get()
+            .uri("/search/similar?url=https://similarhandler.example.com/missing")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 404);
+
+        remove_index_from_disk();
+    }
+
+    #[actix_web::test]
+    async fn test_collapse_duplicates() {
+        let search_engine = SearchEngine::new(1.5, 0.75);
+
+        let app_state = web::Data::new(AppStateWithSearchEngine::new(search_engine.clone()));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .route(
+                    "/search/index/document",
+                    web::post().to(search::add_document_to_index),
+                )
+                .route("/search", web::get().to(search::search)),
+        )
+        .await;
+
+        let content = "Collapsible copies of the same page are served from several mirrors";
+        let req = test::TestRequest::post()
+            .uri("/search/index/document")
+            .set_json(
+                serde_json::json!({"url": "https://collapse.example.com/a", "content": content}),
+            )
+            .to_request();
+        let body: IndexDocumentResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(body.duplicates.is_empty());
+
+        let req = test::TestRequest::post()
+            .uri("/search/index/document")
+            .set_json(
+                serde_json::json!({"url": "https://collapse.example.com/b", "content": content}),
+            )
+            .to_request();
+        let body: IndexDocumentResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.duplicates, vec!["https://collapse.example.com/a"]);
+
+        let req = test::TestRequest::get()
+            .uri("/search?query=collapsible")
+            .to_request();
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.total, 2);
+
+        let req = test::TestRequest::get()
+            .uri("/search?query=collapsible&collapse_duplicates=true")
+            .to_request();
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.total, 1);
+        assert_eq!(body.hits[0].url, "https://collapse.example.com/a");
+
+        remove_index_from_disk();
+    }
+
+    #[actix_web::test]
+    async fn test_search_group_by() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.index(
+            "https://groupby-a.example.com/1",
+            "Groupable groupable groupable",
+        );
+        search_engine.index("https://groupby-a.example.com/2", "Groupable groupable");
+        search_engine.index("https://groupby-a.example.com/3", "Groupable");
+        search_engine.index("https://groupby-b.example.com/1", "Groupable text");
+
+        let app_state = web::Data::new(AppStateWithSearchEngine::new(search_engine.clone()));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .route("/search", web::get().to(search::search)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/search?query=groupable&group_by=_domain&group_size=2")
+            .to_request();
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.total, 4);
+        assert_eq!(body.total_groups, Some(2));
+        let groups = body.groups.unwrap();
+        assert_eq!(groups[0].key.as_deref(), Some("groupby-a.example.com"));
+        assert_eq!(groups[0].count, 3);
+        assert_eq!(groups[0].hits.len(), 2);
+        assert_eq!(groups[1].count, 1);
+
+        let req = test::TestRequest::get()
+            .uri("/search?query=groupable&group_by=_domain&search_after=00")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 400);
+
+        remove_index_from_disk();
+    }
+
+    #[actix_web::test]
+    async fn test_search_with_function_score() {
+        let search_engine = SearchEngine::new(1.5, 0.75);
+
+        let app_state = web::Data::new(AppStateWithSearchEngine::new(search_engine.clone()));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .route(
+                    "/search/index/document",
+                    web::post().to(search::add_document_to_index),
+                )
+                .route("/search", web::post().to(search::search_with_body)),
+        )
+        .await;
+
+        for (url, content, boost) in [
+            (
+                "https://functionscore.example.com/a",
+                "Boostable boostable",
+                1.0,
+            ),
+            ("https://functionscore.example.com/b", "Boostable", 10.0),
+        ] {
+            let req = test::TestRequest::post()
+                .uri("/search/index/document")
+                .set_json(serde_json::json!({"url": url, "content": content, "boost": boost}))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert!(resp.status() == 201);
+        }
+
+        let req = test::TestRequest::post()
+            .uri("/search")
+            .set_json(serde_json::json!({"query": "boostable", "explain": true, "function_score": {"functions": ["document_boost"]}}))
+            .to_request();
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.hits[0].url, "https://functionscore.example.com/b");
+        assert_eq!(
+            body.hits[0].explanation.as_ref().unwrap().value,
+            body.hits[0].score
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/search")
+            .set_json(serde_json::json!({"query": "boostable", "function_score": {"functions": [{"decay": {"field": "published", "function": "gauss", "scale": "0d"}}]}}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 400);
+
+        let req = test::TestRequest::post()
+            .uri("/search/index/document")
+            .set_json(serde_json::json!({"url": "https://functionscore.example.com/c", "content": "Boostable", "boost": -1.0}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 400);
+
+        remove_index_from_disk();
+    }
+
+    #[actix_web::test]
+    async fn test_query_rules() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.index_document(
+            &Document::new(
+                "https://curated.example.com/best",
+                "Curated curated curated",
+            )
+            .with_field("lang", FieldValue::Keyword("en".to_string())),
+        );
+        search_engine.index("https://curated.example.com/spam", "Curated curated");
+        search_engine.index("https://curated.example.com/editorial", "Editorial pick");
+
+        let app_state = web::Data::new(AppStateWithSearchEngine::new(search_engine.clone()));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .route("/search/rules", web::get().to(search::get_query_rules))
+                .route("/search/rules/{id}", web::get().to(search::get_query_rule))
+                .route("/search/rules/{id}", web::put().to(search::put_query_rule))
+                .route(
+                    "/search/rules/{id}",
+                    web::delete().to(search::delete_query_rule),
+                )
+                .route("/search", web::get().to(search::search)),
+        )
+        .await;
+
+        let rule = serde_json::json!({
+            "match": "prefix",
+            "query": "curated",
+            "actions": [
+                {"pin": {"urls": ["https://curated.example.com/editorial"]}},
+                {"hide": {"urls": ["https://curated.example.com/spam"]}},
+                {"boost": {"urls": ["https://curated.example.com/best"], "factor": 2.0}},
+                {"redirect": {"url": "https://curated.example.com/landing"}}
+            ]
+        });
+        let req = test::TestRequest::put()
+            .uri("/search/rules/curation")
+            .set_json(&rule)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 201);
+
+        let req = test::TestRequest::get()
+            .uri("/search/rules/curation")
+            .to_request();
+        let body: QueryRuleResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.rule.query, "curated");
+
+        let req = test::TestRequest::get()
+            .uri("/search?query=curated%20content")
+            .to_request();
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        let urls: Vec<&str> = body.hits.iter().map(|hit| hit.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://curated.example.com/editorial",
+                "https://curated.example.com/best"
+            ]
+        );
+        assert_eq!(
+            body.redirect.as_deref(),
+            Some("https://curated.example.com/landing")
+        );
+
+        // Rules apply after filters, so the pinned document stays, and boosts are explained
+        let req = test::TestRequest::get()
+            .uri("/search?query=curated&filter=lang%20%3D%20en&explain=true")
+            .to_request();
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.hits.len(), 2);
+        assert_eq!(body.hits[0].url, "https://curated.example.com/editorial");
+        assert_eq!(body.hits[0].score, 0.0);
+        let explanation = body.hits[1].explanation.as_ref().unwrap();
+        assert_eq!(explanation.description, "query rule boost, score * factor:");
+        assert_eq!(explanation.value, body.hits[1].score);
+        assert_eq!(explanation.details[0].value * 2.0, body.hits[1].score);
+
+        let req = test::TestRequest::put()
+            .uri("/search/rules/invalid")
+            .set_json(serde_json::json!({"match": "exact", "query": "curated", "actions": []}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 400);
+
+        let req = test::TestRequest::delete()
+            .uri("/search/rules/curation")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 200);
+        let req = test::TestRequest::get().uri("/search/rules").to_request();
+        let body: QueryRulesResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(!body.rules.contains_key("curation"));
+
+        let req = test::TestRequest::get()
+            .uri("/search?query=curated")
+            .to_request();
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.total, 2);
+        assert!(body.redirect.is_none());
+
+        remove_index_from_disk();
+    }
+
+    #[actix_web::test]
+    async fn test_search_with_rescore() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.index(
+            "https://rescorehandler.example.com/apart",
+            "Rescored rescored words between rescoring",
+        );
+        search_engine.index(
+            "https://rescorehandler.example.com/close",
+            "Rescored rescoring",
+        );
+
+        let app_state = web::Data::new(AppStateWithSearchEngine::new(search_engine.clone()));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .route("/search", web::post().to(search::search_with_body)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/search")
+            .set_json(serde_json::json!({"query": "rescored rescoring", "explain": true, "rescore": {"window_size": 10, "scorers": [{"proximity": {"boost": 10.0}}]}}))
+            .to_request();
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.hits[0].url, "https://rescorehandler.example.com/close");
+        assert_eq!(body.max_score, Some(body.hits[0].score));
+        for hit in &body.hits {
+            assert_eq!(hit.explanation.as_ref().unwrap().value, hit.score);
+        }
+
+        let req = test::TestRequest::post()
+            .uri("/search")
+            .set_json(serde_json::json!({"query": "rescored", "sort": "score", "rescore": {"scorers": []}}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 400);
+
+        remove_index_from_disk();
+    }
+
+    #[actix_web::test]
+    async fn test_search_with_script_score() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.index_document(
+            &Document::new(
+                "https://scripthandler.example.com/relevant",
+                "Scripthandler scripthandler",
+            )
+            .with_field("views", FieldValue::Number(1.0)),
+        );
+        search_engine.index_document(
+            &Document::new("https://scripthandler.example.com/viewed", "Scripthandler")
+                .with_field("views", FieldValue::Number(999.0)),
+        );
+
+        let app_state = web::Data::new(AppStateWithSearchEngine::new(search_engine.clone()));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .route("/search", web::post().to(search::search_with_body)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/search")
+            .set_json(serde_json::json!({"query": "scripthandler", "explain": true, "script_score": {"source": "_score * log10(1 + doc['views'])", "timeout": "50ms"}}))
+            .to_request();
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.hits[0].url, "https://scripthandler.example.com/viewed");
+        for hit in &body.hits {
+            assert_eq!(hit.explanation.as_ref().unwrap().value, hit.score);
+        }
+
+        let req = test::TestRequest::post()
+            .uri("/search")
+            .set_json(serde_json::json!({"query": "scripthandler", "script_score": {"source": "std::process::exit(1)"}}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 400);
+
+        remove_index_from_disk();
+    }
+
+    #[actix_web::test]
+    async fn test_learning_to_rank() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.index_document(
+            &Document::new(
+                "https://ltrhandler.example.com/obscure",
+                "Ltrhandler ltrhandler",
+            )
+            .with_field("views", FieldValue::Number(2.0)),
+        );
+        search_engine.index_document(
+            &Document::new("https://ltrhandler.example.com/popular", "Ltrhandler")
+                .with_field("views", FieldValue::Number(500.0)),
+        );
+
+        let app_state = web::Data::new(AppStateWithSearchEngine::new(search_engine.clone()));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .route(
+                    "/search/models/{name}",
+                    web::get().to(search::get_ranking_model),
+                )
+                .route(
+                    "/search/models/{name}",
+                    web::put().to(search::put_ranking_model),
+                )
+                .route("/search", web::get().to(search::search))
+                .route("/search", web::post().to(search::search_with_body)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/search?query=ltrhandler&log_features=score,popularity:views")
+            .to_request();
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.hits[0].url, "https://ltrhandler.example.com/obscure");
+        let features = body.hits[0].features.as_ref().unwrap();
+        assert_eq!(features["score"], body.hits[0].score);
+        assert_eq!(features["popularity:views"], 2.0);
+
+        let model = serde_json::json!({"trees": {"trees": [{"split": {
+            "feature": "popularity:views",
+            "threshold": 100.0,
+            "left": {"leaf": 0.0},
+            "right": {"leaf": 5.0}
+        }}]}});
+        let req = test::TestRequest::put()
+            .uri("/search/models/popularity")
+            .set_json(&model)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 201);
+        let req = test::TestRequest::get()
+            .uri("/search/models/popularity")
+            .to_request();
+        let body: RankingModelResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.model.features().len(), 1);
+
+        let req = test::TestRequest::post()
+            .uri("/search")
+            .set_json(serde_json::json!({"query": "ltrhandler", "log_features": "score", "rescore": {"scorers": [{"model": {"name": "popularity"}}]}}))
+            .to_request();
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.hits[0].url, "https://ltrhandler.example.com/popular");
+        // The features are logged with the scores of the first pass
+        let features = body.hits[0].features.as_ref().unwrap();
+        assert_eq!(body.hits[0].score, features["score"] + 5.0);
+
+        let req = test::TestRequest::post()
+            .uri("/search")
+            .set_json(serde_json::json!({"query": "ltrhandler", "rescore": {"scorers": [{"model": {"name": "missing"}}]}}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 400);
+
+        let req = test::TestRequest::get()
+            .uri("/search?query=ltrhandler&log_features=pagerank")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 400);
+
+        let req = test::TestRequest::put()
+            .uri("/search/models/invalid")
+            .set_json(serde_json::json!({"linear": {"weights": {}}}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 400);
+
+        remove_index_from_disk();
+    }
+
+    #[actix_web::test]
+    async fn test_search_with_highlight() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.index(
+            "https://highlight.example.com",
+            "Speicherverwaltung ohne Garbage Collection",
+        );
+
+        let app_state = web::Data::new(AppStateWithSearchEngine::new(search_engine.clone()));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .route("/search", web::get().to(search::search)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/search?query=garbage&highlight=true&pre_tag=%3Cb%3E&post_tag=%3C/b%3E")
+            .to_request();
+
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(
+            body.hits[0].highlight,
+            Some(vec![
+                "Speicherverwaltung ohne <b>Garbage</b> Collection".to_string()
+            ])
+        );
+
+        remove_index_from_disk();
+    }
+
+    #[actix_web::test]
+    async fn test_search_without_query() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.index("https://example.com", "This is an example document");
+
+        let app_state = web::Data::new(AppStateWithSearchEngine::new(search_engine.clone()));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .route("/search", web::get().to(search::search)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/search?query=").to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 400);
+
+        let body: ErrorResponse = test::read_body_json(resp).await;
+        assert_eq!(body.error, "Query is empty");
+
+        remove_index_from_disk();
+    }
+
+    #[actix_web::test]
+    async fn test_suggest() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.index("https://suggest.example.com", "Suggestable suggestion");
+
+        let app_state = web::Data::new(AppStateWithSearchEngine::new(search_engine.clone()));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .route("/search/suggest", web::get().to(search::suggest)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/search/suggest?prefix=suggesta")
+            .to_request();
+
+        let body: SuggestResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.suggestions[0].text, "suggestable");
+
+        let req = test::TestRequest::get()
+            .uri("/search/suggest?prefix=")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 400);
+
+        remove_index_from_disk();
+    }
+
+    #[actix_web::test]
+    async fn test_did_you_mean() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.index("https://spell.example.com", "Garbagecollection");
+
+        let app_state = web::Data::new(AppStateWithSearchEngine::new(search_engine.clone()));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .route("/search", web::get().to(search::search))
+                .route("/search/spell", web::get().to(search::spell)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/search?query=garbagecolection")
+            .to_request();
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.total, 0);
+        assert_eq!(body.suggestion, Some("garbagecollection".to_string()));
+
+        let req = test::TestRequest::get()
+            .uri("/search/spell?query=garbagecolection")
+            .to_request();
+        let body: SpellResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.suggestion, Some("garbagecollection".to_string()));
+
+        remove_index_from_disk();
+    }
+
+    #[actix_web::test]
+    async fn test_debug_index() {
+        let search_engine = SearchEngine::new(1.5, 0.75);
+
+        let app_state = web::Data::new(AppStateWithSearchEngine::new(search_engine.clone()));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .route("/search/debug", web::get().to(search::debug_index)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/search/debug").to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 200);
+
+        remove_index_from_disk();
+    }
+
+    #[actix_web::test]
+    async fn test_percolator() {
+        let search_engine = SearchEngine::new(1.5, 0.75);
+        let (sender, notified) = mpsc::channel();
+
+        let app_state = web::Data::new(
+            AppStateWithSearchEngine::new(search_engine.clone()).with_percolator_hook(
+                move |url, ids| {
+                    sender.send((url.to_string(), ids.to_vec())).unwrap();
+                },
+            ),
+        );
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .route(
+                    "/search/percolator",
+                    web::get().to(search::get_percolator_queries),
+                )
+                .route(
+                    "/search/percolator/{id}",
+                    web::put().to(search::put_percolator_query),
+                )
+                .route(
+                    "/search/percolator/{id}",
+                    web::delete().to(search::delete_percolator_query),
+                )
+                .route(
+                    "/search/index/document",
+                    web::post().to(search::add_document_to_index),
+                ),
+        )
+        .await;
+
+        let req = test::TestRequest::put()
+            .uri("/search/percolator/alerts")
+            .set_json(serde_json::json!({"query": "percolhandler"}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 201);
+        let req = test::TestRequest::put()
+            .uri("/search/percolator/invalid")
+            .set_json(serde_json::json!({"query": "/percol[/"}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 400);
+        let req = test::TestRequest::get()
+            .uri("/search/percolator")
+            .to_request();
+        let body: PercolatorQueriesResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.queries.len(), 1);
+
+        let data = search::AddDocumentRequest {
+            url: "https://percolhandler.example.com".to_string(),
+            content: "Percolhandler news".to_string(),
+            ..Default::default()
+        };
+        let req = test::TestRequest::post()
+            .uri("/search/index/document")
+            .set_json(&data)
+            .to_request();
+        let body: IndexDocumentResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.percolator_matches, vec!["alerts".to_string()]);
+        // The hook runs in the background
+        assert_eq!(
+            notified.recv_timeout(Duration::from_secs(5)).unwrap(),
+            (
+                "https://percolhandler.example.com".to_string(),
+                vec!["alerts".to_string()]
+            )
+        );
+
+        let data = search::AddDocumentRequest {
+            url: "https://percolhandler.example.com/other".to_string(),
+            content: "Unrelated news".to_string(),
+            ..Default::default()
+        };
+        let req = test::TestRequest::post()
+            .uri("/search/index/document")
+            .set_json(&data)
+            .to_request();
+        let body: IndexDocumentResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(body.percolator_matches.is_empty());
+        assert!(notified.recv_timeout(Duration::from_millis(100)).is_err());
+
+        let req = test::TestRequest::delete()
+            .uri("/search/percolator/alerts")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 200);
+        let req = test::TestRequest::delete()
+            .uri("/search/percolator/alerts")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 404);
+
+        remove_index_from_disk();
+    }
+
+    #[actix_web::test]
+    async fn test_search_template() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.index_document(
+            &Document::new(
+                "https://templhandler.example.com/book",
+                "Templhandler guide",
+            )
+            .with_field("category", FieldValue::Keyword("books".to_string())),
+        );
+        search_engine.index_document(
+            &Document::new(
+                "https://templhandler.example.com/video",
+                "Templhandler talk",
+            )
+            .with_field("category", FieldValue::Keyword("videos".to_string())),
+        );
+
+        let app_state = web::Data::new(AppStateWithSearchEngine::new(search_engine.clone()));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .route(
+                    "/search/template/{id}",
+                    web::post().to(search::search_with_template),
+                )
+                .route(
+                    "/search/templates",
+                    web::get().to(search::get_search_templates),
+                )
+                .route(
+                    "/search/templates/{id}",
+                    web::put().to(search::put_search_template),
+                )
+                .route(
+                    "/search/templates/{id}",
+                    web::delete().to(search::delete_search_template),
+                ),
+        )
+        .await;
+
+        let template = serde_json::json!({
+            "source": {"query": "{{user_query}}", "filter": "{{category_filter}}", "size": "{{size}}"},
+            "params": {"category_filter": null, "size": 10}
+        });
+        let req = test::TestRequest::put()
+            .uri("/search/templates/catalog")
+            .set_json(&template)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 201);
+        let req = test::TestRequest::get()
+            .uri("/search/templates")
+            .to_request();
+        let body: SearchTemplatesResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(body.templates.contains_key("catalog"));
+
+        let req = test::TestRequest::post()
+            .uri("/search/template/catalog")
+            .set_json(serde_json::json!({"params": {"user_query": "templhandler"}}))
+            .to_request();
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.total, 2);
+
+        let req = test::TestRequest::post()
+            .uri("/search/template/catalog")
+            .set_json(serde_json::json!({"params": {"user_query": "templhandler", "category_filter": "category = videos", "size": 1}}))
+            .to_request();
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.total, 1);
+        assert_eq!(body.hits[0].url, "https://templhandler.example.com/video");
+
+        // A missing parameter or a rendered body of the wrong shape is rejected
+        let req = test::TestRequest::post()
+            .uri("/search/template/catalog")
+            .set_json(serde_json::json!({"params": {}}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 400);
+        let req = test::TestRequest::post()
+            .uri("/search/template/catalog")
+            .set_json(serde_json::json!({"params": {"user_query": "templhandler", "size": "many"}}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 400);
+
+        let req = test::TestRequest::delete()
+            .uri("/search/templates/catalog")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 200);
+        let req = test::TestRequest::post()
+            .uri("/search/template/catalog")
+            .set_json(serde_json::json!({"params": {"user_query": "templhandler"}}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status() == 404);
+
+        remove_index_from_disk();
+    }
+}
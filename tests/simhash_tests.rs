@@ -0,0 +1,69 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rustysearch::search::{
+        engine::{remove_index_from_disk, SearchEngine},
+        simhash::{is_near_duplicate, simhash},
+    };
+
+    const ARTICLE: &str = "dupcrate the borrow checker enforces that every value has exactly one \
+        owner and that references never outlive the data they point to which rules out use after \
+        free bugs and data races at compile time without a garbage collector while still allowing \
+        shared and mutable borrows in clearly separated scopes across the whole program";
+
+    #[test]
+    fn test_simhash() {
+        let signature = simhash(ARTICLE).unwrap();
+        assert_eq!(simhash(&ARTICLE.to_uppercase()), Some(signature));
+
+        let syndicated = format!("{} syndicated", ARTICLE);
+        assert!(is_near_duplicate(signature, simhash(&syndicated).unwrap()));
+
+        let unrelated = "dupcrate a recipe for bread needs flour water salt and yeast which are \
+            kneaded into a dough that rests overnight before it is shaped and baked in a very hot \
+            oven until the crust turns golden brown and sounds hollow when tapped";
+        assert!(!is_near_duplicate(signature, simhash(unrelated).unwrap()));
+
+        assert_eq!(simhash("  "), None);
+    }
+
+    #[test]
+    fn test_near_duplicates() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.index("https://dup.example.com/original", ARTICLE);
+        search_engine.index("https://dup.example.com/mirror", ARTICLE);
+        search_engine.index(
+            "https://dup.example.com/syndicated",
+            &format!("{} syndicated", ARTICLE),
+        );
+        search_engine.index("https://dup.example.com/other", "dupcrate something else");
+
+        assert_eq!(
+            search_engine.near_duplicates("https://dup.example.com/original"),
+            vec![
+                "https://dup.example.com/mirror",
+                "https://dup.example.com/syndicated"
+            ]
+        );
+        assert!(search_engine
+            .near_duplicates("https://dup.example.com/other")
+            .is_empty());
+
+        // Only the best hit of each cluster survives collapsing
+        let mut results: HashMap<String, f64> = search_engine.search("dupcrate").unwrap();
+        results.insert("https://dup.example.com/syndicated".to_string(), 10.0);
+        search_engine.collapse_duplicates(&mut results);
+        let mut urls: Vec<&String> = results.keys().collect();
+        urls.sort();
+        assert_eq!(
+            urls,
+            vec![
+                "https://dup.example.com/other",
+                "https://dup.example.com/syndicated"
+            ]
+        );
+
+        remove_index_from_disk();
+    }
+}
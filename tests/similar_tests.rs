@@ -0,0 +1,66 @@
+#[cfg(test)]
+mod tests {
+    use rustysearch::search::engine::{remove_index_from_disk, SearchEngine};
+
+    #[test]
+    fn test_interesting_terms() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.bulk_index(vec![
+            (
+                "https://similar.example.com/source",
+                "mltborrow mltborrow mltchecker mltcommon mltunique",
+            ),
+            ("https://similar.example.com/a", "mltborrow mltcommon"),
+            ("https://similar.example.com/b", "mltchecker mltcommon"),
+            ("https://similar.example.com/c", "mltcommon"),
+        ]);
+
+        let terms = search_engine
+            .interesting_terms("https://similar.example.com/source", 25)
+            .unwrap();
+        let names: Vec<&str> = terms.iter().map(|(term, _)| term.as_str()).collect();
+        // Terms only the source contains cannot find anything and are skipped
+        assert_eq!(names, vec!["mltborrow", "mltchecker", "mltcommon"]);
+        assert_eq!(terms[0].1, 1.0);
+        assert!(terms[1].1 < 1.0 && terms[2].1 < terms[1].1);
+
+        let terms = search_engine
+            .interesting_terms("https://similar.example.com/source", 1)
+            .unwrap();
+        assert_eq!(terms.len(), 1);
+        assert!(search_engine
+            .interesting_terms("https://similar.example.com/missing", 25)
+            .is_none());
+
+        remove_index_from_disk();
+    }
+
+    #[test]
+    fn test_similar() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.bulk_index(vec![
+            (
+                "https://similar.example.com/rust",
+                "simownership simborrowing simlifetimes simcompiler",
+            ),
+            (
+                "https://similar.example.com/close",
+                "simownership simborrowing simlifetimes",
+            ),
+            ("https://similar.example.com/far", "simcompiler simgarbage"),
+            ("https://similar.example.com/none", "simunrelated"),
+        ]);
+
+        let results = search_engine
+            .similar("https://similar.example.com/rust", 25)
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(!results.contains_key("https://similar.example.com/rust"));
+        assert!(
+            results["https://similar.example.com/close"]
+                > results["https://similar.example.com/far"]
+        );
+
+        remove_index_from_disk();
+    }
+}
@@ -0,0 +1,96 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rustysearch::{
+        search::{
+            cursor::Cursor,
+            engine::{remove_index_from_disk, SearchEngine},
+            query::QueryError,
+            sort::{Missing, SortKey, SortOrder, SortTarget},
+        },
+        types::{
+            document::Document,
+            field::{parse_date, FieldValue},
+        },
+    };
+
+    #[test]
+    fn test_parse_sort() {
+        let keys = SortKey::parse_all("published:desc, score, tag:asc:first").unwrap();
+
+        assert_eq!(keys.len(), 3);
+        assert_eq!(keys[0].target, SortTarget::Field("published".to_string()));
+        assert_eq!(keys[0].order, SortOrder::Desc);
+        assert_eq!(keys[1], SortKey::score());
+        assert_eq!(keys[2].order, SortOrder::Asc);
+        assert_eq!(keys[2].missing, Missing::First);
+        assert_eq!(
+            SortKey::parse("year:up"),
+            Err(QueryError::InvalidSort("year:up".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_sort_results() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        let date = |d: &str| FieldValue::Date(parse_date(d).unwrap());
+        let url = |name: &str| format!("https://sort.example.com/{}", name);
+        search_engine.index_document(
+            &Document::new(&url("a"), "sortable").with_field("published", date("2020-01-01")),
+        );
+        search_engine.index_document(
+            &Document::new(&url("b"), "sortable").with_field("published", date("2022-01-01")),
+        );
+        search_engine.index_document(
+            &Document::new(&url("c"), "sortable").with_field("published", date("2022-01-01")),
+        );
+        search_engine.index(&url("d"), "sortable");
+
+        let results: HashMap<String, f64> = [
+            (url("a"), 3.0),
+            (url("b"), 1.0),
+            (url("c"), 2.0),
+            (url("d"), 4.0),
+        ]
+        .into_iter()
+        .collect();
+        let sort = |text: &str| -> Vec<String> {
+            search_engine
+                .sort_results(results.clone(), &SortKey::parse_all(text).unwrap(), None)
+                .into_iter()
+                .map(|(url, _)| url)
+                .collect()
+        };
+
+        // Ties on the date are broken by the score, missing values come last
+        assert_eq!(
+            sort("published:desc,score"),
+            vec![url("c"), url("b"), url("a"), url("d")]
+        );
+        // Ties on every key are broken by URL
+        assert_eq!(
+            sort("published:desc:first"),
+            vec![url("d"), url("b"), url("c"), url("a")]
+        );
+        assert_eq!(sort("score"), vec![url("d"), url("a"), url("c"), url("b")]);
+
+        // Continue after the second hit through an encoded cursor
+        let keys = SortKey::parse_all("published:desc,score").unwrap();
+        let cursor = Cursor {
+            values: search_engine.sort_values(&url("b"), 1.0, &keys),
+            url: url("b"),
+        };
+        let cursor = Cursor::decode(&cursor.encode(), keys.len()).unwrap();
+        let after: Vec<String> = search_engine
+            .sort_results(results.clone(), &keys, Some(&cursor))
+            .into_iter()
+            .map(|(url, _)| url)
+            .collect();
+        assert_eq!(after, vec![url("a"), url("d")]);
+        assert!(Cursor::decode(&cursor.encode(), 1).is_err());
+        assert!(Cursor::decode("not a cursor", 2).is_err());
+
+        remove_index_from_disk();
+    }
+}
@@ -0,0 +1,88 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use rustysearch::search::{
+        engine::{remove_index_from_disk, SearchEngine},
+        query::Query,
+        query_log::{QueryLog, MAX_QUERY_LOG_TERMS},
+    };
+
+    #[test]
+    fn test_suggest_correction() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.bulk_index(vec![
+            (
+                "https://spelling.example.com/a",
+                "Speicherverwaltung jkspeicher",
+            ),
+            (
+                "https://spelling.example.com/b",
+                "Speicherverwaltung jkspeichern",
+            ),
+            ("https://spelling.example.com/c", "jkspeichern"),
+        ]);
+
+        assert_eq!(
+            search_engine.suggest_correction("Speicherverwaltng rust", None),
+            Some("speicherverwaltung rust".to_string())
+        );
+        // Both candidates are one edit away, the more frequent one wins
+        assert_eq!(
+            search_engine.suggest_correction("jkspeichen", None),
+            Some("jkspeichern".to_string())
+        );
+        assert_eq!(search_engine.suggest_correction("jkspeichern", None), None);
+
+        remove_index_from_disk();
+    }
+
+    #[test]
+    fn test_suggest_correction_with_query_log() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.bulk_index(vec![
+            ("https://spelling.example.com/d", "jklogged jkloggen"),
+            ("https://spelling.example.com/e", "jkloggen"),
+        ]);
+        let query_log = QueryLog::default();
+        for _ in 0..3 {
+            query_log.record(&Query::parse("jklogged").unwrap());
+        }
+
+        assert_eq!(
+            search_engine.suggest_correction("jkloggeb", None),
+            Some("jkloggen".to_string())
+        );
+        assert_eq!(
+            search_engine.suggest_correction("jkloggeb", Some(&query_log.counts())),
+            Some("jklogged".to_string())
+        );
+
+        remove_index_from_disk();
+    }
+
+    #[test]
+    fn test_query_log_is_bounded_and_persisted() {
+        let path = "/tmp/rustysearch-spelling-tests.querylog";
+        let _ = fs::remove_file(path);
+
+        let query_log = QueryLog::open(path).unwrap();
+        for _ in 0..2 {
+            query_log.record(&Query::parse("jkfrequent").unwrap());
+        }
+        for i in 0..MAX_QUERY_LOG_TERMS {
+            query_log.record(&Query::parse(&format!("jkrare{}", i)).unwrap());
+        }
+        // The least frequent terms are evicted first
+        assert!(query_log.counts().len() <= MAX_QUERY_LOG_TERMS);
+        assert_eq!(query_log.counts()["jkfrequent"], 2);
+
+        query_log.flush();
+        let reopened = QueryLog::open(path).unwrap();
+        assert_eq!(*reopened.counts(), *query_log.counts());
+
+        fs::write(path, b"not a query log").unwrap();
+        assert!(QueryLog::open(path).is_err());
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,120 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::time::{Duration, Instant};
+
+    use rustysearch::{
+        search::{
+            engine::{remove_index_from_disk, SearchEngine},
+            suggest::{
+                top_completions, Completions, Suggestion, SuggestionSource,
+                MAX_PRECOMPUTED_COMPLETIONS,
+            },
+        },
+        types::document::Document,
+    };
+
+    #[test]
+    fn test_suggest_terms_by_document_frequency() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.bulk_index(vec![
+            (
+                "https://suggest.example.com/a",
+                "qwprogramming qwprogrammer",
+            ),
+            ("https://suggest.example.com/b", "qwprogramming"),
+            ("https://suggest.example.com/c", "qwprogramming qwprogress"),
+        ]);
+
+        let suggestions = search_engine.suggest("qwprogr", 2, false);
+        assert_eq!(
+            suggestions,
+            vec![
+                Suggestion {
+                    text: "qwprogramming".to_string(),
+                    weight: 3,
+                    source: SuggestionSource::Term
+                },
+                Suggestion {
+                    text: "qwprogrammer".to_string(),
+                    weight: 1,
+                    source: SuggestionSource::Term
+                },
+            ]
+        );
+
+        let suggestions = search_engine.suggest("Rust qwprogre", 5, false);
+        assert_eq!(suggestions[0].text, "rust qwprogress");
+
+        remove_index_from_disk();
+    }
+
+    #[test]
+    fn test_suggest_titles() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.index_document(
+            &Document::new("https://suggest.example.com/title", "qwtitled document")
+                .with_title("Qwtitled: The Book"),
+        );
+
+        let suggestions = search_engine.suggest("qwtitled th", 5, true);
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].text, "Qwtitled: The Book");
+        assert_eq!(suggestions[0].source, SuggestionSource::Title);
+        assert_eq!(
+            search_engine.title("https://suggest.example.com/title"),
+            Some("Qwtitled: The Book")
+        );
+
+        remove_index_from_disk();
+    }
+
+    #[test]
+    fn test_completions_stay_consistent() {
+        let mut completions = Completions::default();
+        let mut weights = BTreeMap::new();
+        // More keys per short prefix than are precomputed, with changing weights
+        for round in 0..3 {
+            for i in 0..(2 * MAX_PRECOMPUTED_COMPLETIONS) {
+                let key = format!("co{}", i);
+                let weight = (i * 7 + round * 13) % 40;
+                completions.set(&key, weight);
+                if weight == 0 {
+                    weights.remove(&key);
+                } else {
+                    weights.insert(key, weight);
+                }
+            }
+        }
+        completions.set("c", 100);
+        weights.insert("c".to_string(), 100);
+
+        for prefix in ["c", "co", "co1", "co12", "x"] {
+            for size in [
+                1,
+                10,
+                MAX_PRECOMPUTED_COMPLETIONS,
+                2 * MAX_PRECOMPUTED_COMPLETIONS,
+            ] {
+                let expected: Vec<(&str, usize)> = top_completions(&weights, prefix, size, |w| *w)
+                    .into_iter()
+                    .map(|(key, weight)| (key.as_str(), weight))
+                    .collect();
+                assert_eq!(completions.complete(prefix, size), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_completions_latency() {
+        let keys: Vec<String> = (0..100_000).map(|i| format!("latency{}", i)).collect();
+        let completions = Completions::build(keys.iter().map(|key| (key, key.len())));
+
+        let start = Instant::now();
+        for _ in 0..100 {
+            assert_eq!(completions.complete("lat", 10).len(), 10);
+        }
+        // Short prefixes matching the whole dictionary are answered without a scan
+        assert!(start.elapsed() < Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,83 @@
+#[cfg(test)]
+mod tests {
+    use rustysearch::search::{
+        engine::{remove_index_from_disk, SearchEngine},
+        synonyms::{InvalidSynonymRule, SynonymSet, SynonymSpan},
+    };
+
+    fn words(text: &str) -> Vec<String> {
+        text.split_whitespace().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_synonym_file() {
+        let synonyms = SynonymSet::parse(
+            "# Rust\n\nrs, rustlang, Rust Programming Language\nrustc => rust compiler\n",
+        )
+        .unwrap();
+
+        assert_eq!(synonyms.rules().len(), 2);
+        assert_eq!(
+            SynonymSet::parse("rust\nrustc =>"),
+            Err(InvalidSynonymRule {
+                line: 1,
+                rule: "rust".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_synonym_graph() {
+        let synonyms =
+            SynonymSet::parse("rs, rust programming language\nrustc => rust compiler").unwrap();
+
+        let spans = synonyms.graph(&words("learn rust programming language with rustc"));
+        assert_eq!(spans.len(), 4);
+        assert_eq!(
+            spans[1],
+            SynonymSpan {
+                original: words("rust programming language"),
+                alternatives: vec![words("rs"), words("rust programming language")],
+            }
+        );
+        assert_eq!(spans[3].alternatives, vec![words("rust compiler")]);
+    }
+
+    #[test]
+    fn test_search_with_synonyms() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        search_engine.bulk_index(vec![
+            (
+                "https://synonyms.example.com/a",
+                "synrust synprogramming synlanguage guide",
+            ),
+            (
+                "https://synonyms.example.com/b",
+                "synlanguage of synrust and synprogramming",
+            ),
+            ("https://synonyms.example.com/c", "synrs guide"),
+        ]);
+        search_engine.set_synonyms(
+            SynonymSet::parse("synrs, synrustlang, synrust synprogramming synlanguage").unwrap(),
+        );
+
+        // The multi-word synonym only matches as a phrase
+        let results = search_engine.search("synrustlang").unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.contains_key("https://synonyms.example.com/a"));
+        assert!(results.contains_key("https://synonyms.example.com/c"));
+
+        // Phrases are expanded along every path through the synonym graph
+        let results = search_engine.search("\"synrs guide\"").unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.contains_key("https://synonyms.example.com/a"));
+
+        let results = search_engine
+            .search("\"synrust synprogramming synlanguage guide\"")
+            .unwrap();
+        assert!(results.contains_key("https://synonyms.example.com/c"));
+        assert!(!results.contains_key("https://synonyms.example.com/b"));
+
+        remove_index_from_disk();
+    }
+}
@@ -0,0 +1,151 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use rustysearch::search::{
+        engine::{remove_index_from_disk, SearchEngine},
+        filter::Filter,
+        query::{Clause, Query},
+        template::{SearchTemplate, TemplateError},
+    };
+    use serde_json::{json, Value};
+
+    fn search_template(source: Value, params: Value) -> SearchTemplate {
+        serde_json::from_value(json!({"source": source, "params": params})).unwrap()
+    }
+
+    fn params(params: Value) -> BTreeMap<String, Value> {
+        serde_json::from_value(params).unwrap()
+    }
+
+    #[test]
+    fn test_render_template() {
+        let template = search_template(
+            json!({
+                "query": "{{user_query}}",
+                "filter": "category = {{category}}; year >= {{year}}",
+                "size": "{{size}}",
+                "highlight": "{{highlight}}"
+            }),
+            json!({"size": 10, "highlight": null}),
+        );
+        assert!(template.validate().is_ok());
+
+        let rendered = template
+            .render(&params(json!({
+                "user_query": "rust books",
+                "category": "programming",
+                "year": 2020,
+                "size": 5
+            })))
+            .unwrap();
+        // Whole placeholders keep the type of the value and null values remove the field
+        assert_eq!(
+            rendered,
+            json!({
+                "query": "rust books",
+                "filter": "category = \"programming\"; year >= 2020",
+                "size": 5
+            })
+        );
+
+        let rendered = template
+            .render(&params(json!({
+                "user_query": "rust",
+                "category": "books",
+                "year": 2021
+            })))
+            .unwrap();
+        assert_eq!(rendered["size"], json!(10));
+    }
+
+    #[test]
+    fn test_render_escapes_strings() {
+        let template = search_template(
+            json!({
+                "query": "title {{user_query}}",
+                "filter": "tag = {{tag}}",
+                "sort": "{{field}} desc"
+            }),
+            json!({"field": "year"}),
+        );
+        let rendered = template
+            .render(&params(json!({
+                "user_query": "foo OR /.*/ \"bar",
+                "tag": "rust; tag = secret"
+            })))
+            .unwrap();
+
+        // String parameters only ever add literal words
+        let query = Query::parse(rendered["query"].as_str().unwrap()).unwrap();
+        assert_eq!(
+            query.clauses,
+            ["title", "foo", "or", "bar"]
+                .iter()
+                .map(|term| Clause::Term(term.to_string()))
+                .collect::<Vec<_>>()
+        );
+        let filters = Filter::parse_all(rendered["filter"].as_str().unwrap()).unwrap();
+        assert_eq!(filters.len(), 1);
+        assert_eq!(filters[0].values, vec!["rust; tag = secret".to_string()]);
+        assert_eq!(rendered["sort"], json!("year desc"));
+
+        assert_eq!(
+            template.render(&params(json!({
+                "user_query": "rust",
+                "tag": "rust",
+                "field": "year desc, secret"
+            }))),
+            Err(TemplateError::InvalidParam("field".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_render_errors() {
+        let template = search_template(
+            json!({"query": "{{user_query}}", "filter": "tag = {{tag}}"}),
+            json!({}),
+        );
+        assert_eq!(
+            template.render(&params(json!({"tag": "rust"}))),
+            Err(TemplateError::MissingParam("user_query".to_string()))
+        );
+        assert_eq!(
+            template.render(&params(json!({"user_query": "rust", "tag": ["a", "b"]}))),
+            Err(TemplateError::InvalidParam("tag".to_string()))
+        );
+
+        assert_eq!(
+            search_template(json!(["{{query}}"]), json!({})).validate(),
+            Err(TemplateError::NotAnObject)
+        );
+        for source in ["{{user query}}", "{{query", "query}}", "{{}}"] {
+            assert_eq!(
+                search_template(json!({ "query": source }), json!({})).validate(),
+                Err(TemplateError::InvalidPlaceholder(source.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_store_templates() {
+        let mut search_engine = SearchEngine::new(1.5, 0.75);
+        let books = search_template(json!({"query": "{{q}}"}), json!({}));
+        assert!(search_engine
+            .put_search_template("books", books.clone())
+            .unwrap());
+        assert!(!search_engine
+            .put_search_template("books", books.clone())
+            .unwrap());
+        assert!(search_engine
+            .put_search_template("invalid", search_template(json!("{{q}}"), json!({})))
+            .is_err());
+        assert_eq!(search_engine.search_templates()["books"], books);
+        assert_eq!(search_engine.search_templates().len(), 1);
+
+        assert!(search_engine.delete_search_template("books"));
+        assert!(!search_engine.delete_search_template("books"));
+
+        remove_index_from_disk();
+    }
+}